@@ -0,0 +1,305 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64;
+
+use implement::bound_util::Dist;
+
+/// ball tree over points of a dimension chosen at runtime, each node bounds its points by a
+/// sphere about their mean
+pub struct BallTree<T>
+where
+    T: Default + Clone,
+{
+    _dim: usize,
+    _leaf_size: usize,
+    _coords: Vec<f64>, //point coordinates, dim values per point in node order
+    _objs: Vec<T>,
+    _nodes: Vec<NodeBallTree>,
+}
+
+///node of the tree covering a contiguous range of points
+pub struct NodeBallTree {
+    _centre: Vec<f64>,
+    _radius: f64,
+    _first: usize,
+    _count: usize,
+    _children: Option<(usize, usize)>,
+}
+
+///kernel profiles for density queries, evaluated at distance over bandwidth
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kernel {
+    Gaussian,
+    Epanechnikov,
+    Tophat,
+}
+
+impl Kernel {
+    pub fn eval(&self, u: f64) -> f64 {
+        match *self {
+            Kernel::Gaussian => (-0.5 * u * u).exp(),
+            Kernel::Epanechnikov => (1. - u * u).max(0.),
+            Kernel::Tophat => {
+                if u <= 1. {
+                    1.
+                } else {
+                    0.
+                }
+            }
+        }
+    }
+}
+
+fn dist(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .fold(0., |acc, (x, y)| acc + (x - y) * (x - y))
+        .sqrt()
+}
+
+impl<T> BallTree<T>
+where
+    T: Default + Clone,
+{
+    ///tree over points of dimension dim, with at most leaf_size points per leaf
+    pub fn init(dim: usize, leaf_size: usize) -> BallTree<T> {
+        assert!(dim > 0);
+        assert!(leaf_size > 0);
+        BallTree {
+            _dim: dim,
+            _leaf_size: leaf_size,
+            _coords: vec![],
+            _objs: vec![],
+            _nodes: vec![],
+        }
+    }
+    pub fn get_dim(&self) -> usize {
+        self._dim
+    }
+    ///number of points stored
+    pub fn len(&self) -> usize {
+        self._objs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._objs.is_empty()
+    }
+    pub fn node_count(&self) -> usize {
+        self._nodes.len()
+    }
+    fn point(&self, i: usize) -> &[f64] {
+        &self._coords[i * self._dim..(i + 1) * self._dim]
+    }
+    fn check_point(&self, p: &[f64]) -> Result<(), &'static str> {
+        if p.len() != self._dim {
+            return Err("point dimension mismatch");
+        }
+        if p.iter().any(|x| !x.is_finite()) {
+            return Err("ball tree requires finite points");
+        }
+        Ok(())
+    }
+    ///replaces content of the tree with the given points
+    pub fn build(&mut self, points: &[(T, Vec<f64>)]) -> Result<(), &'static str> {
+        for p in points.iter() {
+            self.check_point(&p.1[..])?;
+        }
+        let mut order = (0..points.len()).collect::<Vec<_>>();
+        self._nodes.clear();
+        if !points.is_empty() {
+            self.build_node(points, &mut order[..], 0);
+        }
+        self._coords = order
+            .iter()
+            .flat_map(|i| points[*i].1.iter().cloned())
+            .collect();
+        self._objs = order.iter().map(|i| points[*i].0.clone()).collect();
+        Ok(())
+    }
+    ///builds the node over the points referenced by order, which start at offset in node order,
+    ///splitting about the pair of points found by two farthest point searches
+    fn build_node(
+        &mut self,
+        points: &[(T, Vec<f64>)],
+        order: &mut [usize],
+        offset: usize,
+    ) -> usize {
+        let dim = self._dim;
+        let mut centre = vec![0.; dim];
+        for i in order.iter() {
+            for (c, x) in centre.iter_mut().zip(points[*i].1.iter()) {
+                *c += x;
+            }
+        }
+        for c in centre.iter_mut() {
+            *c /= order.len() as f64;
+        }
+        let radius = order
+            .iter()
+            .map(|i| dist(&centre[..], &points[*i].1[..]))
+            .fold(0., f64::max);
+        let idx = self._nodes.len();
+        self._nodes.push(NodeBallTree {
+            _centre: centre,
+            _radius: radius,
+            _first: offset,
+            _count: order.len(),
+            _children: None,
+        });
+        if order.len() <= self._leaf_size || radius == 0. {
+            return idx;
+        }
+
+        let farthest = |from: &[f64]| {
+            order
+                .iter()
+                .map(|i| (dist(from, &points[*i].1[..]), *i))
+                .fold((-1., 0), |acc, x| if x.0 > acc.0 { x } else { acc })
+                .1
+        };
+        let a = farthest(&points[order[0]].1[..]);
+        let b = farthest(&points[a].1[..]);
+        let pa = &points[a].1;
+        let dir = points[b]
+            .1
+            .iter()
+            .zip(pa.iter())
+            .map(|(x, y)| x - y)
+            .collect::<Vec<_>>();
+        let proj = |i: usize| {
+            points[i]
+                .1
+                .iter()
+                .zip(pa.iter())
+                .zip(dir.iter())
+                .fold(0., |acc, ((x, y), d)| acc + (x - y) * d)
+        };
+        let half = order.len() / 2;
+        order.select_nth_unstable_by(half, |x, y| {
+            proj(*x).partial_cmp(&proj(*y)).unwrap_or(Ordering::Equal)
+        });
+        let (l, r) = order.split_at_mut(half);
+        let left = self.build_node(points, l, offset);
+        let right = self.build_node(points, r, offset + half);
+        self._nodes[idx]._children = Some((left, right));
+        idx
+    }
+    ///lower bound of the distance from p to any point of the node
+    fn min_dist(&self, n: usize, p: &[f64]) -> f64 {
+        let node = &self._nodes[n];
+        (dist(&node._centre[..], p) - node._radius).max(0.)
+    }
+    ///k points closest to p with their distances in increasing order
+    pub fn query_nearest(&self, p: &[f64], k: usize) -> Result<Vec<(T, f64)>, &'static str> {
+        self.check_point(p)?;
+        let mut best: BinaryHeap<Dist> = BinaryHeap::new();
+        let mut q = BinaryHeap::new();
+        if k > 0 && !self._nodes.is_empty() {
+            q.push(::std::cmp::Reverse(Dist(self.min_dist(0, p), 0)));
+        }
+        while let Some(::std::cmp::Reverse(Dist(d, n))) = q.pop() {
+            if best.len() == k && d > best.peek().unwrap().0 {
+                break;
+            }
+            let node = &self._nodes[n];
+            match node._children {
+                Some((l, r)) => {
+                    q.push(::std::cmp::Reverse(Dist(self.min_dist(l, p), l)));
+                    q.push(::std::cmp::Reverse(Dist(self.min_dist(r, p), r)));
+                }
+                _ => {
+                    for i in node._first..node._first + node._count {
+                        let d = dist(self.point(i), p);
+                        if best.len() < k {
+                            best.push(Dist(d, i));
+                        } else if d < best.peek().unwrap().0 {
+                            best.pop();
+                            best.push(Dist(d, i));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(best
+            .into_sorted_vec()
+            .into_iter()
+            .map(|x| (self._objs[x.1].clone(), x.0))
+            .collect())
+    }
+    ///points within distance r of p with their distances in increasing order
+    pub fn query_radius(&self, p: &[f64], r: f64) -> Result<Vec<(T, f64)>, &'static str> {
+        self.check_point(p)?;
+        let mut out = vec![];
+        let mut q = vec![];
+        if !self._nodes.is_empty() {
+            q.push(0);
+        }
+        while let Some(n) = q.pop() {
+            if self.min_dist(n, p) > r {
+                continue;
+            }
+            let node = &self._nodes[n];
+            match node._children {
+                Some((a, b)) => {
+                    q.push(a);
+                    q.push(b);
+                }
+                _ => {
+                    for i in node._first..node._first + node._count {
+                        let d = dist(self.point(i), p);
+                        if d <= r {
+                            out.push(Dist(d, i));
+                        }
+                    }
+                }
+            }
+        }
+        out.sort();
+        Ok(out
+            .into_iter()
+            .map(|x| (self._objs[x.1].clone(), x.0))
+            .collect())
+    }
+    ///sum of kernel weights K(d / bandwidth) over all points, without normalisation
+    ///
+    ///nodes whose kernel weights vary by at most atol are summed from their bounds, giving an
+    ///error of at most atol / 2 per point, atol of 0 gives the exact sum
+    pub fn query_kernel_density(
+        &self,
+        p: &[f64],
+        kernel: Kernel,
+        bandwidth: f64,
+        atol: f64,
+    ) -> Result<f64, &'static str> {
+        self.check_point(p)?;
+        if bandwidth <= 0. || bandwidth.is_nan() {
+            return Err("bandwidth must be positive");
+        }
+        let mut sum = 0.;
+        let mut q = vec![];
+        if !self._nodes.is_empty() {
+            q.push(0);
+        }
+        while let Some(n) = q.pop() {
+            let node = &self._nodes[n];
+            let dc = dist(&node._centre[..], p);
+            let k_max = kernel.eval((dc - node._radius).max(0.) / bandwidth);
+            let k_min = kernel.eval((dc + node._radius) / bandwidth);
+            if k_max - k_min <= atol {
+                sum += node._count as f64 * (k_max + k_min) / 2.;
+                continue;
+            }
+            match node._children {
+                Some((a, b)) => {
+                    q.push(a);
+                    q.push(b);
+                }
+                _ => {
+                    for i in node._first..node._first + node._count {
+                        sum += kernel.eval(dist(self.point(i), p) / bandwidth);
+                    }
+                }
+            }
+        }
+        Ok(sum)
+    }
+}
@@ -0,0 +1,273 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+use self::mazth::ray::Ray3;
+
+use std::cmp::Ordering;
+use std::f64;
+
+use implement::bound_util::{bound_copy, ray_intersect_bound};
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// bounding interval hierarchy, inner nodes store only the two planes along one axis that bound
+/// the objects of their children, the rest of a child's box is inherited from its parent
+pub struct Bih<T>
+where
+    T: Default + Clone,
+{
+    _leaf_size: usize,
+    _bound: AxisAlignedBBox,
+    _nodes: Vec<NodeBih>,
+    _objs: Vec<(T, AxisAlignedBBox)>, //in leaf order
+}
+
+pub enum NodeBih {
+    ///axis, upper plane of the left child, lower plane of the right child and index of the left
+    ///child, the right child follows it
+    Inner(usize, f64, f64, usize),
+    ///first object and object count
+    Leaf(usize, usize),
+}
+
+///maximum number of times an empty side is cut away from a node box before making a leaf
+const MAX_EMPTY_CUTS: u32 = 64;
+
+impl<T> Bih<T>
+where
+    T: Default + Clone,
+{
+    ///hierarchy with at most leaf_size objects per leaf where a split is possible
+    pub fn init(leaf_size: usize) -> Bih<T> {
+        assert!(leaf_size > 0);
+        Bih {
+            _leaf_size: leaf_size,
+            _bound: AxisAlignedBBox {
+                _bound_lower: [0.; 3],
+                _bound_upper: [0.; 3],
+            },
+            _nodes: vec![],
+            _objs: vec![],
+        }
+    }
+    ///number of objects stored
+    pub fn len(&self) -> usize {
+        self._objs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._objs.is_empty()
+    }
+    pub fn node_count(&self) -> usize {
+        self._nodes.len()
+    }
+    pub fn get_bound(&self) -> &AxisAlignedBBox {
+        &self._bound
+    }
+    ///builds node n over objs, which start at first in leaf order, within the node box b,
+    ///splitting the box at the middle of its longest axis and cutting away empty halves
+    fn build_node(
+        &mut self,
+        objs: &mut [(T, AxisAlignedBBox)],
+        first: usize,
+        b: AxisAlignedBBox,
+        n: usize,
+    ) {
+        let mut b = b;
+        if objs.len() <= self._leaf_size {
+            self._nodes[n] = NodeBih::Leaf(first, objs.len());
+            return;
+        }
+        for _ in 0..MAX_EMPTY_CUTS {
+            let axis = (0..3)
+                .max_by(|x, y| {
+                    let ex = b._bound_upper[*x] - b._bound_lower[*x];
+                    let ey = b._bound_upper[*y] - b._bound_lower[*y];
+                    ex.partial_cmp(&ey).unwrap_or(Ordering::Equal)
+                })
+                .unwrap();
+            let split = (b._bound_lower[axis] + b._bound_upper[axis]) / 2.;
+            let centre = |o: &AxisAlignedBBox| (o._bound_lower[axis] + o._bound_upper[axis]) / 2.;
+            let mut mid = 0;
+            for i in 0..objs.len() {
+                if centre(&objs[i].1) < split {
+                    objs.swap(i, mid);
+                    mid += 1;
+                }
+            }
+            if mid == 0 {
+                b._bound_lower[axis] = split;
+                continue;
+            }
+            if mid == objs.len() {
+                b._bound_upper[axis] = split;
+                continue;
+            }
+            let (l, r) = objs.split_at_mut(mid);
+            let clip_l = l
+                .iter()
+                .map(|x| x.1._bound_upper[axis])
+                .fold(f64::NEG_INFINITY, f64::max);
+            let clip_r = r
+                .iter()
+                .map(|x| x.1._bound_lower[axis])
+                .fold(f64::INFINITY, f64::min);
+            let c = self._nodes.len();
+            self._nodes.push(NodeBih::Leaf(0, 0));
+            self._nodes.push(NodeBih::Leaf(0, 0));
+            self._nodes[n] = NodeBih::Inner(axis, clip_l, clip_r, c);
+            let mut bl = b.clone();
+            bl._bound_upper[axis] = split;
+            let mut br = b;
+            br._bound_lower[axis] = split;
+            self.build_node(l, first, bl, c);
+            self.build_node(r, first + mid, br, c + 1);
+            return;
+        }
+        //objects with coincident centres cannot be separated
+        self._nodes[n] = NodeBih::Leaf(first, objs.len());
+    }
+    ///visits leaves whose box, tightened by the clip planes above them, is accepted by pred
+    fn search<P, F>(&self, mut pred: P, mut f: F)
+    where
+        P: FnMut(&AxisAlignedBBox) -> bool,
+        F: FnMut(usize) -> bool,
+    {
+        if self._nodes.is_empty() {
+            return;
+        }
+        let mut q = vec![(0, self._bound.clone())];
+        while let Some((n, b)) = q.pop() {
+            if !pred(&b) {
+                continue;
+            }
+            match self._nodes[n] {
+                NodeBih::Inner(axis, clip_l, clip_r, c) => {
+                    let mut bl = b.clone();
+                    bl._bound_upper[axis] = bl._bound_upper[axis].min(clip_l);
+                    let mut br = b;
+                    br._bound_lower[axis] = br._bound_lower[axis].max(clip_r);
+                    if br._bound_lower[axis] <= br._bound_upper[axis] {
+                        q.push((c + 1, br));
+                    }
+                    if bl._bound_lower[axis] <= bl._bound_upper[axis] {
+                        q.push((c, bl));
+                    }
+                }
+                NodeBih::Leaf(first, count) => {
+                    for i in first..first + count {
+                        if f(i) {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        self.search(
+            |b| b.intersect(input),
+            |i| {
+                if self._objs[i].1.intersect(input) {
+                    out.push(self._objs[i].0.clone());
+                    return single;
+                }
+                false
+            },
+        );
+        Ok(out)
+    }
+    ///objects whose bounds are hit by the ray, ordered by increasing entry distance
+    pub fn query_ray(&self, r: &Ray3) -> Result<Vec<(T, f64)>, &'static str> {
+        let mut hits = vec![];
+        self.search(
+            |b| ray_intersect_bound(r, b).is_some(),
+            |i| {
+                if let Some(t) = ray_intersect_bound(r, &self._objs[i].1) {
+                    hits.push((t.0, i));
+                }
+                false
+            },
+        );
+        hits.sort_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .unwrap_or(Ordering::Equal)
+                .then(a.1.cmp(&b.1))
+        });
+        Ok(hits
+            .into_iter()
+            .map(|x| (self._objs[x.1].0.clone(), x.0))
+            .collect())
+    }
+    ///object whose bound is entered first by the ray, skipping subtrees entered after the best
+    ///hit found so far
+    pub fn query_ray_nearest(&self, r: &Ray3) -> Result<Option<(T, f64)>, &'static str> {
+        let mut best: Option<(f64, usize)> = None;
+        {
+            let best_t = ::std::cell::Cell::new(f64::INFINITY);
+            self.search(
+                |b| match ray_intersect_bound(r, b) {
+                    Some(t) => t.0 <= best_t.get(),
+                    _ => false,
+                },
+                |i| {
+                    if let Some(t) = ray_intersect_bound(r, &self._objs[i].1) {
+                        if t.0 < best_t.get() {
+                            best_t.set(t.0);
+                            best = Some((t.0, i));
+                        }
+                    }
+                    false
+                },
+            );
+        }
+        Ok(best.map(|x| (self._objs[x.1].0.clone(), x.0)))
+    }
+}
+
+impl<T> ISpatialAccel<T> for Bih<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        let mut v = vec![];
+        for i in objs {
+            match i.1.get_type() {
+                BoundType::AxisAlignBox => (),
+                _ => return Err("unsupported bound type"),
+            }
+            let b = bound_copy(i.1);
+            if (0..3).any(|k| !b._bound_lower[k].is_finite() || !b._bound_upper[k].is_finite()) {
+                return Err("bih requires finite bounds");
+            }
+            v.push((i.0.clone(), b));
+        }
+        self._nodes.clear();
+        self._objs.clear();
+        if v.is_empty() {
+            return Ok(());
+        }
+        let mut u = v[0].1.clone();
+        for o in v.iter() {
+            for k in 0..3 {
+                u._bound_lower[k] = u._bound_lower[k].min(o.1._bound_lower[k]);
+                u._bound_upper[k] = u._bound_upper[k].max(o.1._bound_upper[k]);
+            }
+        }
+        self._bound = u.clone();
+        self._nodes.push(NodeBih::Leaf(0, 0));
+        self.build_node(&mut v[..], 0, u, 0);
+        self._objs = v;
+        Ok(())
+    }
+}
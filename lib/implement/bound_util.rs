@@ -0,0 +1,88 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::IBound;
+use self::mazth::ray::Ray3;
+
+use std::cmp::Ordering;
+use std::f64;
+
+///axis aligned copy of the bound data
+pub(crate) fn bound_copy(b: &dyn IBound) -> AxisAlignedBBox {
+    let d = b.get_bound_data();
+    AxisAlignedBBox {
+        _bound_lower: [d[0], d[1], d[2]],
+        _bound_upper: [d[3], d[4], d[5]],
+    }
+}
+
+///smallest bound enclosing both bounds
+pub(crate) fn bound_union(a: &AxisAlignedBBox, b: &AxisAlignedBBox) -> AxisAlignedBBox {
+    let mut u = a.clone();
+    for i in 0..3 {
+        u._bound_lower[i] = a._bound_lower[i].min(b._bound_lower[i]);
+        u._bound_upper[i] = a._bound_upper[i].max(b._bound_upper[i]);
+    }
+    u
+}
+
+///slab test of ray against bound, returns entry and exit distance along the ray
+pub(crate) fn ray_intersect_bound(r: &Ray3, b: &AxisAlignedBBox) -> Option<(f64, f64)> {
+    slab_intersect_bound(
+        &[r._ori[0], r._ori[1], r._ori[2]],
+        &[r._dir[0], r._dir[1], r._dir[2]],
+        b,
+    )
+}
+
+///slab test of the ray with origin o and direction d against bound, returns entry and exit
+///distance along the ray
+pub(crate) fn slab_intersect_bound(
+    o: &[f64; 3],
+    d: &[f64; 3],
+    b: &AxisAlignedBBox,
+) -> Option<(f64, f64)> {
+    let mut t_min = 0f64;
+    let mut t_max = f64::INFINITY;
+    for i in 0..3 {
+        if d[i] == 0. {
+            if o[i] < b._bound_lower[i] || o[i] > b._bound_upper[i] {
+                return None;
+            }
+            continue;
+        }
+        let inv = 1. / d[i];
+        let mut t0 = (b._bound_lower[i] - o[i]) * inv;
+        let mut t1 = (b._bound_upper[i] - o[i]) * inv;
+        if t0 > t1 {
+            ::std::mem::swap(&mut t0, &mut t1);
+        }
+        t_min = t_min.max(t0);
+        t_max = t_max.min(t1);
+        if t_min > t_max {
+            return None;
+        }
+    }
+    Some((t_min, t_max))
+}
+
+///f64 key with total ordering for use in heaps
+#[derive(PartialEq)]
+pub(crate) struct Dist(pub f64, pub usize);
+
+impl Eq for Dist {}
+
+impl PartialOrd for Dist {
+    fn partial_cmp(&self, other: &Dist) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Dist {
+    fn cmp(&self, other: &Dist) -> Ordering {
+        self.0
+            .partial_cmp(&other.0)
+            .unwrap_or(Ordering::Equal)
+            .then(self.1.cmp(&other.1))
+    }
+}
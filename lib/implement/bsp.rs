@@ -0,0 +1,365 @@
+use std::f64;
+
+/// binary space partitioning tree over convex polygons, polygons crossing a splitting plane are
+/// cut so every fragment lies on exactly one side of each plane above it
+pub struct Bsp<T>
+where
+    T: Default + Clone,
+{
+    _select: PlaneSelect,
+    _eps: f64,
+    _nodes: Vec<NodeBsp>,
+    _polys: Vec<Polygon<T>>,
+    _splits: usize,
+}
+
+///polygon vertices with the payload of the source polygon
+type Polygon<T> = (T, Vec<[f64; 3]>);
+
+///strategy for choosing the splitting plane of a node among the planes of its polygons
+#[derive(Clone, Copy, Debug)]
+pub enum PlaneSelect {
+    ///plane of the first polygon
+    First,
+    ///plane minimising |front - back| + split_weight * splits over up to candidates polygons
+    Heuristic {
+        split_weight: f64,
+        candidates: usize,
+    },
+}
+
+///position of a point relative to the solid bounded by the polygons, with normals facing out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Classification {
+    Inside,
+    Outside,
+    Boundary,
+}
+
+///plane with normal n and offset d, containing points x where n.x = d
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub _normal: [f64; 3],
+    pub _d: f64,
+}
+
+///node of the tree holding the polygons lying in its plane
+pub struct NodeBsp {
+    _plane: Plane,
+    _polys: Vec<usize>,
+    _front: Option<usize>,
+    _back: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Front,
+    Back,
+    On,
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+impl Plane {
+    ///plane of a polygon using Newell's method, None for degenerate polygons
+    pub fn from_polygon(verts: &[[f64; 3]]) -> Option<Plane> {
+        let mut n = [0.; 3];
+        let mut c = [0.; 3];
+        for (i, a) in verts.iter().enumerate() {
+            let b = &verts[(i + 1) % verts.len()];
+            n[0] += (a[1] - b[1]) * (a[2] + b[2]);
+            n[1] += (a[2] - b[2]) * (a[0] + b[0]);
+            n[2] += (a[0] - b[0]) * (a[1] + b[1]);
+            for k in 0..3 {
+                c[k] += a[k] / verts.len() as f64;
+            }
+        }
+        let l = dot(&n, &n).sqrt();
+        if l <= 0. || !l.is_finite() {
+            return None;
+        }
+        let n = [n[0] / l, n[1] / l, n[2] / l];
+        Some(Plane {
+            _normal: n,
+            _d: dot(&n, &c),
+        })
+    }
+    ///signed distance of a point, positive in front
+    pub fn distance(&self, p: &[f64; 3]) -> f64 {
+        dot(&self._normal, p) - self._d
+    }
+}
+
+impl<T> Bsp<T>
+where
+    T: Default + Clone,
+{
+    ///eps is the positive distance within which points are considered to lie on a plane, so a
+    ///polygon always lies on the plane taken from it despite rounding
+    pub fn init(select: PlaneSelect, eps: f64) -> Bsp<T> {
+        assert!(eps > 0.);
+        if let PlaneSelect::Heuristic { candidates, .. } = select {
+            assert!(candidates > 0);
+        }
+        Bsp {
+            _select: select,
+            _eps: eps,
+            _nodes: vec![],
+            _polys: vec![],
+            _splits: 0,
+        }
+    }
+    ///number of polygon fragments stored after splitting
+    pub fn len(&self) -> usize {
+        self._polys.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._polys.is_empty()
+    }
+    pub fn node_count(&self) -> usize {
+        self._nodes.len()
+    }
+    ///number of polygon cuts made while building
+    pub fn split_count(&self) -> usize {
+        self._splits
+    }
+    ///polygon fragments with their source payload
+    pub fn get_polygons(&self) -> &[Polygon<T>] {
+        &self._polys[..]
+    }
+    fn side(&self, plane: &Plane, p: &[f64; 3]) -> Side {
+        let d = plane.distance(p);
+        if d > self._eps {
+            Side::Front
+        } else if d < -self._eps {
+            Side::Back
+        } else {
+            Side::On
+        }
+    }
+    ///number of polygons in front, behind and spanning the plane
+    fn count_sides(&self, plane: &Plane, polys: &[Polygon<T>]) -> (usize, usize, usize) {
+        let mut c = (0, 0, 0);
+        for p in polys.iter() {
+            let mut front = false;
+            let mut back = false;
+            for v in p.1.iter() {
+                match self.side(plane, v) {
+                    Side::Front => front = true,
+                    Side::Back => back = true,
+                    Side::On => (),
+                }
+            }
+            match (front, back) {
+                (true, true) => c.2 += 1,
+                (true, false) => c.0 += 1,
+                (false, true) => c.1 += 1,
+                _ => (),
+            }
+        }
+        c
+    }
+    fn choose_plane(&self, polys: &[Polygon<T>]) -> Plane {
+        match self._select {
+            PlaneSelect::First => Plane::from_polygon(&polys[0].1[..]).unwrap(),
+            PlaneSelect::Heuristic {
+                split_weight,
+                candidates,
+            } => {
+                let step = (polys.len() / candidates).max(1);
+                let mut best = (f64::INFINITY, Plane::from_polygon(&polys[0].1[..]).unwrap());
+                for p in polys.iter().step_by(step).take(candidates) {
+                    let plane = Plane::from_polygon(&p.1[..]).unwrap();
+                    let (f, b, s) = self.count_sides(&plane, polys);
+                    let score = (f as f64 - b as f64).abs() + split_weight * s as f64;
+                    if score < best.0 {
+                        best = (score, plane);
+                    }
+                }
+                best.1
+            }
+        }
+    }
+    ///cuts a convex polygon by the plane into its front and back parts
+    fn split(&self, plane: &Plane, verts: &[[f64; 3]]) -> (Vec<[f64; 3]>, Vec<[f64; 3]>) {
+        let mut front = vec![];
+        let mut back = vec![];
+        for (i, a) in verts.iter().enumerate() {
+            let b = &verts[(i + 1) % verts.len()];
+            let sa = self.side(plane, a);
+            let sb = self.side(plane, b);
+            match sa {
+                Side::Front => front.push(*a),
+                Side::Back => back.push(*a),
+                Side::On => {
+                    front.push(*a);
+                    back.push(*a);
+                }
+            }
+            if (sa == Side::Front && sb == Side::Back) || (sa == Side::Back && sb == Side::Front) {
+                let da = plane.distance(a);
+                let db = plane.distance(b);
+                let t = da / (da - db);
+                let p = [
+                    a[0] + (b[0] - a[0]) * t,
+                    a[1] + (b[1] - a[1]) * t,
+                    a[2] + (b[2] - a[2]) * t,
+                ];
+                front.push(p);
+                back.push(p);
+            }
+        }
+        (front, back)
+    }
+    ///replaces content of the tree with the given convex polygons, each with at least 3 vertices
+    pub fn build(&mut self, polys: &[Polygon<T>]) -> Result<(), &'static str> {
+        for p in polys.iter() {
+            if p.1.len() < 3 {
+                return Err("polygon requires at least 3 vertices");
+            }
+            if Plane::from_polygon(&p.1[..]).is_none() {
+                return Err("degenerate polygon");
+            }
+        }
+        self._nodes.clear();
+        self._polys.clear();
+        self._splits = 0;
+        if polys.is_empty() {
+            return Ok(());
+        }
+
+        //pending lists with the link to set in their parent
+        let root: Option<(usize, bool)> = None;
+        let mut q = vec![(root, polys.to_vec())];
+        while let Some((link, list)) = q.pop() {
+            let plane = self.choose_plane(&list[..]);
+            let idx = self._nodes.len();
+            match link {
+                Some((parent, true)) => self._nodes[parent]._front = Some(idx),
+                Some((parent, false)) => self._nodes[parent]._back = Some(idx),
+                _ => (),
+            }
+            let mut node = NodeBsp {
+                _plane: plane,
+                _polys: vec![],
+                _front: None,
+                _back: None,
+            };
+            let mut front = vec![];
+            let mut back = vec![];
+            for (obj, verts) in list {
+                let sides = verts
+                    .iter()
+                    .map(|v| self.side(&plane, v))
+                    .collect::<Vec<_>>();
+                let f = sides.contains(&Side::Front);
+                let b = sides.contains(&Side::Back);
+                match (f, b) {
+                    (false, false) => {
+                        node._polys.push(self._polys.len());
+                        self._polys.push((obj, verts));
+                    }
+                    (true, false) => front.push((obj, verts)),
+                    (false, true) => back.push((obj, verts)),
+                    _ => {
+                        let (vf, vb) = self.split(&plane, &verts[..]);
+                        self._splits += 1;
+                        //drop slivers that collapse within tolerance
+                        if Plane::from_polygon(&vf[..]).is_some() {
+                            front.push((obj.clone(), vf));
+                        }
+                        if Plane::from_polygon(&vb[..]).is_some() {
+                            back.push((obj, vb));
+                        }
+                    }
+                }
+            }
+            self._nodes.push(node);
+            if !front.is_empty() {
+                q.push((Some((idx, true)), front));
+            }
+            if !back.is_empty() {
+                q.push((Some((idx, false)), back));
+            }
+        }
+        Ok(())
+    }
+    fn traverse<F>(&self, n: usize, eye: &[f64; 3], back_to_front: bool, f: &mut F)
+    where
+        F: FnMut(&T, &[[f64; 3]]),
+    {
+        let node = &self._nodes[n];
+        let in_front = node._plane.distance(eye) >= 0.;
+        //far side is the one not containing the eye
+        let (near, far) = if in_front {
+            (node._front, node._back)
+        } else {
+            (node._back, node._front)
+        };
+        let (first, last) = if back_to_front {
+            (far, near)
+        } else {
+            (near, far)
+        };
+        if let Some(c) = first {
+            self.traverse(c, eye, back_to_front, f);
+        }
+        for p in node._polys.iter() {
+            f(&self._polys[*p].0, &self._polys[*p].1[..]);
+        }
+        if let Some(c) = last {
+            self.traverse(c, eye, back_to_front, f);
+        }
+    }
+    ///visits polygons ordered from farthest to nearest as seen from eye, as needed by the
+    ///painter's algorithm
+    pub fn traverse_back_to_front<F>(&self, eye: &[f64; 3], mut f: F)
+    where
+        F: FnMut(&T, &[[f64; 3]]),
+    {
+        if !self._nodes.is_empty() {
+            self.traverse(0, eye, true, &mut f);
+        }
+    }
+    ///visits polygons ordered from nearest to farthest as seen from eye
+    pub fn traverse_front_to_back<F>(&self, eye: &[f64; 3], mut f: F)
+    where
+        F: FnMut(&T, &[[f64; 3]]),
+    {
+        if !self._nodes.is_empty() {
+            self.traverse(0, eye, false, &mut f);
+        }
+    }
+    fn classify_node(&self, n: Option<usize>, p: &[f64; 3], behind: bool) -> Classification {
+        let n = match n {
+            Some(n) => n,
+            //empty leaves in front of their parent plane are outside, behind are inside
+            _ if behind => return Classification::Inside,
+            _ => return Classification::Outside,
+        };
+        let node = &self._nodes[n];
+        match self.side(&node._plane, p) {
+            Side::Front => self.classify_node(node._front, p, false),
+            Side::Back => self.classify_node(node._back, p, true),
+            Side::On => {
+                let a = self.classify_node(node._front, p, false);
+                let b = self.classify_node(node._back, p, true);
+                if a == b {
+                    a
+                } else {
+                    Classification::Boundary
+                }
+            }
+        }
+    }
+    ///inside/outside test treating the polygons as the closed boundary of a solid with
+    ///outward facing normals
+    pub fn classify_point(&self, p: &[f64; 3]) -> Classification {
+        if self._nodes.is_empty() {
+            return Classification::Outside;
+        }
+        self.classify_node(Some(0), p, false)
+    }
+}
@@ -8,6 +8,7 @@ use std::boxed::Box;
 use std::f64;
 
 use interface::i_spatial_accel::ISpatialAccel;
+use interface::i_spatial_accel_mask::ISpatialAccelMask;
 use interface::i_stat_tree::IStatTree;
 
 /// implementation of spatial acceleration using bounding volume hierarchy with surface area heuristic
@@ -27,7 +28,8 @@ where
     _bound: AxisAlignedBBox,
     _left: BvhBranch<T>,
     _right: BvhBranch<T>,
-    _obj: T, //leaf data
+    _obj: T,    //leaf data
+    _mask: u64, //union of object category masks in subtree
 }
 
 pub enum BvhBranch<T>
@@ -38,6 +40,18 @@ where
     EMPTY,
 }
 
+impl<T> BvhBranch<T>
+where
+    T: Default + Clone,
+{
+    pub fn get_mask(&self) -> u64 {
+        match *self {
+            BvhBranch::CHILD(ref o) => o._mask,
+            _ => 0,
+        }
+    }
+}
+
 impl<T> Default for NodeBvh<T>
 where
     T: Default + Clone,
@@ -51,6 +65,7 @@ where
             _left: BvhBranch::EMPTY,
             _right: BvhBranch::EMPTY,
             _obj: T::default(),
+            _mask: 0,
         }
     }
 }
@@ -60,25 +75,44 @@ where
     T: Default + Clone,
 {
     pub fn init_branches(b: AxisAlignedBBox, l: BvhBranch<T>, r: BvhBranch<T>) -> NodeBvh<T> {
+        let mask = l.get_mask() | r.get_mask();
         NodeBvh {
             _bound: b,
             _left: l,
             _right: r,
             _obj: T::default(),
+            _mask: mask,
         }
     }
     pub fn init_leaf(b: AxisAlignedBBox, o: T) -> NodeBvh<T> {
+        NodeBvh::init_leaf_mask(b, o, !0)
+    }
+    pub fn init_leaf_mask(b: AxisAlignedBBox, o: T, mask: u64) -> NodeBvh<T> {
         NodeBvh {
             _bound: b,
             _left: BvhBranch::EMPTY,
             _right: BvhBranch::EMPTY,
             _obj: o,
+            _mask: mask,
         }
     }
+    pub fn get_bound(&self) -> &AxisAlignedBBox {
+        &self._bound
+    }
+    pub fn get_left(&self) -> &BvhBranch<T> {
+        &self._left
+    }
+    pub fn get_right(&self) -> &BvhBranch<T> {
+        &self._right
+    }
+    ///leaf data, only meaningful when both branches are empty
+    pub fn get_obj(&self) -> &T {
+        &self._obj
+    }
     pub fn build_node(
         &mut self,
         num_bins: u32,
-        objs: &[(T, &dyn IBound)],
+        objs: &[(T, &dyn IBound, u64)],
     ) -> Result<(), &'static str> {
         for i in objs {
             match i.1.get_type() {
@@ -86,11 +120,7 @@ where
                 _ => return Err("unsupported bound type"),
             }
         }
-        let b = objs
-            .iter()
-            .cloned()
-            .map(|x| x.1)
-            .collect::<Vec<&dyn IBound>>();
+        let b = objs.iter().map(|x| x.1).collect::<Vec<&dyn IBound>>();
         let mut u: AxisAlignedBBox = Default::default();
         u.get_union(&b[..]);
 
@@ -100,6 +130,7 @@ where
         if objs.len() == 1 {
             self._bound = u;
             self._obj = objs[0].0.clone();
+            self._mask = objs[0].2;
             return Ok(());
         }
 
@@ -206,8 +237,8 @@ where
         }
 
         // println!( "bins surf area: {:?}", bins_surf_area );
-        let mut bin_left: Vec<(T, &dyn IBound)> = vec![];
-        let mut bin_right: Vec<(T, &dyn IBound)> = vec![];
+        let mut bin_left: Vec<(T, &dyn IBound, u64)> = vec![];
+        let mut bin_right: Vec<(T, &dyn IBound, u64)> = vec![];
 
         {
             let _ = bins_surf_area.iter_mut().fold(0, |acc, x| {
@@ -234,20 +265,18 @@ where
                 }
             }
 
-            let mut idx_obj = 0;
-            for i in obj_bin {
+            for (idx_obj, i) in obj_bin.into_iter().enumerate() {
                 let obj = objs[idx_obj].clone();
                 if i <= split_bin_idx {
                     bin_left.push(obj);
                 } else {
                     bin_right.push(obj);
                 }
-                idx_obj += 1;
             }
         }
 
-        if (bin_left.len() >= 2 && bin_right.len() == 0)
-            || (bin_left.len() == 0 && bin_right.len() >= 2)
+        if (bin_left.len() >= 2 && bin_right.is_empty())
+            || (bin_left.is_empty() && bin_right.len() >= 2)
         {
             bin_left.clear();
             bin_right.clear();
@@ -265,7 +294,7 @@ where
 
         // debug!( "split bin left count: {}, bin right count: {}", bin_left.len(), bin_right.len() );
 
-        if bin_left.len() > 0 {
+        if !bin_left.is_empty() {
             let mut l: NodeBvh<T> = Default::default();
             // println!("num left children: {}", bin_left.len() );
             l.build_node(num_bins, &bin_left[..])?;
@@ -274,7 +303,7 @@ where
             self._left = BvhBranch::EMPTY;
         }
 
-        if bin_right.len() > 0 {
+        if !bin_right.is_empty() {
             let mut r: NodeBvh<T> = Default::default();
             // println!("num right children: {}", bin_right.len() );
             r.build_node(num_bins, &bin_right[..])?;
@@ -283,16 +312,24 @@ where
             self._right = BvhBranch::EMPTY;
         }
 
+        self._mask = self._left.get_mask() | self._right.get_mask();
+
         Ok(())
     }
-    pub fn search<F>(n: &NodeBvh<T>, b: &dyn IBound, mut f: F)
+    pub fn search<F>(n: &NodeBvh<T>, b: &dyn IBound, f: F)
+    where
+        F: FnMut(T) -> bool,
+    {
+        NodeBvh::search_mask(n, b, !0, f)
+    }
+    ///skips subtrees whose union of category masks shares no bit with the filter mask
+    pub fn search_mask<F>(n: &NodeBvh<T>, b: &dyn IBound, mask: u64, mut f: F)
     where
         F: FnMut(T) -> bool,
     {
         let mut q = vec![n];
-        while q.len() > 0 {
-            let l = q.pop().unwrap();
-            if l._bound.intersect(b) {
+        while let Some(l) = q.pop() {
+            if l._mask & mask != 0 && l._bound.intersect(b) {
                 let mut present_l = true;
                 let mut present_r = true;
                 match l._left {
@@ -309,10 +346,8 @@ where
                     }
                     _ => present_r = false,
                 }
-                if !present_l && !present_r {
-                    if f(l._obj.clone()) {
-                        return;
-                    }
+                if !present_l && !present_r && f(l._obj.clone()) {
+                    return;
                 }
             }
         }
@@ -355,6 +390,56 @@ where
     }
     fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
         //initiate top down construction
+        if self._bins == 0 {
+            return Err("bvh bin count cannot be zero");
+        }
+        let objs_mask = objs
+            .iter()
+            .map(|x| (x.0.clone(), x.1, !0))
+            .collect::<Vec<_>>();
+        self._root.build_node(self._bins, &objs_mask[..])
+    }
+}
+
+impl<T> ISpatialAccelMask<T> for Bvh<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect_mask(&self, input: &dyn IBound, mask: u64) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        {
+            let func_collect = |x| {
+                out.push(x);
+                false
+            };
+            NodeBvh::search_mask(&self._root, input, mask, func_collect);
+        }
+        Ok(out)
+    }
+    fn query_intersect_single_mask(
+        &self,
+        input: &dyn IBound,
+        mask: u64,
+    ) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        {
+            let func_collect = |x| {
+                out.push(x);
+                true
+            };
+            NodeBvh::search_mask(&self._root, input, mask, func_collect);
+        }
+        Ok(out)
+    }
+    fn build_all_mask(&mut self, objs: &[(T, &dyn IBound, u64)]) -> Result<(), &'static str> {
         if self._bins == 0 {
             return Err("bvh bin count cannot be zero");
         }
@@ -377,10 +462,14 @@ where
                 _left: BvhBranch::EMPTY,
                 _right: BvhBranch::EMPTY,
                 _obj: Default::default(),
+                _mask: 0,
             },
             _bins: bins,
         }
     }
+    pub fn get_root(&self) -> &NodeBvh<T> {
+        &self._root
+    }
 }
 
 impl<T> IStatTree for Bvh<T>
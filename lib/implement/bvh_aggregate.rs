@@ -0,0 +1,396 @@
+extern crate mazth;
+
+use self::mazth::bound::{Axis, AxisAlignedBBox};
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::boxed::Box;
+use std::f64;
+
+use implement::bound_util::bound_copy;
+use interface::i_monoid::IMonoid;
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// bounding volume hierarchy using median split with a user-defined aggregate maintained per subtree
+pub struct Bvh<T, A>
+where
+    T: Default + Clone,
+    A: IMonoid + Clone,
+{
+    _root: NodeBvh<T, A>,
+}
+
+///internal node structure for Bvh
+pub struct NodeBvh<T, A>
+where
+    T: Default + Clone,
+    A: IMonoid + Clone,
+{
+    _bound: AxisAlignedBBox,
+    _left: BvhBranch<T, A>,
+    _right: BvhBranch<T, A>,
+    _obj: T,  //leaf data
+    _aggr: A, //combined aggregate of objects in subtree
+}
+
+pub enum BvhBranch<T, A>
+where
+    T: Default + Clone,
+    A: IMonoid + Clone,
+{
+    CHILD(Box<NodeBvh<T, A>>),
+    EMPTY,
+}
+
+///bound that intersects nothing, used for nodes without objects
+fn bound_empty() -> AxisAlignedBBox {
+    AxisAlignedBBox {
+        _bound_lower: [f64::INFINITY; 3],
+        _bound_upper: [f64::NEG_INFINITY; 3],
+    }
+}
+
+///checks whether bound b lies entirely within bound a
+fn bound_contains(a: &dyn IBound, b: &AxisAlignedBBox) -> bool {
+    let a_bounds = a.get_bound_data();
+    (0..3).all(|i| a_bounds[i] <= b._bound_lower[i] && b._bound_upper[i] <= a_bounds[i + 3])
+}
+
+impl<T, A> Default for NodeBvh<T, A>
+where
+    T: Default + Clone,
+    A: IMonoid + Clone,
+{
+    fn default() -> NodeBvh<T, A> {
+        NodeBvh {
+            _bound: bound_empty(),
+            _left: BvhBranch::EMPTY,
+            _right: BvhBranch::EMPTY,
+            _obj: T::default(),
+            _aggr: A::identity(),
+        }
+    }
+}
+
+impl<T, A> NodeBvh<T, A>
+where
+    T: Default + Clone,
+    A: IMonoid + Clone,
+{
+    pub fn is_leaf(&self) -> bool {
+        matches!(
+            (&self._left, &self._right),
+            (&BvhBranch::EMPTY, &BvhBranch::EMPTY)
+        )
+    }
+    pub fn get_aggregate(&self) -> &A {
+        &self._aggr
+    }
+    pub fn build_node(&mut self, objs: &[(T, &dyn IBound, A)]) -> Result<(), &'static str> {
+        for i in objs {
+            match i.1.get_type() {
+                BoundType::AxisAlignBox => (),
+                _ => return Err("unsupported bound type"),
+            }
+        }
+
+        if objs.is_empty() {
+            *self = Default::default();
+            return Ok(());
+        }
+
+        let b = objs.iter().map(|x| x.1).collect::<Vec<&dyn IBound>>();
+
+        let mut u: AxisAlignedBBox = Default::default();
+        u.get_union(&b[..]);
+
+        //check for leaf condition
+        if objs.len() == 1 {
+            self._bound = u;
+            self._obj = objs[0].0.clone();
+            self._aggr = objs[0].2.clone();
+            self._left = BvhBranch::EMPTY;
+            self._right = BvhBranch::EMPTY;
+            return Ok(());
+        }
+
+        //split at median of centroids along the longest axis of the bounding box
+        let (ref axis, ref _length) = u.get_longest_axis();
+
+        self._bound = u;
+
+        let mut centroids = vec![];
+
+        for (idx, i) in objs.iter().enumerate() {
+            let c = i.1.get_centroid();
+            match *axis {
+                Axis::X => {
+                    centroids.push((c[0], idx));
+                }
+                Axis::Y => {
+                    centroids.push((c[1], idx));
+                }
+                Axis::Z => {
+                    centroids.push((c[2], idx));
+                }
+            }
+        }
+
+        centroids.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let half = centroids.len() / 2;
+
+        let bin_left = centroids[0..half]
+            .iter()
+            .map(|x| objs[x.1].clone())
+            .collect::<Vec<_>>();
+        let bin_right = centroids[half..]
+            .iter()
+            .map(|x| objs[x.1].clone())
+            .collect::<Vec<_>>();
+
+        let mut l: NodeBvh<T, A> = Default::default();
+        l.build_node(&bin_left[..])?;
+        self._left = BvhBranch::CHILD(Box::new(l));
+
+        let mut r: NodeBvh<T, A> = Default::default();
+        r.build_node(&bin_right[..])?;
+        self._right = BvhBranch::CHILD(Box::new(r));
+
+        self.refit_local();
+
+        Ok(())
+    }
+    ///recompute bound and aggregate of an internal node from its immediate children
+    fn refit_local(&mut self) {
+        if self.is_leaf() {
+            return;
+        }
+        let mut bound = bound_empty();
+        let mut aggr = A::identity();
+        for branch in [&self._left, &self._right].iter() {
+            if let BvhBranch::CHILD(ref o) = **branch {
+                for i in 0..3 {
+                    bound._bound_lower[i] = bound._bound_lower[i].min(o._bound._bound_lower[i]);
+                    bound._bound_upper[i] = bound._bound_upper[i].max(o._bound._bound_upper[i]);
+                }
+                aggr = aggr.combine(&o._aggr);
+            }
+        }
+        self._bound = bound;
+        self._aggr = aggr;
+    }
+    ///recompute bounds and aggregates of all internal nodes in the subtree from the leaves up
+    pub fn refit(&mut self) {
+        if let BvhBranch::CHILD(ref mut o) = self._left {
+            o.refit();
+        }
+        if let BvhBranch::CHILD(ref mut o) = self._right {
+            o.refit();
+        }
+        self.refit_local();
+    }
+    pub fn search<F>(n: &NodeBvh<T, A>, b: &dyn IBound, mut f: F)
+    where
+        F: FnMut(T) -> bool,
+    {
+        let mut q = vec![n];
+        while let Some(l) = q.pop() {
+            if l._bound.intersect(b) {
+                if l.is_leaf() {
+                    if f(l._obj.clone()) {
+                        return;
+                    }
+                    continue;
+                }
+                if let BvhBranch::CHILD(ref o) = l._left {
+                    q.push(o);
+                }
+                if let BvhBranch::CHILD(ref o) = l._right {
+                    q.push(o);
+                }
+            }
+        }
+    }
+    ///combine aggregates of objects intersecting with b, taking whole subtree values for nodes inside b
+    pub fn search_aggregate(n: &NodeBvh<T, A>, b: &dyn IBound) -> A {
+        let mut aggr = A::identity();
+        let mut q = vec![n];
+        while let Some(l) = q.pop() {
+            if !l._bound.intersect(b) {
+                continue;
+            }
+            if l.is_leaf() || bound_contains(b, &l._bound) {
+                aggr = aggr.combine(&l._aggr);
+                continue;
+            }
+            if let BvhBranch::CHILD(ref o) = l._left {
+                q.push(o);
+            }
+            if let BvhBranch::CHILD(ref o) = l._right {
+                q.push(o);
+            }
+        }
+        aggr
+    }
+}
+
+impl<T, A> NodeBvh<T, A>
+where
+    T: Default + Clone + PartialEq,
+    A: IMonoid + Clone,
+{
+    ///replace bound and aggregate of the leaf holding obj, refitting ancestors on the way back up
+    fn has_leaf(&self, obj: &T) -> bool {
+        if self.is_leaf() {
+            return self._obj == *obj && self._bound._bound_lower[0] <= self._bound._bound_upper[0];
+        }
+        let left = match self._left {
+            BvhBranch::CHILD(ref o) => o.has_leaf(obj),
+            _ => false,
+        };
+        left || match self._right {
+            BvhBranch::CHILD(ref o) => o.has_leaf(obj),
+            _ => false,
+        }
+    }
+    fn update_leaf(&mut self, obj: &T, b: &AxisAlignedBBox, aggr: &A, refit: bool) -> bool {
+        if self.is_leaf() {
+            if self._obj == *obj && self._bound._bound_lower[0] <= self._bound._bound_upper[0] {
+                self._bound = b.clone();
+                self._aggr = aggr.clone();
+                return true;
+            }
+            return false;
+        }
+        let mut found = false;
+        if let BvhBranch::CHILD(ref mut o) = self._left {
+            found = o.update_leaf(obj, b, aggr, refit);
+        }
+        if !found {
+            if let BvhBranch::CHILD(ref mut o) = self._right {
+                found = o.update_leaf(obj, b, aggr, refit);
+            }
+        }
+        if found && refit {
+            self.refit_local();
+        }
+        found
+    }
+}
+
+impl<T, A> ISpatialAccel<T> for Bvh<T, A>
+where
+    T: Default + Clone,
+    A: IMonoid + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        {
+            let func_collect = |x| {
+                out.push(x);
+                false
+            };
+            NodeBvh::search(&self._root, input, func_collect);
+        }
+        Ok(out)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        {
+            let func_collect = |x| {
+                out.push(x);
+                true
+            };
+            NodeBvh::search(&self._root, input, func_collect);
+        }
+        Ok(out)
+    }
+    ///builds with identity aggregates; use build_all_aggregate to supply per object values
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        let objs_aggr = objs
+            .iter()
+            .map(|x| (x.0.clone(), x.1, A::identity()))
+            .collect::<Vec<_>>();
+        self._root.build_node(&objs_aggr[..])
+    }
+}
+
+impl<T, A> Bvh<T, A>
+where
+    T: Default + Clone,
+    A: IMonoid + Clone,
+{
+    pub fn init() -> Bvh<T, A> {
+        Bvh {
+            _root: Default::default(),
+        }
+    }
+    ///build with input bounds, object ids and per object aggregate values
+    pub fn build_all_aggregate(
+        &mut self,
+        objs: &[(T, &dyn IBound, A)],
+    ) -> Result<(), &'static str> {
+        self._root.build_node(objs)
+    }
+    ///combined aggregate of all objects in the tree
+    pub fn get_aggregate(&self) -> &A {
+        self._root.get_aggregate()
+    }
+    ///combined aggregate of objects intersecting with input
+    pub fn query_aggregate(&self, input: &dyn IBound) -> Result<A, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        Ok(NodeBvh::search_aggregate(&self._root, input))
+    }
+    ///recompute all internal bounds and aggregates from the leaves
+    pub fn refit(&mut self) {
+        self._root.refit();
+    }
+}
+
+impl<T, A> Bvh<T, A>
+where
+    T: Default + Clone + PartialEq,
+    A: IMonoid + Clone,
+{
+    ///change bound and aggregate of an existing object and refit its ancestors
+    pub fn update(&mut self, obj: &T, bound: &dyn IBound, aggr: A) -> Result<(), &'static str> {
+        match bound.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        if self._root.update_leaf(obj, &bound_copy(bound), &aggr, true) {
+            Ok(())
+        } else {
+            Err("object not found")
+        }
+    }
+    ///change bounds and aggregates of several existing objects followed by a single refit, the
+    ///tree is left unchanged when any entry is invalid
+    pub fn update_batch(&mut self, objs: &[(T, &dyn IBound, A)]) -> Result<(), &'static str> {
+        for i in objs {
+            match i.1.get_type() {
+                BoundType::AxisAlignBox => (),
+                _ => return Err("unsupported bound type"),
+            }
+            if !self._root.has_leaf(&i.0) {
+                return Err("object not found");
+            }
+        }
+        for i in objs {
+            self._root.update_leaf(&i.0, &bound_copy(i.1), &i.2, false);
+        }
+        self._root.refit();
+        Ok(())
+    }
+}
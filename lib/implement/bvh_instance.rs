@@ -0,0 +1,254 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+use self::mazth::mat::Mat4;
+use self::mazth::ray::Ray3;
+
+use std::f64;
+use std::rc::Rc;
+
+use implement::bound_util::{bound_copy, slab_intersect_bound};
+use implement::bvh::{Bvh, BvhBranch, NodeBvh};
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// two-level bounding volume hierarchy, the top level is built over instances that each place a
+/// shared bottom-level Bvh in the world with an affine transform
+pub struct BvhInstance<T>
+where
+    T: Default + Clone,
+{
+    _top: Bvh<usize>,
+    _instances: Vec<Instance<T>>,
+}
+
+struct Instance<T>
+where
+    T: Default + Clone,
+{
+    _blas: Rc<Bvh<T>>,
+    _xform: Mat4<f64>,
+    _inv: Mat4<f64>,
+    _bound: AxisAlignedBBox, //world bound of the transformed root bound of the bottom level
+}
+
+///bound of a box after an affine transform (Arvo, 1990)
+fn bound_transform(m: &Mat4<f64>, b: &AxisAlignedBBox) -> AxisAlignedBBox {
+    let mut out = b.clone();
+    for i in 0..3 {
+        let t = m.index(i as u64, 3);
+        out._bound_lower[i] = t;
+        out._bound_upper[i] = t;
+        for j in 0..3 {
+            let e = m.index(i as u64, j as u64);
+            let a = e * b._bound_lower[j];
+            let c = e * b._bound_upper[j];
+            out._bound_lower[i] += a.min(c);
+            out._bound_upper[i] += a.max(c);
+        }
+    }
+    out
+}
+
+///inverse of an affine transform from the adjugate of its linear part
+fn affine_inverse(m: &Mat4<f64>) -> Option<Mat4<f64>> {
+    let a = |i: usize, j: usize| m.index(i as u64, j as u64);
+    let c = |i: usize, j: usize| {
+        let (i0, i1) = ((i + 1) % 3, (i + 2) % 3);
+        let (j0, j1) = ((j + 1) % 3, (j + 2) % 3);
+        a(i0, j0) * a(i1, j1) - a(i0, j1) * a(i1, j0)
+    };
+    let det = a(0, 0) * c(0, 0) + a(0, 1) * c(0, 1) + a(0, 2) * c(0, 2);
+    if det == 0. || !det.is_finite() {
+        return None;
+    }
+    let mut v = [0.; 16];
+    for i in 0..3 {
+        for j in 0..3 {
+            v[i * 4 + j] = c(j, i) / det;
+        }
+    }
+    for i in 0..3 {
+        v[i * 4 + 3] = -(0..3).fold(0., |acc, j| acc + v[i * 4 + j] * a(j, 3));
+    }
+    v[15] = 1.;
+    Some(Mat4::<f64>::init(v, true))
+}
+
+fn point_transform(m: &Mat4<f64>, p: &[f64; 3], w: f64) -> [f64; 3] {
+    let mut out = [0.; 3];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = m.index(i as u64, 3) * w;
+        for (j, x) in p.iter().enumerate() {
+            *o += m.index(i as u64, j as u64) * x;
+        }
+    }
+    out
+}
+
+///visits leaves of the subtrees accepted by pred until f returns true
+fn search<T, P, F>(n: &NodeBvh<T>, mut pred: P, mut f: F) -> bool
+where
+    T: Default + Clone,
+    P: FnMut(&NodeBvh<T>) -> bool,
+    F: FnMut(&NodeBvh<T>) -> bool,
+{
+    let mut q = vec![n];
+    while let Some(l) = q.pop() {
+        if !pred(l) {
+            continue;
+        }
+        let mut leaf = true;
+        for c in [l.get_left(), l.get_right()] {
+            if let BvhBranch::CHILD(ref o) = *c {
+                let o_ref: &NodeBvh<T> = o;
+                q.push(o_ref);
+                leaf = false;
+            }
+        }
+        if leaf && f(l) {
+            return true;
+        }
+    }
+    false
+}
+
+impl<T> BvhInstance<T>
+where
+    T: Default + Clone,
+{
+    ///bins is the bin count used to build the top level
+    pub fn init(bins: u32) -> BvhInstance<T> {
+        BvhInstance {
+            _top: Bvh::init(bins),
+            _instances: vec![],
+        }
+    }
+    ///number of instances
+    pub fn len(&self) -> usize {
+        self._instances.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._instances.is_empty()
+    }
+    ///world bound of an instance
+    pub fn get_instance_bound(&self, id: usize) -> Option<&AxisAlignedBBox> {
+        self._instances.get(id).map(|x| &x._bound)
+    }
+    pub fn get_transform(&self, id: usize) -> Option<&Mat4<f64>> {
+        self._instances.get(id).map(|x| &x._xform)
+    }
+    ///replaces content with instances of built bottom-level hierarchies placed by object to
+    ///world transforms acting on column vectors, instance ids follow input order
+    pub fn build(&mut self, instances: &[(Rc<Bvh<T>>, Mat4<f64>)]) -> Result<(), &'static str> {
+        let mut v = vec![];
+        for (blas, xform) in instances.iter() {
+            let root = blas.get_root().get_bound();
+            if (0..3)
+                .any(|i| !root._bound_lower[i].is_finite() || !root._bound_upper[i].is_finite())
+            {
+                return Err("bottom level bvh must be built and non-empty");
+            }
+            if (0..3).any(|j| xform.index(3, j) != 0.) || xform.index(3, 3) != 1. {
+                return Err("instance transform must be affine");
+            }
+            let inv = match affine_inverse(xform) {
+                Some(inv) => inv,
+                _ => return Err("instance transform is not invertible"),
+            };
+            v.push(Instance {
+                _blas: blas.clone(),
+                _xform: *xform,
+                _inv: inv,
+                _bound: bound_transform(xform, root),
+            });
+        }
+        self._instances = v;
+        if !self._instances.is_empty() {
+            let bounds = self
+                ._instances
+                .iter()
+                .enumerate()
+                .map(|(i, x)| (i, &x._bound as &dyn IBound))
+                .collect::<Vec<_>>();
+            self._top.build_all(&bounds[..])?;
+        }
+        Ok(())
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<(usize, T)>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        if self._instances.is_empty() {
+            return Ok(out);
+        }
+        let world = bound_copy(input);
+        search(
+            self._top.get_root(),
+            |n| n.get_bound().intersect(input),
+            |top| {
+                let id = *top.get_obj();
+                let inst = &self._instances[id];
+                //the query maps to the bound of its transformed corners, which is exact for
+                //translations and scales and conservative under rotation
+                let local = bound_transform(&inst._inv, &world);
+                search(
+                    inst._blas.get_root(),
+                    |n| n.get_bound().intersect(&local),
+                    |n| {
+                        out.push((id, n.get_obj().clone()));
+                        single
+                    },
+                )
+            },
+        );
+        Ok(out)
+    }
+    ///query for objects whose bounds are hit by the ray, as pairs of instance id and payload
+    ///ordered by increasing entry distance along the ray
+    pub fn query_ray(&self, r: &Ray3) -> Result<Vec<(usize, T)>, &'static str> {
+        let o = [r._ori[0], r._ori[1], r._ori[2]];
+        let d = [r._dir[0], r._dir[1], r._dir[2]];
+        let mut hits = vec![];
+        if self._instances.is_empty() {
+            return Ok(vec![]);
+        }
+        search(
+            self._top.get_root(),
+            |n| slab_intersect_bound(&o, &d, n.get_bound()).is_some(),
+            |top| {
+                let id = *top.get_obj();
+                let inst = &self._instances[id];
+                //affine maps preserve the ray parameter so object space distances are world
+                //distances
+                let lo = point_transform(&inst._inv, &o, 1.);
+                let ld = point_transform(&inst._inv, &d, 0.);
+                search(
+                    inst._blas.get_root(),
+                    |n| slab_intersect_bound(&lo, &ld, n.get_bound()).is_some(),
+                    |n| {
+                        if let Some(t) = slab_intersect_bound(&lo, &ld, n.get_bound()) {
+                            hits.push((t.0, id, n.get_obj().clone()));
+                        }
+                        false
+                    },
+                )
+            },
+        );
+        hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(::std::cmp::Ordering::Equal));
+        Ok(hits.into_iter().map(|x| (x.1, x.2)).collect())
+    }
+    ///query for objects of the instances whose world bound intersects input, testing object
+    ///bounds against input mapped into object space, as pairs of instance id and payload
+    pub fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<(usize, T)>, &'static str> {
+        self.query(input, false)
+    }
+    pub fn query_intersect_single(
+        &self,
+        input: &dyn IBound,
+    ) -> Result<Vec<(usize, T)>, &'static str> {
+        self.query(input, true)
+    }
+}
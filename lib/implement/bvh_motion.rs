@@ -0,0 +1,337 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+use self::mazth::ray::Ray3;
+
+use std::boxed::Box;
+use std::f64;
+
+use implement::bound_util::{bound_copy, bound_union, ray_intersect_bound};
+use implement::bvh;
+use implement::bvh_median;
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// split strategy used for constructing the motion blur hierarchy over swept object bounds
+pub enum BuildMethod {
+    ///split at median of centroids, as in implement::bvh_median
+    Median,
+    ///binned surface area heuristic with given bin count, as in implement::bvh
+    SurfaceArea(u32),
+}
+
+/// bounding volume hierarchy for motion blur with bounds at shutter open (t=0) and close (t=1)
+pub struct Bvh<T>
+where
+    T: Default + Clone,
+{
+    _root: BvhBranch<T>,
+    _method: BuildMethod,
+}
+
+///internal node structure for Bvh, bounds are linearly interpolated at query time
+pub struct NodeBvh<T>
+where
+    T: Default + Clone,
+{
+    _bound_t0: AxisAlignedBBox,
+    _bound_t1: AxisAlignedBBox,
+    _left: BvhBranch<T>,
+    _right: BvhBranch<T>,
+    _obj: T, //leaf data
+}
+
+pub enum BvhBranch<T>
+where
+    T: Default + Clone,
+{
+    CHILD(Box<NodeBvh<T>>),
+    EMPTY,
+}
+
+fn bound_lerp(a: &AxisAlignedBBox, b: &AxisAlignedBBox, t: f64) -> AxisAlignedBBox {
+    let mut u = a.clone();
+    for i in 0..3 {
+        u._bound_lower[i] = a._bound_lower[i] * (1. - t) + b._bound_lower[i] * t;
+        u._bound_upper[i] = a._bound_upper[i] * (1. - t) + b._bound_upper[i] * t;
+    }
+    u
+}
+
+impl<T> BvhBranch<T>
+where
+    T: Default + Clone,
+{
+    fn get_bounds(&self) -> Option<(&AxisAlignedBBox, &AxisAlignedBBox)> {
+        match *self {
+            BvhBranch::CHILD(ref o) => Some((&o._bound_t0, &o._bound_t1)),
+            _ => None,
+        }
+    }
+    ///combine a pair of subtrees under a new node with bounds enclosing both at each end of the shutter
+    fn join(l: BvhBranch<T>, r: BvhBranch<T>) -> BvhBranch<T> {
+        let (b0, b1) = match (l.get_bounds(), r.get_bounds()) {
+            (Some(x), Some(y)) => (bound_union(x.0, y.0), bound_union(x.1, y.1)),
+            (Some(x), None) | (None, Some(x)) => (x.0.clone(), x.1.clone()),
+            (None, None) => return BvhBranch::EMPTY,
+        };
+        BvhBranch::CHILD(Box::new(NodeBvh {
+            _bound_t0: b0,
+            _bound_t1: b1,
+            _left: l,
+            _right: r,
+            _obj: T::default(),
+        }))
+    }
+    fn from_median(
+        n: &bvh_median::NodeBvh<usize>,
+        objs: &[(T, AxisAlignedBBox, AxisAlignedBBox)],
+    ) -> BvhBranch<T> {
+        match (n.get_left(), n.get_right()) {
+            (&bvh_median::BvhBranch::EMPTY, &bvh_median::BvhBranch::EMPTY) => {
+                NodeBvh::init_leaf(&objs[*n.get_obj()])
+            }
+            (l, r) => {
+                let l = match *l {
+                    bvh_median::BvhBranch::CHILD(ref o) => BvhBranch::from_median(o, objs),
+                    _ => BvhBranch::EMPTY,
+                };
+                let r = match *r {
+                    bvh_median::BvhBranch::CHILD(ref o) => BvhBranch::from_median(o, objs),
+                    _ => BvhBranch::EMPTY,
+                };
+                BvhBranch::join(l, r)
+            }
+        }
+    }
+    fn from_sah(
+        n: &bvh::NodeBvh<usize>,
+        objs: &[(T, AxisAlignedBBox, AxisAlignedBBox)],
+    ) -> BvhBranch<T> {
+        match (n.get_left(), n.get_right()) {
+            (&bvh::BvhBranch::EMPTY, &bvh::BvhBranch::EMPTY) => {
+                NodeBvh::init_leaf(&objs[*n.get_obj()])
+            }
+            (l, r) => {
+                let l = match *l {
+                    bvh::BvhBranch::CHILD(ref o) => BvhBranch::from_sah(o, objs),
+                    _ => BvhBranch::EMPTY,
+                };
+                let r = match *r {
+                    bvh::BvhBranch::CHILD(ref o) => BvhBranch::from_sah(o, objs),
+                    _ => BvhBranch::EMPTY,
+                };
+                BvhBranch::join(l, r)
+            }
+        }
+    }
+}
+
+impl<T> NodeBvh<T>
+where
+    T: Default + Clone,
+{
+    fn init_leaf(o: &(T, AxisAlignedBBox, AxisAlignedBBox)) -> BvhBranch<T> {
+        BvhBranch::CHILD(Box::new(NodeBvh {
+            _bound_t0: o.1.clone(),
+            _bound_t1: o.2.clone(),
+            _left: BvhBranch::EMPTY,
+            _right: BvhBranch::EMPTY,
+            _obj: o.0.clone(),
+        }))
+    }
+    fn is_leaf(&self) -> bool {
+        matches!(
+            (&self._left, &self._right),
+            (&BvhBranch::EMPTY, &BvhBranch::EMPTY)
+        )
+    }
+    ///bound of the node at time t in [0,1]
+    pub fn get_bound(&self, t: f64) -> AxisAlignedBBox {
+        bound_lerp(&self._bound_t0, &self._bound_t1, t)
+    }
+    ///bound enclosing the node over the whole shutter interval
+    pub fn get_bound_swept(&self) -> AxisAlignedBBox {
+        bound_union(&self._bound_t0, &self._bound_t1)
+    }
+    ///visits leaves whose bound passes the node test, stopping when f returns true
+    pub fn search<F, G>(n: &BvhBranch<T>, mut node_test: G, mut f: F)
+    where
+        F: FnMut(T) -> bool,
+        G: FnMut(&NodeBvh<T>) -> bool,
+    {
+        let mut q = vec![n];
+        while let Some(branch) = q.pop() {
+            if let BvhBranch::CHILD(ref l) = *branch {
+                if node_test(l) {
+                    if l.is_leaf() {
+                        if f(l._obj.clone()) {
+                            return;
+                        }
+                        continue;
+                    }
+                    q.push(&l._left);
+                    q.push(&l._right);
+                }
+            }
+        }
+    }
+}
+
+impl<T> ISpatialAccel<T> for Bvh<T>
+where
+    T: Default + Clone,
+{
+    ///query against bounds swept over the whole shutter interval
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        NodeBvh::search(
+            &self._root,
+            |n| n.get_bound_swept().intersect(input),
+            |x| {
+                out.push(x);
+                false
+            },
+        );
+        Ok(out)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        NodeBvh::search(
+            &self._root,
+            |n| n.get_bound_swept().intersect(input),
+            |x| {
+                out.push(x);
+                true
+            },
+        );
+        Ok(out)
+    }
+    ///build with stationary objects, using the same bound at shutter open and close
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        let objs_motion = objs
+            .iter()
+            .map(|x| (x.0.clone(), x.1, x.1))
+            .collect::<Vec<_>>();
+        self.build_all_motion(&objs_motion[..])
+    }
+}
+
+impl<T> Bvh<T>
+where
+    T: Default + Clone,
+{
+    pub fn init(method: BuildMethod) -> Bvh<T> {
+        if let BuildMethod::SurfaceArea(bins) = method {
+            assert!(bins != 0);
+        }
+        Bvh {
+            _root: BvhBranch::EMPTY,
+            _method: method,
+        }
+    }
+    ///build with input object ids and bounds at shutter open and close
+    pub fn build_all_motion(
+        &mut self,
+        objs: &[(T, &dyn IBound, &dyn IBound)],
+    ) -> Result<(), &'static str> {
+        for i in objs {
+            match (i.1.get_type(), i.2.get_type()) {
+                (BoundType::AxisAlignBox, BoundType::AxisAlignBox) => (),
+                _ => return Err("unsupported bound type"),
+            }
+        }
+
+        self._root = BvhBranch::EMPTY;
+        if objs.is_empty() {
+            return Ok(());
+        }
+
+        let bounds = objs
+            .iter()
+            .map(|x| (x.0.clone(), bound_copy(x.1), bound_copy(x.2)))
+            .collect::<Vec<_>>();
+
+        //partition objects using their bounds swept over the shutter interval
+        let swept = bounds
+            .iter()
+            .map(|x| bound_union(&x.1, &x.2))
+            .collect::<Vec<_>>();
+        let swept_refs = swept
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| (idx, x as &dyn IBound, !0u64))
+            .collect::<Vec<_>>();
+
+        self._root = match self._method {
+            BuildMethod::Median => {
+                let mut n: bvh_median::NodeBvh<usize> = Default::default();
+                n.build_node(0, &swept_refs[..])?;
+                BvhBranch::from_median(&n, &bounds[..])
+            }
+            BuildMethod::SurfaceArea(bins) => {
+                if bins == 0 {
+                    return Err("bvh bin count cannot be zero");
+                }
+                let mut n: bvh::NodeBvh<usize> = Default::default();
+                n.build_node(bins, &swept_refs[..])?;
+                BvhBranch::from_sah(&n, &bounds[..])
+            }
+        };
+        Ok(())
+    }
+    ///query for objects intersecting with input at time t in [0,1]
+    pub fn query_intersect_time(&self, input: &dyn IBound, t: f64) -> Result<Vec<T>, &'static str> {
+        self.query_time(input, t, false)
+    }
+    pub fn query_intersect_single_time(
+        &self,
+        input: &dyn IBound,
+        t: f64,
+    ) -> Result<Vec<T>, &'static str> {
+        self.query_time(input, t, true)
+    }
+    fn query_time(&self, input: &dyn IBound, t: f64, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        if !(0. ..=1.).contains(&t) {
+            return Err("time out of range");
+        }
+        let mut out = vec![];
+        NodeBvh::search(
+            &self._root,
+            |n| n.get_bound(t).intersect(input),
+            |x| {
+                out.push(x);
+                single
+            },
+        );
+        Ok(out)
+    }
+    ///query for objects whose bounds at time t in [0,1] are hit by the ray
+    pub fn query_ray_time(&self, r: &Ray3, t: f64) -> Result<Vec<T>, &'static str> {
+        if !(0. ..=1.).contains(&t) {
+            return Err("time out of range");
+        }
+        let mut out = vec![];
+        NodeBvh::search(
+            &self._root,
+            |n| ray_intersect_bound(r, &n.get_bound(t)).is_some(),
+            |x| {
+                out.push(x);
+                false
+            },
+        );
+        Ok(out)
+    }
+}
@@ -0,0 +1,202 @@
+extern crate mazth;
+extern crate rand;
+extern crate rand_chacha;
+
+use self::mazth::i_bound::{BoundType, IBound};
+
+use self::rand::{seq, Rng};
+use self::rand_chacha::ChaChaRng;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f64;
+
+use interface::i_cluster::ClusterMetric;
+
+///score used to choose the cluster count for ClusterSize::Auto
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Criterion {
+    ///mean silhouette of all objects, the highest score wins
+    Silhouette,
+    ///gap statistic against uniform reference sets drawn over the bounding box of the objects
+    ///from a ChaCha rng seeded with seed, the smallest k whose gap is within one standard error of the
+    ///next gap wins
+    Gap { references: usize, seed: u64 },
+    ///bayesian information criterion of a gaussian mixture, the lowest score wins
+    Bic,
+}
+
+///distance between centroids under the metric
+pub fn distance(metric: ClusterMetric, a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    match metric {
+        ClusterMetric::L1 => (0..3).fold(0., |acc, i| acc + (a[i] - b[i]).abs()),
+        ClusterMetric::L2 => (0..3)
+            .fold(0., |acc, i| acc + (a[i] - b[i]) * (a[i] - b[i]))
+            .sqrt(),
+    }
+}
+
+///map from object id to input index with the centroids of the input bounds
+pub type Centroids = (HashMap<u64, usize>, Vec<[f64; 3]>);
+
+///centroids of axis aligned input bounds, object ids must be unique
+pub fn centroids(input: &[(u64, &dyn IBound)]) -> Result<Centroids, &'static str> {
+    let mut ids = HashMap::new();
+    let mut points = vec![];
+    for (i, x) in input.iter().enumerate() {
+        match x.1.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let d = x.1.get_bound_data();
+        let p = [(d[0] + d[3]) / 2., (d[1] + d[4]) / 2., (d[2] + d[5]) / 2.];
+        if p.iter().any(|v| !v.is_finite()) {
+            return Err("clustering requires finite bounds");
+        }
+        if ids.insert(x.0, i).is_some() {
+            return Err("duplicate object id");
+        }
+        points.push(p);
+    }
+    Ok((ids, points))
+}
+
+///k-means++ initial centres chosen from a uniform sample of at most sample objects, later
+///centres are drawn with probability proportional to the squared distance to the closest
+///chosen centre under L2 and to the distance under L1
+pub fn seed_plus_plus(
+    metric: ClusterMetric,
+    points: &[[f64; 3]],
+    k: usize,
+    sample: usize,
+    rng: &mut ChaChaRng,
+) -> Vec<[f64; 3]> {
+    let n = points.len();
+    let m = sample.max(k).min(n);
+    let candidates = seq::index::sample(rng, n, m)
+        .into_vec()
+        .into_iter()
+        .map(|i| points[i])
+        .collect::<Vec<_>>();
+    let mut centres = vec![candidates[rng.gen_range(0, m)]];
+    let mut weights = candidates
+        .iter()
+        .map(|p| distance(metric, p, &centres[0]))
+        .collect::<Vec<_>>();
+    let weight = |d: f64| match metric {
+        ClusterMetric::L1 => d,
+        ClusterMetric::L2 => d * d,
+    };
+    while centres.len() < k {
+        let total = weights.iter().map(|d| weight(*d)).sum::<f64>();
+        let next = if total > 0. {
+            let mut target = rng.gen_range(0., total);
+            let mut chosen = weights.iter().rposition(|d| *d > 0.).unwrap_or(m - 1);
+            for (i, d) in weights.iter().enumerate() {
+                let w = weight(*d);
+                if target < w {
+                    chosen = i;
+                    break;
+                }
+                target -= w;
+            }
+            chosen
+        } else {
+            //all candidates coincide with chosen centres
+            rng.gen_range(0, m)
+        };
+        let c = candidates[next];
+        for (d, p) in weights.iter_mut().zip(candidates.iter()) {
+            *d = d.min(distance(metric, p, &c));
+        }
+        centres.push(c);
+    }
+    centres
+}
+
+///range of k scored for the criterion given the objects, silhouette needs at least 2 clusters
+///and one cluster with more than one object, gap takes the log of the clustering cost so k
+///stays below the number of distinct objects to keep the cost positive, bic keeps k within the
+///number of distinct objects so no two components start identical
+pub fn candidates(
+    criterion: Criterion,
+    range: (usize, usize),
+    points: &[[f64; 3]],
+) -> Result<(usize, usize), &'static str> {
+    let n = points.len();
+    let (lo, hi) = match criterion {
+        Criterion::Silhouette => (range.0.max(2), range.1.min(n.saturating_sub(1))),
+        Criterion::Gap { .. } => (range.0, range.1.min(distinct(points).saturating_sub(1))),
+        Criterion::Bic => (range.0, range.1.min(distinct(points))),
+    };
+    if lo > hi {
+        Err("too few objects for cluster range")
+    } else {
+        Ok((lo, hi))
+    }
+}
+
+///number of distinct points
+pub fn distinct(points: &[[f64; 3]]) -> usize {
+    let mut v = points.to_vec();
+    v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    v.dedup();
+    v.len()
+}
+
+///mean silhouette over objects given their cluster assignments, objects alone in their cluster
+///score 0
+pub fn silhouette(metric: ClusterMetric, points: &[[f64; 3]], assign: &[usize], k: usize) -> f64 {
+    let mut sizes = vec![0usize; k];
+    for a in assign.iter() {
+        sizes[*a] += 1;
+    }
+    let mut total = 0.;
+    for (p, a) in points.iter().zip(assign.iter()) {
+        if sizes[*a] < 2 {
+            continue;
+        }
+        let mut sums = vec![0.; k];
+        for (q, b) in points.iter().zip(assign.iter()) {
+            sums[*b] += distance(metric, p, q);
+        }
+        let intra = sums[*a] / (sizes[*a] - 1) as f64;
+        let mut inter = f64::INFINITY;
+        for (c, (s, size)) in sums.iter().zip(sizes.iter()).enumerate() {
+            if c != *a && *size > 0 {
+                inter = inter.min(s / *size as f64);
+            }
+        }
+        let m = intra.max(inter);
+        if m > 0. && m.is_finite() {
+            total += (inter - intra) / m;
+        }
+    }
+    if points.is_empty() {
+        0.
+    } else {
+        total / points.len() as f64
+    }
+}
+
+///index into scores of the chosen k, gaps holds the gap and its standard error per k in
+///increasing k, falling back to the largest gap when no k passes the one standard error rule
+pub fn select_gap(gaps: &[(f64, f64)]) -> usize {
+    for i in 0..gaps.len().saturating_sub(1) {
+        if gaps[i].0 >= gaps[i + 1].0 - gaps[i + 1].1 {
+            return i;
+        }
+    }
+    select_max(&gaps.iter().map(|x| x.0).collect::<Vec<_>>()[..])
+}
+
+///index of the highest score, ties resolved to the lower index
+pub fn select_max(scores: &[f64]) -> usize {
+    let mut best = (f64::NEG_INFINITY, 0);
+    for (i, s) in scores.iter().enumerate() {
+        if *s > best.0 {
+            best = (*s, i);
+        }
+    }
+    best.1
+}
@@ -0,0 +1,176 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::IBound;
+
+use std::collections::HashMap;
+
+use implement::bvh::Bvh;
+use implement::cluster_select::{centroids, distance};
+use interface::i_cluster::{ClusterMethod, ClusterMetric, ClusterOrder, ClusterSize, ICluster};
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// density based clustering (DBSCAN) of bound centroids, neighbourhoods are found with a Bvh
+/// over the centroids
+pub struct Dbscan {
+    _eps: f64,
+    _min_pts: usize,
+    _bins: u32,
+    _ids: HashMap<u64, usize>, //object id to input index
+    _labels: Vec<(u64, PointLabel)>,
+    _clusters: usize,
+}
+
+///cluster id reported for noise objects
+pub const NOISE: u64 = u64::MAX;
+
+///role of an object in its cluster
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointLabel {
+    ///at least min_pts objects within eps, counting itself
+    Core,
+    ///within eps of a core object without being one
+    Border,
+    Noise,
+}
+
+///labels unvisited and noise neighbours as border objects of cluster c, only unvisited ones are
+///queued for expansion so each object enters the queue at most once
+fn enqueue(
+    labels: &mut [Option<(u64, PointLabel)>],
+    q: &mut Vec<usize>,
+    c: u64,
+    neighbours: Vec<usize>,
+) {
+    for j in neighbours {
+        match labels[j] {
+            None => {
+                labels[j] = Some((c, PointLabel::Border));
+                q.push(j);
+            }
+            Some((_, PointLabel::Noise)) => labels[j] = Some((c, PointLabel::Border)),
+            _ => (),
+        }
+    }
+}
+
+impl Dbscan {
+    ///objects are core when at least min_pts objects including themselves lie within eps, bins
+    ///is the bin count of the Bvh used for neighbourhood queries
+    pub fn init(eps: f64, min_pts: usize, bins: u32) -> Dbscan {
+        assert!(eps >= 0.);
+        assert!(min_pts > 0);
+        assert!(bins > 0);
+        Dbscan {
+            _eps: eps,
+            _min_pts: min_pts,
+            _bins: bins,
+            _ids: HashMap::new(),
+            _labels: vec![],
+            _clusters: 0,
+        }
+    }
+    ///number of clusters found, noise excluded
+    pub fn cluster_count(&self) -> usize {
+        self._clusters
+    }
+    pub fn get_label(&self, id: u64) -> Result<PointLabel, &'static str> {
+        match self._ids.get(&id) {
+            Some(i) => Ok(self._labels[*i].1),
+            _ => Err("unknown object id"),
+        }
+    }
+}
+
+impl ICluster for Dbscan {
+    fn query_cluster(&self, input: u64) -> Result<u64, &'static str> {
+        match self._ids.get(&input) {
+            Some(i) => Ok(self._labels[*i].0),
+            _ => Err("unknown object id"),
+        }
+    }
+    ///the number of clusters follows from the density so size must be Auto, clusters are
+    ///numbered from 0 in the order their first core object appears in input, a border object
+    ///reachable from several clusters joins the first one to reach it
+    fn build_all(
+        &mut self,
+        size: ClusterSize,
+        method: ClusterMethod,
+        metric: ClusterMetric,
+        order: ClusterOrder,
+        input: &[(u64, &dyn IBound)],
+    ) -> Result<(), &'static str> {
+        match method {
+            ClusterMethod::Density => (),
+            _ => return Err("unsupported cluster method"),
+        }
+        match size {
+            ClusterSize::Auto => (),
+            _ => return Err("cluster count is determined by density"),
+        }
+        match order {
+            ClusterOrder::InOrder => (),
+            _ => return Err("unsupported cluster order"),
+        }
+        let (ids, points) = centroids(input)?;
+        let bounds = points
+            .iter()
+            .map(|p| AxisAlignedBBox {
+                _bound_lower: *p,
+                _bound_upper: *p,
+            })
+            .collect::<Vec<_>>();
+        let mut bvh = Bvh::init(self._bins);
+        if !points.is_empty() {
+            let objs = bounds
+                .iter()
+                .enumerate()
+                .map(|(i, b)| (i, b as &dyn IBound))
+                .collect::<Vec<_>>();
+            bvh.build_all(&objs[..])?;
+        }
+        let eps = self._eps;
+        let neighbours = |i: usize| -> Result<Vec<usize>, &'static str> {
+            let p = &points[i];
+            let q = AxisAlignedBBox {
+                _bound_lower: [p[0] - eps, p[1] - eps, p[2] - eps],
+                _bound_upper: [p[0] + eps, p[1] + eps, p[2] + eps],
+            };
+            Ok(bvh
+                .query_intersect(&q)?
+                .into_iter()
+                .filter(|j| distance(metric, p, &points[*j]) <= eps)
+                .collect())
+        };
+
+        let mut labels: Vec<Option<(u64, PointLabel)>> = vec![None; points.len()];
+        let mut clusters = 0;
+        for i in 0..points.len() {
+            if labels[i].is_some() {
+                continue;
+            }
+            let n = neighbours(i)?;
+            if n.len() < self._min_pts {
+                //may later be reached as a border object
+                labels[i] = Some((NOISE, PointLabel::Noise));
+                continue;
+            }
+            let c = clusters as u64;
+            clusters += 1;
+            labels[i] = Some((c, PointLabel::Core));
+            let mut q = vec![];
+            enqueue(&mut labels[..], &mut q, c, n);
+            while let Some(j) = q.pop() {
+                let nj = neighbours(j)?;
+                if nj.len() >= self._min_pts {
+                    labels[j] = Some((c, PointLabel::Core));
+                    enqueue(&mut labels[..], &mut q, c, nj);
+                }
+            }
+        }
+        self._ids = ids;
+        self._labels = labels.into_iter().map(|x| x.unwrap()).collect();
+        self._clusters = clusters;
+        Ok(())
+    }
+}
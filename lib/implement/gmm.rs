@@ -0,0 +1,415 @@
+extern crate mazth;
+extern crate rand;
+extern crate rand_chacha;
+
+use self::mazth::i_bound::IBound;
+
+use self::rand::SeedableRng;
+use self::rand_chacha::ChaChaRng;
+
+use std::collections::HashMap;
+use std::f64;
+
+use implement::cluster_select::{self, centroids, Criterion};
+use interface::i_cluster::{ClusterMethod, ClusterMetric, ClusterOrder, ClusterSize, ICluster};
+
+///added to covariance diagonals so components collapsing onto a single point stay invertible
+const REG_COVAR: f64 = 1e-6;
+
+///shape of the component covariances
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Covariance {
+    Full,
+    ///axis aligned, off-diagonal terms are zero
+    Diagonal,
+    ///single variance shared by all axes
+    Spherical,
+}
+
+/// gaussian mixture clustering of bound centroids fitted by expectation-maximization
+pub struct Gmm {
+    _max_iter: usize,
+    _tol: f64,
+    _covariance: Covariance,
+    _ids: HashMap<u64, usize>, //object id to input index
+    _points: Vec<[f64; 3]>,
+    _weights: Vec<f64>,
+    _means: Vec<[f64; 3]>,
+    _covs: Vec<[[f64; 3]; 3]>,
+    _resp: Vec<Vec<f64>>, //membership probability per object and component
+    _log_likelihood: f64,
+    _iterations: usize,
+    _converged: bool,
+    _auto: (usize, usize, Criterion),
+    _scores: Vec<(usize, f64)>,
+    _seed: u64,
+    _sample: usize,
+}
+
+fn determinant(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+///inverse from cofactors, None for non positive definite input
+fn inverse(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let det = determinant(m);
+    if det <= 0. || !det.is_finite() {
+        return None;
+    }
+    let mut inv = [[0.; 3]; 3];
+    for (r, row) in inv.iter_mut().enumerate() {
+        for (c, v) in row.iter_mut().enumerate() {
+            //transposed cofactor
+            let (r0, r1) = ((c + 1) % 3, (c + 2) % 3);
+            let (c0, c1) = ((r + 1) % 3, (r + 2) % 3);
+            *v = (m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]) / det;
+        }
+    }
+    Some(inv)
+}
+
+///weighted covariance about mean, restricted to the given shape and regularized
+fn covariance(
+    shape: Covariance,
+    points: &[[f64; 3]],
+    weights: &[f64],
+    mean: &[f64; 3],
+) -> [[f64; 3]; 3] {
+    let total = weights.iter().sum::<f64>();
+    let mut m = [[0.; 3]; 3];
+    if total > 0. {
+        for (p, w) in points.iter().zip(weights.iter()) {
+            for (r, row) in m.iter_mut().enumerate() {
+                for (c, v) in row.iter_mut().enumerate() {
+                    *v += w * (p[r] - mean[r]) * (p[c] - mean[c]);
+                }
+            }
+        }
+        for row in m.iter_mut() {
+            for v in row.iter_mut() {
+                *v /= total;
+            }
+        }
+    }
+    match shape {
+        Covariance::Full => (),
+        Covariance::Diagonal => {
+            for (r, row) in m.iter_mut().enumerate() {
+                for (c, v) in row.iter_mut().enumerate() {
+                    if r != c {
+                        *v = 0.;
+                    }
+                }
+            }
+        }
+        Covariance::Spherical => {
+            let s = (m[0][0] + m[1][1] + m[2][2]) / 3.;
+            m = [[s, 0., 0.], [0., s, 0.], [0., 0., s]];
+        }
+    }
+    for (i, row) in m.iter_mut().enumerate() {
+        row[i] += REG_COVAR;
+    }
+    m
+}
+
+impl Gmm {
+    ///fitting stops once the mean log-likelihood per object improves by less than tol or after
+    ///max_iter EM steps, ClusterSize::Auto scores k from 1 to 10 by BIC unless changed with
+    ///set_auto
+    pub fn init(max_iter: usize, tol: f64, covariance: Covariance) -> Gmm {
+        assert!(max_iter > 0);
+        assert!(tol >= 0.);
+        Gmm {
+            _max_iter: max_iter,
+            _tol: tol,
+            _covariance: covariance,
+            _ids: HashMap::new(),
+            _points: vec![],
+            _weights: vec![],
+            _means: vec![],
+            _covs: vec![],
+            _resp: vec![],
+            _log_likelihood: f64::NEG_INFINITY,
+            _iterations: 0,
+            _converged: false,
+            _auto: (1, 10, Criterion::Bic),
+            _scores: vec![],
+            _seed: 0,
+            _sample: usize::MAX,
+        }
+    }
+    ///seed of the ChaCha rng used for PreSample seeding, each fit restarts from it so results
+    ///are reproducible across platforms
+    pub fn set_seed(&mut self, seed: u64) {
+        self._seed = seed;
+    }
+    ///number of objects drawn for k-means++ seeding of the means under PreSample, all objects
+    ///by default, at least k objects are drawn
+    pub fn set_sample_size(&mut self, sample: usize) -> Result<(), &'static str> {
+        if sample == 0 {
+            return Err("sample size must be positive");
+        }
+        self._sample = sample;
+        Ok(())
+    }
+    ///range of k and criterion used for ClusterSize::Auto, Gap is not supported
+    pub fn set_auto(
+        &mut self,
+        k_min: usize,
+        k_max: usize,
+        criterion: Criterion,
+    ) -> Result<(), &'static str> {
+        if k_min == 0 || k_min > k_max {
+            return Err("invalid cluster range");
+        }
+        if let Criterion::Gap { .. } = criterion {
+            return Err("unsupported criterion");
+        }
+        self._auto = (k_min, k_max, criterion);
+        Ok(())
+    }
+    ///k and its score for each cluster count tried by the last ClusterSize::Auto build
+    pub fn get_scores(&self) -> &[(usize, f64)] {
+        &self._scores[..]
+    }
+    ///bayesian information criterion of the fitted mixture, lower is better, infinite before
+    ///any successful build
+    pub fn get_bic(&self) -> f64 {
+        let k = self._means.len();
+        if k == 0 {
+            return f64::INFINITY;
+        }
+        let cov = match self._covariance {
+            Covariance::Full => 6,
+            Covariance::Diagonal => 3,
+            Covariance::Spherical => 1,
+        };
+        let params = (k - 1) + 3 * k + cov * k;
+        params as f64 * (self._points.len() as f64).ln() - 2. * self._log_likelihood
+    }
+    pub fn get_weights(&self) -> &[f64] {
+        &self._weights[..]
+    }
+    pub fn get_means(&self) -> &[[f64; 3]] {
+        &self._means[..]
+    }
+    pub fn get_covariances(&self) -> &[[[f64; 3]; 3]] {
+        &self._covs[..]
+    }
+    ///log-likelihood of all objects under the fitted mixture
+    pub fn get_log_likelihood(&self) -> f64 {
+        self._log_likelihood
+    }
+    ///number of EM steps done by the last build
+    pub fn get_iterations(&self) -> usize {
+        self._iterations
+    }
+    ///whether the last build met the tolerance before reaching max_iter
+    pub fn is_converged(&self) -> bool {
+        self._converged
+    }
+    ///probability of the object belonging to each component, summing to 1
+    pub fn query_membership(&self, input: u64) -> Result<&[f64], &'static str> {
+        match self._ids.get(&input) {
+            Some(i) => Ok(&self._resp[*i][..]),
+            _ => Err("unknown object id"),
+        }
+    }
+    ///updates memberships from the current parameters and returns the log-likelihood
+    fn expectation(&mut self) -> Result<f64, &'static str> {
+        let mut comps = vec![];
+        for (w, c) in self._weights.iter().zip(self._covs.iter()) {
+            let inv = match inverse(c) {
+                Some(x) => x,
+                _ => return Err("singular covariance"),
+            };
+            let norm = w.ln() - 0.5 * (3. * (2. * f64::consts::PI).ln() + determinant(c).ln());
+            comps.push((norm, inv));
+        }
+        let mut ll = 0.;
+        for (p, resp) in self._points.iter().zip(self._resp.iter_mut()) {
+            for ((r, (norm, inv)), m) in resp.iter_mut().zip(comps.iter()).zip(self._means.iter()) {
+                let d = [p[0] - m[0], p[1] - m[1], p[2] - m[2]];
+                let mut q = 0.;
+                for (row, di) in inv.iter().zip(d.iter()) {
+                    q += di * (row[0] * d[0] + row[1] * d[1] + row[2] * d[2]);
+                }
+                *r = norm - 0.5 * q;
+            }
+            //log-sum-exp for the object density
+            let max = resp.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let lse = max + resp.iter().map(|x| (x - max).exp()).sum::<f64>().ln();
+            for r in resp.iter_mut() {
+                *r = (*r - lse).exp();
+            }
+            ll += lse;
+        }
+        Ok(ll)
+    }
+    ///fits k components to the current objects with initial means chosen in the given order
+    fn fit(&mut self, k: usize, order: ClusterOrder) -> Result<(), &'static str> {
+        let means = match order {
+            ClusterOrder::InOrder => {
+                //first k distinct centroids so no two components start identical
+                let mut means: Vec<[f64; 3]> = vec![];
+                for p in self._points.iter() {
+                    if means.len() == k {
+                        break;
+                    }
+                    if !means.contains(p) {
+                        means.push(*p);
+                    }
+                }
+                means
+            }
+            ClusterOrder::PreSample => cluster_select::seed_plus_plus(
+                ClusterMetric::L2,
+                &self._points[..],
+                k,
+                self._sample,
+                &mut ChaChaRng::seed_from_u64(self._seed),
+            ),
+        };
+        let n = self._points.len();
+        let mut mean = [0.; 3];
+        for p in self._points.iter() {
+            for (m, x) in mean.iter_mut().zip(p.iter()) {
+                *m += x / n as f64;
+            }
+        }
+        let cov = covariance(self._covariance, &self._points[..], &vec![1.; n][..], &mean);
+        self._weights = vec![1. / k as f64; k];
+        self._means = means;
+        self._covs = vec![cov; k];
+        self._resp = vec![vec![0.; k]; n];
+        self._iterations = 0;
+        self._converged = false;
+
+        let mut ll = self.expectation()?;
+        while self._iterations < self._max_iter {
+            self.maximization();
+            self._iterations += 1;
+            let next = self.expectation()?;
+            let gain = (next - ll) / n as f64;
+            ll = next;
+            if gain.abs() < self._tol {
+                self._converged = true;
+                break;
+            }
+        }
+        self._log_likelihood = ll;
+        Ok(())
+    }
+    ///refits weights, means and covariances from the memberships, components without
+    ///members keep their previous mean and covariance
+    fn maximization(&mut self) {
+        let n = self._points.len() as f64;
+        for k in 0..self._means.len() {
+            let r = self._resp.iter().map(|x| x[k]).collect::<Vec<_>>();
+            let total = r.iter().sum::<f64>();
+            self._weights[k] = total / n;
+            if total <= 0. {
+                continue;
+            }
+            let mut mean = [0.; 3];
+            for (p, w) in self._points.iter().zip(r.iter()) {
+                for (m, x) in mean.iter_mut().zip(p.iter()) {
+                    *m += w * x / total;
+                }
+            }
+            self._means[k] = mean;
+            self._covs[k] = covariance(self._covariance, &self._points[..], &r[..], &mean);
+        }
+    }
+}
+
+impl ICluster for Gmm {
+    ///component with the highest membership probability, ties resolved to the lower index
+    fn query_cluster(&self, input: u64) -> Result<u64, &'static str> {
+        let resp = self.query_membership(input)?;
+        let mut best = (f64::NEG_INFINITY, 0);
+        for (i, r) in resp.iter().enumerate() {
+            if *r > best.0 {
+                best = (*r, i);
+            }
+        }
+        Ok(best.1 as u64)
+    }
+    ///components are numbered from 0, the initial means are the first k objects for InOrder
+    ///and k-means++ picks over a seeded random sample for PreSample, with equal weights and the
+    ///covariance of all objects, only L2 is supported, for Auto each k in the configured range
+    ///is fitted and scored and the best k is kept
+    fn build_all(
+        &mut self,
+        size: ClusterSize,
+        method: ClusterMethod,
+        metric: ClusterMetric,
+        order: ClusterOrder,
+        input: &[(u64, &dyn IBound)],
+    ) -> Result<(), &'static str> {
+        match method {
+            ClusterMethod::Gaussian => (),
+            _ => return Err("unsupported cluster method"),
+        }
+        match metric {
+            ClusterMetric::L2 => (),
+            _ => return Err("unsupported cluster metric"),
+        }
+        let (ids, points) = centroids(input)?;
+        let (lo, hi, criterion) = match size {
+            ClusterSize::Manual(0) => return Err("cluster count must be positive"),
+            ClusterSize::Manual(k) if k > cluster_select::distinct(&points[..]) => {
+                return Err("cluster count exceeds distinct object count")
+            }
+            ClusterSize::Manual(k) => (k, k, None),
+            ClusterSize::Auto => {
+                let (lo, hi) = cluster_select::candidates(
+                    self._auto.2,
+                    (self._auto.0, self._auto.1),
+                    &points[..],
+                )?;
+                (lo, hi, Some(self._auto.2))
+            }
+        };
+        //fit a working copy so a failed build leaves the previous clustering intact
+        let mut g = Gmm::init(self._max_iter, self._tol, self._covariance);
+        g._auto = self._auto;
+        g._seed = self._seed;
+        g._sample = self._sample;
+        g._ids = ids;
+        g._points = points;
+        let best = match criterion {
+            None => lo,
+            Some(c) => {
+                for k in lo..=hi {
+                    g.fit(k, order)?;
+                    let s = match c {
+                        Criterion::Bic => g.get_bic(),
+                        _ => {
+                            let assign = (0..g._points.len())
+                                .map(|i| cluster_select::select_max(&g._resp[i][..]))
+                                .collect::<Vec<_>>();
+                            cluster_select::silhouette(metric, &g._points[..], &assign[..], k)
+                        }
+                    };
+                    g._scores.push((k, s));
+                }
+                let s = g
+                    ._scores
+                    .iter()
+                    .map(|x| match c {
+                        Criterion::Bic => -x.1,
+                        _ => x.1,
+                    })
+                    .collect::<Vec<_>>();
+                lo + cluster_select::select_max(&s[..])
+            }
+        };
+        g.fit(best, order)?;
+        *self = g;
+        Ok(())
+    }
+}
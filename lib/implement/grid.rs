@@ -0,0 +1,539 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+use self::mazth::ray::Ray3;
+
+use std::collections::HashMap;
+use std::f64;
+
+use implement::bound_util::bound_copy;
+use interface::i_spatial_accel::ISpatialAccel;
+
+///upper limit on number of cells spanned by the bounded grid and on number of cells a single
+///object is inserted into by the hashed grid
+const MAX_CELLS: usize = 1 << 26;
+
+/// cell sizing of a grid
+#[derive(Clone, Debug)]
+pub enum CellSize {
+    ///fixed cell edge length
+    Manual(f64),
+    ///cell edge length derived from object density and mean object extent at build time
+    Auto,
+}
+
+/// cell visited by a ray traversing a grid
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellHit {
+    pub _coord: [usize; 3],
+    ///distance along the ray where it enters the cell
+    pub _t_entry: f64,
+    ///distance along the ray where it exits the cell
+    pub _t_exit: f64,
+    ///normal of the cell face crossed on entry, zero when the ray starts inside the cell
+    pub _normal: [f64; 3],
+}
+
+/// implementation of spatial acceleration using a uniform grid over the bounds of the input objects
+pub struct Grid<T>
+where
+    T: Default + Clone,
+{
+    _size: CellSize,
+    _cell: f64,
+    _lower: [f64; 3],
+    _dims: [usize; 3],
+    _cells: HashMap<[usize; 3], Vec<usize>>, //populated cells only
+    _objs: Vec<(T, AxisAlignedBBox)>,
+}
+
+/// implementation of spatial acceleration using a hashed grid of unbounded extent
+pub struct GridHash<T>
+where
+    T: Default + Clone,
+{
+    _size: CellSize,
+    _cell: f64,
+    _cells: HashMap<[i64; 3], Vec<usize>>,
+    _objs: Vec<(T, AxisAlignedBBox)>,
+}
+
+fn check_bounds<T>(objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+    for i in objs {
+        match i.1.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+    }
+    Ok(())
+}
+
+///cell edge length such that cells roughly hold one object each but are no smaller than the mean object extent
+pub fn cell_size_auto(objs: &[&dyn IBound]) -> f64 {
+    if objs.is_empty() {
+        return 1.;
+    }
+    let mut u: AxisAlignedBBox = Default::default();
+    u.get_union(objs);
+    let mut volume = 1.;
+    let mut extent_max = 0f64;
+    for i in 0..3 {
+        let e = u._bound_upper[i] - u._bound_lower[i];
+        volume *= e;
+        extent_max = extent_max.max(e);
+    }
+    let extent_mean = objs
+        .iter()
+        .map(|x| {
+            let d = x.get_bound_data();
+            (0..3).fold(0f64, |acc, i| acc.max(d[i + 3] - d[i]))
+        })
+        .sum::<f64>()
+        / objs.len() as f64;
+    let c = (volume / objs.len() as f64).cbrt().max(extent_mean);
+    if c.is_finite() && c > 0. {
+        c
+    } else if extent_max.is_finite() && extent_max > 0. {
+        extent_max
+    } else {
+        1.
+    }
+}
+
+fn resolve_cell_size(size: &CellSize, objs: &[&dyn IBound]) -> Result<f64, &'static str> {
+    match *size {
+        CellSize::Manual(c) => {
+            if c.is_finite() && c > 0. {
+                Ok(c)
+            } else {
+                Err("grid cell size must be positive")
+            }
+        }
+        CellSize::Auto => Ok(cell_size_auto(objs)),
+    }
+}
+
+///collects indices of distinct objects from the visited cells that intersect input
+fn collect_unique<T, I>(
+    objs: &[(T, AxisAlignedBBox)],
+    cells: I,
+    input: &dyn IBound,
+    single: bool,
+) -> Vec<T>
+where
+    T: Default + Clone,
+    I: Iterator<Item = usize>,
+{
+    let mut out = vec![];
+    let mut idxs = cells
+        .filter(|x| objs[*x].1.intersect(input))
+        .collect::<Vec<_>>();
+    idxs.sort();
+    idxs.dedup();
+    for i in idxs {
+        out.push(objs[i].0.clone());
+        if single {
+            break;
+        }
+    }
+    out
+}
+
+impl<T> Grid<T>
+where
+    T: Default + Clone,
+{
+    pub fn init(size: CellSize) -> Grid<T> {
+        if let CellSize::Manual(c) = size {
+            assert!(c > 0.);
+        }
+        Grid {
+            _size: size,
+            _cell: 1.,
+            _lower: [0.; 3],
+            _dims: [0; 3],
+            _cells: HashMap::new(),
+            _objs: vec![],
+        }
+    }
+    pub fn get_cell_size(&self) -> f64 {
+        self._cell
+    }
+    ///lower corner of the grid domain
+    pub fn get_origin(&self) -> [f64; 3] {
+        self._lower
+    }
+    ///number of cells along each axis
+    pub fn get_dims(&self) -> [usize; 3] {
+        self._dims
+    }
+    ///number of populated cells
+    pub fn get_cell_count(&self) -> usize {
+        self._cells.len()
+    }
+    ///objects overlapping the cell at given coordinate
+    pub fn get_cell(&self, coord: [usize; 3]) -> Option<Vec<T>> {
+        if (0..3).any(|i| coord[i] >= self._dims[i]) {
+            return None;
+        }
+        Some(match self._cells.get(&coord) {
+            Some(v) => v.iter().map(|i| self._objs[*i].0.clone()).collect(),
+            _ => vec![],
+        })
+    }
+    ///inclusive range of cell coordinates overlapped by bound, clamped to the grid domain
+    fn cell_range(&self, b: &[f64]) -> Option<([usize; 3], [usize; 3])> {
+        let mut lo = [0usize; 3];
+        let mut hi = [0usize; 3];
+        for i in 0..3 {
+            let l = ((b[i] - self._lower[i]) / self._cell).floor();
+            let h = ((b[i + 3] - self._lower[i]) / self._cell).floor();
+            if h < 0. || l >= self._dims[i] as f64 || l.is_nan() || h.is_nan() {
+                return None;
+            }
+            lo[i] = l.max(0.) as usize;
+            hi[i] = h.min(self._dims[i] as f64 - 1.) as usize;
+        }
+        Some((lo, hi))
+    }
+    ///visits cells pierced by the ray in order up to distance t_max using 3D-DDA (Amanatides-Woo),
+    ///stopping and returning the current cell when f returns true
+    pub fn traverse_ray<F>(
+        &self,
+        r: &Ray3,
+        t_max: f64,
+        mut f: F,
+    ) -> Result<Option<CellHit>, &'static str>
+    where
+        F: FnMut(&CellHit) -> bool,
+    {
+        if t_max.is_nan() || t_max < 0. {
+            return Err("ray traversal distance must be non-negative");
+        }
+        if self._objs.is_empty() {
+            return Ok(None);
+        }
+
+        let o = [r._ori[0], r._ori[1], r._ori[2]];
+        let d = [r._dir[0], r._dir[1], r._dir[2]];
+
+        //clip the ray against the grid domain
+        let mut t_enter = 0f64;
+        let mut t_leave = t_max;
+        let mut axis_enter = None;
+        for i in 0..3 {
+            let lower = self._lower[i];
+            let upper = self._lower[i] + self._dims[i] as f64 * self._cell;
+            if d[i] == 0. {
+                if o[i] < lower || o[i] >= upper {
+                    return Ok(None);
+                }
+                continue;
+            }
+            let mut t0 = (lower - o[i]) / d[i];
+            let mut t1 = (upper - o[i]) / d[i];
+            if t0 > t1 {
+                ::std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > t_enter {
+                t_enter = t0;
+                axis_enter = Some(i);
+            }
+            t_leave = t_leave.min(t1);
+        }
+        if t_enter > t_leave {
+            return Ok(None);
+        }
+
+        let mut coord = [0usize; 3];
+        let mut step = [0i64; 3];
+        let mut t_next = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for i in 0..3 {
+            let p = o[i] + d[i] * t_enter;
+            let c = ((p - self._lower[i]) / self._cell).floor();
+            let c = c.max(0.).min(self._dims[i] as f64 - 1.) as usize;
+            //snap to the boundary cell along the entry axis to avoid rounding off the domain
+            let c = match axis_enter {
+                Some(a) if a == i && d[i] < 0. => self._dims[i] - 1,
+                Some(a) if a == i => 0,
+                _ => c,
+            };
+            coord[i] = c;
+            if d[i] > 0. {
+                step[i] = 1;
+                t_delta[i] = self._cell / d[i];
+                t_next[i] = (self._lower[i] + (c + 1) as f64 * self._cell - o[i]) / d[i];
+            } else if d[i] < 0. {
+                step[i] = -1;
+                t_delta[i] = -self._cell / d[i];
+                t_next[i] = (self._lower[i] + c as f64 * self._cell - o[i]) / d[i];
+            }
+        }
+
+        let mut normal = [0f64; 3];
+        if let Some(a) = axis_enter {
+            normal[a] = -(step[a] as f64);
+        }
+
+        let mut t_cur = t_enter;
+        loop {
+            let axis = if t_next[0] <= t_next[1] && t_next[0] <= t_next[2] {
+                0
+            } else if t_next[1] <= t_next[2] {
+                1
+            } else {
+                2
+            };
+            let hit = CellHit {
+                _coord: coord,
+                _t_entry: t_cur,
+                _t_exit: t_next[axis].min(t_leave),
+                _normal: normal,
+            };
+            if f(&hit) {
+                return Ok(Some(hit));
+            }
+            if t_next[axis] >= t_leave {
+                return Ok(None);
+            }
+            let c = coord[axis] as i64 + step[axis];
+            if c < 0 || c >= self._dims[axis] as i64 {
+                return Ok(None);
+            }
+            coord[axis] = c as usize;
+            t_cur = t_next[axis];
+            t_next[axis] += t_delta[axis];
+            normal = [0f64; 3];
+            normal[axis] = -(step[axis] as f64);
+        }
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let (lo, hi) = match self.cell_range(&input.get_bound_data()[0..6]) {
+            Some(x) => x,
+            _ => return Ok(vec![]),
+        };
+        let mut idxs = vec![];
+        let count = (0..3).fold(1usize, |acc, i| acc.saturating_mul(hi[i] - lo[i] + 1));
+        if count > self._cells.len() {
+            //query spans more cells than are populated, scan populated cells instead
+            for (k, v) in self._cells.iter() {
+                if (0..3).all(|i| lo[i] <= k[i] && k[i] <= hi[i]) {
+                    idxs.extend_from_slice(&v[..]);
+                }
+            }
+        } else {
+            for z in lo[2]..=hi[2] {
+                for y in lo[1]..=hi[1] {
+                    for x in lo[0]..=hi[0] {
+                        if let Some(v) = self._cells.get(&[x, y, z]) {
+                            idxs.extend_from_slice(&v[..]);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(collect_unique(
+            &self._objs[..],
+            idxs.into_iter(),
+            input,
+            single,
+        ))
+    }
+}
+
+impl<T> ISpatialAccel<T> for Grid<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        check_bounds(objs)?;
+
+        let b = objs.iter().map(|x| x.1).collect::<Vec<&dyn IBound>>();
+        let cell = resolve_cell_size(&self._size, &b[..])?;
+
+        self._cells.clear();
+        self._objs.clear();
+        self._dims = [0; 3];
+        self._cell = cell;
+
+        if objs.is_empty() {
+            return Ok(());
+        }
+
+        let mut u: AxisAlignedBBox = Default::default();
+        u.get_union(&b[..]);
+
+        let mut count = 1usize;
+        for i in 0..3 {
+            let extent = u._bound_upper[i] - u._bound_lower[i];
+            if !extent.is_finite() {
+                return Err("grid requires finite bounds");
+            }
+            let n = ((extent / cell).floor() as usize).saturating_add(1);
+            self._dims[i] = n;
+            count = count.saturating_mul(n);
+        }
+        if count > MAX_CELLS {
+            self._dims = [0; 3];
+            return Err("grid cell count too large");
+        }
+
+        self._lower = u._bound_lower;
+        for (idx, i) in objs.iter().enumerate() {
+            let d = i.1.get_bound_data();
+            let (lo, hi) = self.cell_range(&d[0..6]).unwrap();
+            for z in lo[2]..=hi[2] {
+                for y in lo[1]..=hi[1] {
+                    for x in lo[0]..=hi[0] {
+                        self._cells.entry([x, y, z]).or_default().push(idx);
+                    }
+                }
+            }
+            self._objs.push((i.0.clone(), bound_copy(i.1)));
+        }
+        Ok(())
+    }
+}
+
+impl<T> GridHash<T>
+where
+    T: Default + Clone,
+{
+    pub fn init(size: CellSize) -> GridHash<T> {
+        if let CellSize::Manual(c) = size {
+            assert!(c > 0.);
+        }
+        GridHash {
+            _size: size,
+            _cell: 1.,
+            _cells: HashMap::new(),
+            _objs: vec![],
+        }
+    }
+    pub fn get_cell_size(&self) -> f64 {
+        self._cell
+    }
+    ///number of populated cells
+    pub fn get_cell_count(&self) -> usize {
+        self._cells.len()
+    }
+    fn cell_coord(&self, v: f64) -> i64 {
+        (v / self._cell).floor() as i64
+    }
+    ///number of cells in the inclusive coordinate range, saturating on overflow
+    fn range_count(lo: &[i64; 3], hi: &[i64; 3]) -> u64 {
+        (0..3).fold(1u64, |acc, i| {
+            let n = (hi[i] as i128 - lo[i] as i128 + 1).max(0) as u64;
+            acc.saturating_mul(n)
+        })
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let d = input.get_bound_data();
+        let lo = [
+            self.cell_coord(d[0]),
+            self.cell_coord(d[1]),
+            self.cell_coord(d[2]),
+        ];
+        let hi = [
+            self.cell_coord(d[3]),
+            self.cell_coord(d[4]),
+            self.cell_coord(d[5]),
+        ];
+        let mut idxs = vec![];
+        if GridHash::<T>::range_count(&lo, &hi) > self._cells.len() as u64 {
+            //query spans more cells than are populated, scan populated cells instead
+            for (k, v) in self._cells.iter() {
+                if (0..3).all(|i| lo[i] <= k[i] && k[i] <= hi[i]) {
+                    idxs.extend_from_slice(&v[..]);
+                }
+            }
+        } else {
+            for z in lo[2]..=hi[2] {
+                for y in lo[1]..=hi[1] {
+                    for x in lo[0]..=hi[0] {
+                        if let Some(v) = self._cells.get(&[x, y, z]) {
+                            idxs.extend_from_slice(&v[..]);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(collect_unique(
+            &self._objs[..],
+            idxs.into_iter(),
+            input,
+            single,
+        ))
+    }
+}
+
+impl<T> ISpatialAccel<T> for GridHash<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        check_bounds(objs)?;
+
+        let b = objs.iter().map(|x| x.1).collect::<Vec<&dyn IBound>>();
+        let cell = resolve_cell_size(&self._size, &b[..])?;
+
+        self._cells.clear();
+        self._objs.clear();
+        self._cell = cell;
+
+        for (idx, i) in objs.iter().enumerate() {
+            let d = i.1.get_bound_data();
+            if (0..6).any(|x| !d[x].is_finite()) {
+                self._cells.clear();
+                self._objs.clear();
+                return Err("grid requires finite bounds");
+            }
+            let lo = [
+                self.cell_coord(d[0]),
+                self.cell_coord(d[1]),
+                self.cell_coord(d[2]),
+            ];
+            let hi = [
+                self.cell_coord(d[3]),
+                self.cell_coord(d[4]),
+                self.cell_coord(d[5]),
+            ];
+            if GridHash::<T>::range_count(&lo, &hi) > MAX_CELLS as u64 {
+                self._cells.clear();
+                self._objs.clear();
+                return Err("grid cell count too large");
+            }
+            for z in lo[2]..=hi[2] {
+                for y in lo[1]..=hi[1] {
+                    for x in lo[0]..=hi[0] {
+                        self._cells.entry([x, y, z]).or_default().push(idx);
+                    }
+                }
+            }
+            self._objs.push((i.0.clone(), bound_copy(i.1)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::collections::{BTreeMap, HashMap};
+use std::f64;
+
+use implement::bound_util::bound_copy;
+use implement::grid::CellSize;
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// implementation of spatial acceleration using a hierarchy of hashed grids with doubling cell sizes,
+/// where each object is stored in the level whose cell size matches its extent
+pub struct GridHierarchy<T>
+where
+    T: Default + Clone,
+{
+    _size: CellSize,
+    _cell: f64, //cell size of level 0
+    _levels: BTreeMap<u32, LevelGrid>,
+    _objs: Vec<Option<ObjGrid<T>>>,
+    _free: Vec<usize>,
+}
+
+///populated cells of a single level
+struct LevelGrid {
+    _cells: HashMap<[i64; 3], Vec<usize>>,
+    _count: usize,
+}
+
+///object stored in the hierarchy
+struct ObjGrid<T> {
+    _obj: T,
+    _bound: AxisAlignedBBox,
+    _level: u32,
+}
+
+///levels beyond this are clamped, giving a largest cell of 2^63 base cells
+const MAX_LEVEL: u32 = 63;
+
+fn bound_extent(b: &AxisAlignedBBox) -> f64 {
+    (0..3).fold(0f64, |acc, i| {
+        acc.max(b._bound_upper[i] - b._bound_lower[i])
+    })
+}
+
+fn cell_coord(v: f64, cell: f64) -> i64 {
+    (v / cell).floor() as i64
+}
+
+///inclusive range of cells at given cell size overlapped by bound data
+fn cell_range(d: &[f64], cell: f64) -> ([i64; 3], [i64; 3]) {
+    (
+        [
+            cell_coord(d[0], cell),
+            cell_coord(d[1], cell),
+            cell_coord(d[2], cell),
+        ],
+        [
+            cell_coord(d[3], cell),
+            cell_coord(d[4], cell),
+            cell_coord(d[5], cell),
+        ],
+    )
+}
+
+impl LevelGrid {
+    fn cells_in_range(&self, lo: &[i64; 3], hi: &[i64; 3], out: &mut Vec<usize>) {
+        let count = (0..3).fold(1u64, |acc, i| {
+            let n = (hi[i] as i128 - lo[i] as i128 + 1).max(0) as u64;
+            acc.saturating_mul(n)
+        });
+        if count > self._cells.len() as u64 {
+            //query spans more cells than are populated, scan populated cells instead
+            for (k, v) in self._cells.iter() {
+                if (0..3).all(|i| lo[i] <= k[i] && k[i] <= hi[i]) {
+                    out.extend_from_slice(&v[..]);
+                }
+            }
+        } else {
+            for z in lo[2]..=hi[2] {
+                for y in lo[1]..=hi[1] {
+                    for x in lo[0]..=hi[0] {
+                        if let Some(v) = self._cells.get(&[x, y, z]) {
+                            out.extend_from_slice(&v[..]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> GridHierarchy<T>
+where
+    T: Default + Clone,
+{
+    ///size gives the cell size of the finest level, or derives it from the smallest object at build time
+    pub fn init(size: CellSize) -> GridHierarchy<T> {
+        let cell = match size {
+            CellSize::Manual(c) => {
+                assert!(c > 0.);
+                c
+            }
+            CellSize::Auto => 1.,
+        };
+        GridHierarchy {
+            _size: size,
+            _cell: cell,
+            _levels: BTreeMap::new(),
+            _objs: vec![],
+            _free: vec![],
+        }
+    }
+    pub fn get_cell_size(&self, level: u32) -> f64 {
+        self._cell * 2f64.powi(level as i32)
+    }
+    ///levels currently holding objects
+    pub fn get_levels(&self) -> Vec<u32> {
+        self._levels.keys().cloned().collect()
+    }
+    ///number of objects stored
+    pub fn len(&self) -> usize {
+        self._objs.len() - self._free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    ///level whose cells are at least as large as the object
+    fn level_for(&self, b: &AxisAlignedBBox) -> u32 {
+        let ratio = bound_extent(b) / self._cell;
+        if ratio <= 1. {
+            0
+        } else {
+            (ratio.log2().ceil() as u32).min(MAX_LEVEL)
+        }
+    }
+    fn link(&mut self, handle: usize) {
+        let (lo, hi, level) = {
+            let o = self._objs[handle].as_ref().unwrap();
+            let d = o._bound.get_bound_data();
+            let (lo, hi) = cell_range(&d[0..6], self.get_cell_size(o._level));
+            (lo, hi, o._level)
+        };
+        let g = self._levels.entry(level).or_insert_with(|| LevelGrid {
+            _cells: HashMap::new(),
+            _count: 0,
+        });
+        for z in lo[2]..=hi[2] {
+            for y in lo[1]..=hi[1] {
+                for x in lo[0]..=hi[0] {
+                    g._cells.entry([x, y, z]).or_default().push(handle);
+                }
+            }
+        }
+        g._count += 1;
+    }
+    fn unlink(&mut self, handle: usize) {
+        let (lo, hi, level) = {
+            let o = self._objs[handle].as_ref().unwrap();
+            let d = o._bound.get_bound_data();
+            let (lo, hi) = cell_range(&d[0..6], self.get_cell_size(o._level));
+            (lo, hi, o._level)
+        };
+        let empty = match self._levels.get_mut(&level) {
+            Some(g) => {
+                for z in lo[2]..=hi[2] {
+                    for y in lo[1]..=hi[1] {
+                        for x in lo[0]..=hi[0] {
+                            let remove_cell = match g._cells.get_mut(&[x, y, z]) {
+                                Some(v) => {
+                                    v.retain(|h| *h != handle);
+                                    v.is_empty()
+                                }
+                                _ => false,
+                            };
+                            if remove_cell {
+                                g._cells.remove(&[x, y, z]);
+                            }
+                        }
+                    }
+                }
+                g._count -= 1;
+                g._count == 0
+            }
+            _ => false,
+        };
+        if empty {
+            self._levels.remove(&level);
+        }
+    }
+    fn check_bound(b: &dyn IBound) -> Result<AxisAlignedBBox, &'static str> {
+        match b.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let a = bound_copy(b);
+        if (0..3).any(|i| !a._bound_lower[i].is_finite() || !a._bound_upper[i].is_finite()) {
+            return Err("grid requires finite bounds");
+        }
+        Ok(a)
+    }
+    ///add an object and return its handle
+    pub fn insert(&mut self, obj: T, b: &dyn IBound) -> Result<usize, &'static str> {
+        let bound = GridHierarchy::<T>::check_bound(b)?;
+        let o = ObjGrid {
+            _obj: obj,
+            _level: self.level_for(&bound),
+            _bound: bound,
+        };
+        let handle = match self._free.pop() {
+            Some(h) => {
+                self._objs[h] = Some(o);
+                h
+            }
+            _ => {
+                self._objs.push(Some(o));
+                self._objs.len() - 1
+            }
+        };
+        self.link(handle);
+        Ok(handle)
+    }
+    ///remove an object by handle and return it
+    pub fn remove(&mut self, handle: usize) -> Result<T, &'static str> {
+        match self._objs.get(handle) {
+            Some(&Some(_)) => (),
+            _ => return Err("invalid handle"),
+        }
+        self.unlink(handle);
+        self._free.push(handle);
+        Ok(self._objs[handle].take().unwrap()._obj)
+    }
+    ///move an object to a new bound, changing level when its extent changes
+    pub fn update(&mut self, handle: usize, b: &dyn IBound) -> Result<(), &'static str> {
+        let bound = GridHierarchy::<T>::check_bound(b)?;
+        match self._objs.get(handle) {
+            Some(&Some(_)) => (),
+            _ => return Err("invalid handle"),
+        }
+        self.unlink(handle);
+        let level = self.level_for(&bound);
+        {
+            let o = self._objs[handle].as_mut().unwrap();
+            o._bound = bound;
+            o._level = level;
+        }
+        self.link(handle);
+        Ok(())
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let d = input.get_bound_data();
+        let mut handles = vec![];
+        for (level, g) in self._levels.iter() {
+            let (lo, hi) = cell_range(&d[0..6], self.get_cell_size(*level));
+            g.cells_in_range(&lo, &hi, &mut handles);
+        }
+        handles.sort();
+        handles.dedup();
+        let mut out = vec![];
+        for h in handles {
+            let o = self._objs[h].as_ref().unwrap();
+            if o._bound.intersect(input) {
+                out.push(o._obj.clone());
+                if single {
+                    break;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl<T> ISpatialAccel<T> for GridHierarchy<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    ///replaces content of the hierarchy, handles of the objects follow input order
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        let mut bounds = vec![];
+        for i in objs {
+            bounds.push(GridHierarchy::<T>::check_bound(i.1)?);
+        }
+
+        self._cell = match self._size {
+            CellSize::Manual(c) => c,
+            CellSize::Auto => {
+                let e = bounds
+                    .iter()
+                    .map(bound_extent)
+                    .filter(|x| *x > 0.)
+                    .fold(f64::INFINITY, f64::min);
+                if e.is_finite() {
+                    e
+                } else {
+                    1.
+                }
+            }
+        };
+        self._levels.clear();
+        self._objs.clear();
+        self._free.clear();
+
+        for (i, b) in objs.iter().zip(bounds.iter()) {
+            self.insert(i.0.clone(), b)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+use std::cmp::Ordering;
+use std::f64;
+
+/// AVL balanced interval tree over closed 1D intervals, each node keeps the largest interval end
+/// in its subtree so that queries skip subtrees ending before the query
+pub struct IntervalTree<T>
+where
+    T: Default + Clone,
+{
+    _nodes: Vec<Option<NodeInterval<T>>>, //indexed by handle
+    _free: Vec<usize>,
+    _root: Option<usize>,
+}
+
+struct NodeInterval<T> {
+    _lo: f64,
+    _hi: f64,
+    _obj: T,
+    _max: f64, //largest end in subtree
+    _height: u32,
+    _left: Option<usize>,
+    _right: Option<usize>,
+}
+
+impl<T> Default for IntervalTree<T>
+where
+    T: Default + Clone,
+{
+    fn default() -> IntervalTree<T> {
+        IntervalTree::init()
+    }
+}
+
+fn check_interval(lo: f64, hi: f64) -> Result<(), &'static str> {
+    if lo.is_nan() || hi.is_nan() || lo > hi {
+        return Err("invalid interval");
+    }
+    Ok(())
+}
+
+impl<T> IntervalTree<T>
+where
+    T: Default + Clone,
+{
+    pub fn init() -> IntervalTree<T> {
+        IntervalTree {
+            _nodes: vec![],
+            _free: vec![],
+            _root: None,
+        }
+    }
+    ///number of intervals stored
+    pub fn len(&self) -> usize {
+        self._nodes.len() - self._free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    ///number of nodes on the longest path from the root
+    pub fn height(&self) -> u32 {
+        self.height_of(self._root)
+    }
+    pub fn get(&self, handle: usize) -> Option<&T> {
+        match self._nodes.get(handle) {
+            Some(Some(n)) => Some(&n._obj),
+            _ => None,
+        }
+    }
+    pub fn get_interval(&self, handle: usize) -> Option<(f64, f64)> {
+        match self._nodes.get(handle) {
+            Some(Some(n)) => Some((n._lo, n._hi)),
+            _ => None,
+        }
+    }
+    fn node(&self, n: usize) -> &NodeInterval<T> {
+        self._nodes[n].as_ref().unwrap()
+    }
+    fn node_mut(&mut self, n: usize) -> &mut NodeInterval<T> {
+        self._nodes[n].as_mut().unwrap()
+    }
+    fn height_of(&self, n: Option<usize>) -> u32 {
+        n.map_or(0, |x| self.node(x)._height)
+    }
+    fn max_of(&self, n: Option<usize>) -> f64 {
+        n.map_or(f64::NEG_INFINITY, |x| self.node(x)._max)
+    }
+    ///ordering of nodes by start, then end, then handle
+    fn cmp_key(&self, a: usize, b: usize) -> Ordering {
+        let x = self.node(a);
+        let y = self.node(b);
+        x._lo
+            .partial_cmp(&y._lo)
+            .unwrap_or(Ordering::Equal)
+            .then(x._hi.partial_cmp(&y._hi).unwrap_or(Ordering::Equal))
+            .then(a.cmp(&b))
+    }
+    fn refresh(&mut self, n: usize) {
+        let (l, r) = (self.node(n)._left, self.node(n)._right);
+        let h = self.height_of(l).max(self.height_of(r)) + 1;
+        let m = self.node(n)._hi.max(self.max_of(l)).max(self.max_of(r));
+        let x = self.node_mut(n);
+        x._height = h;
+        x._max = m;
+    }
+    fn rotate_right(&mut self, n: usize) -> usize {
+        let l = self.node(n)._left.unwrap();
+        self.node_mut(n)._left = self.node(l)._right;
+        self.node_mut(l)._right = Some(n);
+        self.refresh(n);
+        self.refresh(l);
+        l
+    }
+    fn rotate_left(&mut self, n: usize) -> usize {
+        let r = self.node(n)._right.unwrap();
+        self.node_mut(n)._right = self.node(r)._left;
+        self.node_mut(r)._left = Some(n);
+        self.refresh(n);
+        self.refresh(r);
+        r
+    }
+    ///restores the AVL property at n and returns the new subtree root
+    fn rebalance(&mut self, n: usize) -> usize {
+        self.refresh(n);
+        let (l, r) = (self.node(n)._left, self.node(n)._right);
+        let balance = self.height_of(l) as i64 - self.height_of(r) as i64;
+        if balance > 1 {
+            let l = l.unwrap();
+            if self.height_of(self.node(l)._left) < self.height_of(self.node(l)._right) {
+                let c = self.rotate_left(l);
+                self.node_mut(n)._left = Some(c);
+            }
+            self.rotate_right(n)
+        } else if balance < -1 {
+            let r = r.unwrap();
+            if self.height_of(self.node(r)._right) < self.height_of(self.node(r)._left) {
+                let c = self.rotate_right(r);
+                self.node_mut(n)._right = Some(c);
+            }
+            self.rotate_left(n)
+        } else {
+            n
+        }
+    }
+    fn insert_node(&mut self, root: Option<usize>, n: usize) -> usize {
+        let r = match root {
+            Some(r) => r,
+            _ => return n,
+        };
+        if self.cmp_key(n, r) == Ordering::Less {
+            let c = self.insert_node(self.node(r)._left, n);
+            self.node_mut(r)._left = Some(c);
+        } else {
+            let c = self.insert_node(self.node(r)._right, n);
+            self.node_mut(r)._right = Some(c);
+        }
+        self.rebalance(r)
+    }
+    ///detaches the smallest node of the subtree, returns the new subtree root and that node
+    fn remove_min(&mut self, root: usize) -> (Option<usize>, usize) {
+        match self.node(root)._left {
+            Some(l) => {
+                let (c, m) = self.remove_min(l);
+                self.node_mut(root)._left = c;
+                (Some(self.rebalance(root)), m)
+            }
+            _ => (self.node(root)._right, root),
+        }
+    }
+    fn remove_node(&mut self, root: Option<usize>, n: usize) -> Option<usize> {
+        let r = root?;
+        match self.cmp_key(n, r) {
+            Ordering::Less => {
+                let c = self.remove_node(self.node(r)._left, n);
+                self.node_mut(r)._left = c;
+            }
+            Ordering::Greater => {
+                let c = self.remove_node(self.node(r)._right, n);
+                self.node_mut(r)._right = c;
+            }
+            Ordering::Equal => {
+                let (l, rr) = (self.node(r)._left, self.node(r)._right);
+                //nodes are relinked rather than swapped so that handles stay valid
+                let s = match rr {
+                    Some(rr) => {
+                        let (c, s) = self.remove_min(rr);
+                        self.node_mut(s)._right = c;
+                        self.node_mut(s)._left = l;
+                        s
+                    }
+                    _ => return l,
+                };
+                return Some(self.rebalance(s));
+            }
+        }
+        Some(self.rebalance(r))
+    }
+    fn alloc(&mut self, lo: f64, hi: f64, obj: T) -> usize {
+        let n = NodeInterval {
+            _lo: lo,
+            _hi: hi,
+            _obj: obj,
+            _max: hi,
+            _height: 1,
+            _left: None,
+            _right: None,
+        };
+        match self._free.pop() {
+            Some(h) => {
+                self._nodes[h] = Some(n);
+                h
+            }
+            _ => {
+                self._nodes.push(Some(n));
+                self._nodes.len() - 1
+            }
+        }
+    }
+    ///add the closed interval [lo, hi] and return its handle
+    pub fn insert(&mut self, lo: f64, hi: f64, obj: T) -> Result<usize, &'static str> {
+        check_interval(lo, hi)?;
+        let h = self.alloc(lo, hi, obj);
+        let root = self._root;
+        self._root = Some(self.insert_node(root, h));
+        Ok(h)
+    }
+    ///remove an interval by handle and return its object
+    pub fn remove(&mut self, handle: usize) -> Result<T, &'static str> {
+        match self._nodes.get(handle) {
+            Some(Some(_)) => (),
+            _ => return Err("invalid handle"),
+        }
+        let root = self._root;
+        self._root = self.remove_node(root, handle);
+        self._free.push(handle);
+        Ok(self._nodes[handle].take().unwrap()._obj)
+    }
+    ///links the sorted handles into a balanced subtree and returns its root
+    fn build_node(&mut self, sorted: &[usize]) -> Option<usize> {
+        if sorted.is_empty() {
+            return None;
+        }
+        let mid = sorted.len() / 2;
+        let n = sorted[mid];
+        let l = self.build_node(&sorted[..mid]);
+        let r = self.build_node(&sorted[mid + 1..]);
+        self.node_mut(n)._left = l;
+        self.node_mut(n)._right = r;
+        self.refresh(n);
+        Some(n)
+    }
+    ///replaces content with the given intervals, handles follow input order
+    pub fn build_all(&mut self, objs: &[(T, f64, f64)]) -> Result<(), &'static str> {
+        for i in objs {
+            check_interval(i.1, i.2)?;
+        }
+        *self = IntervalTree::init();
+        for i in objs {
+            self.alloc(i.1, i.2, i.0.clone());
+        }
+        let mut sorted = (0..objs.len()).collect::<Vec<_>>();
+        sorted.sort_by(|a, b| self.cmp_key(*a, *b));
+        self._root = self.build_node(&sorted[..]);
+        Ok(())
+    }
+    ///visits intervals overlapping [lo, hi] in order of start until f returns true
+    fn search<F>(&self, n: Option<usize>, lo: f64, hi: f64, f: &mut F) -> bool
+    where
+        F: FnMut(usize) -> bool,
+    {
+        let n = match n {
+            Some(n) => n,
+            _ => return false,
+        };
+        let node = self.node(n);
+        if node._max < lo {
+            return false;
+        }
+        if self.search(node._left, lo, hi, f) {
+            return true;
+        }
+        //starts of the right subtree are at least this start
+        if node._lo > hi {
+            return false;
+        }
+        if node._hi >= lo && f(n) {
+            return true;
+        }
+        self.search(node._right, lo, hi, f)
+    }
+    fn query(&self, lo: f64, hi: f64, single: bool) -> Result<Vec<T>, &'static str> {
+        check_interval(lo, hi)?;
+        let mut out = vec![];
+        self.search(self._root, lo, hi, &mut |n| {
+            out.push(self.node(n)._obj.clone());
+            single
+        });
+        Ok(out)
+    }
+    ///objects whose intervals overlap [lo, hi], in order of interval start
+    pub fn query_overlap(&self, lo: f64, hi: f64) -> Result<Vec<T>, &'static str> {
+        self.query(lo, hi, false)
+    }
+    pub fn query_overlap_single(&self, lo: f64, hi: f64) -> Result<Vec<T>, &'static str> {
+        self.query(lo, hi, true)
+    }
+    ///objects whose intervals contain x, in order of interval start
+    pub fn query_stab(&self, x: f64) -> Result<Vec<T>, &'static str> {
+        self.query(x, x, false)
+    }
+    ///handles of intervals overlapping [lo, hi], in order of interval start
+    pub fn query_overlap_handles(&self, lo: f64, hi: f64) -> Result<Vec<usize>, &'static str> {
+        check_interval(lo, hi)?;
+        let mut out = vec![];
+        self.search(self._root, lo, hi, &mut |n| {
+            out.push(n);
+            false
+        });
+        Ok(out)
+    }
+}
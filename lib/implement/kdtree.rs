@@ -0,0 +1,257 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64;
+
+use implement::bound_util::Dist;
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// implementation of spatial acceleration for point data using a balanced k-d tree
+///
+/// nodes are stored implicitly: the point at the median of each index range is the node
+/// splitting the range, with the lower and upper halves as its subtrees
+pub struct KdTree<T>
+where
+    T: Default + Clone,
+{
+    _points: Vec<NodeKdTree<T>>,
+}
+
+///point of the tree together with the axis it splits its subtree on
+pub struct NodeKdTree<T>
+where
+    T: Default + Clone,
+{
+    _point: [f64; 3],
+    _obj: T,
+    _axis: usize,
+}
+
+fn dist_sq(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).fold(0., |acc, i| acc + (a[i] - b[i]) * (a[i] - b[i]))
+}
+
+impl<T> Default for KdTree<T>
+where
+    T: Default + Clone,
+{
+    fn default() -> KdTree<T> {
+        KdTree::init()
+    }
+}
+
+impl<T> KdTree<T>
+where
+    T: Default + Clone,
+{
+    pub fn init() -> KdTree<T> {
+        KdTree { _points: vec![] }
+    }
+    ///number of points stored
+    pub fn len(&self) -> usize {
+        self._points.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._points.is_empty()
+    }
+    ///replaces content of the tree with the given points
+    pub fn build(&mut self, points: &[(T, [f64; 3])]) -> Result<(), &'static str> {
+        if points.iter().any(|x| x.1.iter().any(|v| !v.is_finite())) {
+            return Err("kdtree requires finite points");
+        }
+        self._points = points
+            .iter()
+            .map(|x| NodeKdTree {
+                _point: x.1,
+                _obj: x.0.clone(),
+                _axis: 0,
+            })
+            .collect();
+        let n = self._points.len();
+        KdTree::build_range(&mut self._points[0..n]);
+        Ok(())
+    }
+    ///splits the range at the median along the axis of largest spread and recurses on both halves
+    fn build_range(pts: &mut [NodeKdTree<T>]) {
+        if pts.is_empty() {
+            return;
+        }
+        let mut lower = [f64::INFINITY; 3];
+        let mut upper = [f64::NEG_INFINITY; 3];
+        for p in pts.iter() {
+            for i in 0..3 {
+                lower[i] = lower[i].min(p._point[i]);
+                upper[i] = upper[i].max(p._point[i]);
+            }
+        }
+        let axis = (0..3).fold(0, |acc, i| {
+            if upper[i] - lower[i] > upper[acc] - lower[acc] {
+                i
+            } else {
+                acc
+            }
+        });
+        let half = pts.len() / 2;
+        pts.select_nth_unstable_by(half, |a, b| {
+            a._point[axis]
+                .partial_cmp(&b._point[axis])
+                .unwrap_or(Ordering::Equal)
+        });
+        pts[half]._axis = axis;
+        let (l, r) = pts.split_at_mut(half);
+        KdTree::build_range(l);
+        KdTree::build_range(&mut r[1..]);
+    }
+    ///k points closest to p with their distances in increasing order
+    pub fn query_nearest(&self, p: &[f64; 3], k: usize) -> Vec<(T, f64)> {
+        self.query_nearest_approx(p, k, 0.)
+    }
+    ///approximate k nearest points, each reported distance is within a factor of (1+eps) of the
+    ///distance of the true neighbour at the same rank
+    pub fn query_nearest_approx(&self, p: &[f64; 3], k: usize, eps: f64) -> Vec<(T, f64)> {
+        assert!(eps >= 0.);
+        let mut best = BinaryHeap::new();
+        if k > 0 {
+            let scale = (1. + eps) * (1. + eps);
+            self.nearest(0, self._points.len(), p, k, scale, &mut best);
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|x| (self._points[x.1]._obj.clone(), x.0.sqrt()))
+            .collect()
+    }
+    fn nearest(
+        &self,
+        lo: usize,
+        hi: usize,
+        p: &[f64; 3],
+        k: usize,
+        scale: f64,
+        best: &mut BinaryHeap<Dist>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let n = &self._points[mid];
+        let d = dist_sq(p, &n._point);
+        if best.len() < k {
+            best.push(Dist(d, mid));
+        } else if d < best.peek().unwrap().0 {
+            best.pop();
+            best.push(Dist(d, mid));
+        }
+        let diff = p[n._axis] - n._point[n._axis];
+        let (near, far) = if diff < 0. {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.nearest(near.0, near.1, p, k, scale, best);
+        if best.len() < k || diff * diff * scale < best.peek().unwrap().0 {
+            self.nearest(far.0, far.1, p, k, scale, best);
+        }
+    }
+    ///points within distance r of p with their distances in increasing order
+    pub fn query_radius(&self, p: &[f64; 3], r: f64) -> Vec<(T, f64)> {
+        let mut out = vec![];
+        self.radius(0, self._points.len(), p, r * r, &mut out);
+        out.sort();
+        out.into_iter()
+            .map(|x| (self._points[x.1]._obj.clone(), x.0.sqrt()))
+            .collect()
+    }
+    fn radius(&self, lo: usize, hi: usize, p: &[f64; 3], r_sq: f64, out: &mut Vec<Dist>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let n = &self._points[mid];
+        let d = dist_sq(p, &n._point);
+        if d <= r_sq {
+            out.push(Dist(d, mid));
+        }
+        let diff = p[n._axis] - n._point[n._axis];
+        if diff <= 0. || diff * diff <= r_sq {
+            self.radius(lo, mid, p, r_sq, out);
+        }
+        if diff >= 0. || diff * diff <= r_sq {
+            self.radius(mid + 1, hi, p, r_sq, out);
+        }
+    }
+    ///points inside or on the boundary of the box
+    pub fn query_box(&self, b: &AxisAlignedBBox) -> Vec<T> {
+        let mut out = vec![];
+        self.range(0, self._points.len(), b, usize::MAX, &mut out);
+        out
+    }
+    fn range(&self, lo: usize, hi: usize, b: &AxisAlignedBBox, limit: usize, out: &mut Vec<T>) {
+        if lo >= hi || out.len() >= limit {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let n = &self._points[mid];
+        if (0..3).all(|i| b._bound_lower[i] <= n._point[i] && n._point[i] <= b._bound_upper[i]) {
+            out.push(n._obj.clone());
+        }
+        let v = n._point[n._axis];
+        if b._bound_lower[n._axis] <= v {
+            self.range(lo, mid, b, limit, out);
+        }
+        if v <= b._bound_upper[n._axis] {
+            self.range(mid + 1, hi, b, limit, out);
+        }
+    }
+}
+
+impl<T> ISpatialAccel<T> for KdTree<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let d = input.get_bound_data();
+        let b = AxisAlignedBBox {
+            _bound_lower: [d[0], d[1], d[2]],
+            _bound_upper: [d[3], d[4], d[5]],
+        };
+        Ok(self.query_box(&b))
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let d = input.get_bound_data();
+        let b = AxisAlignedBBox {
+            _bound_lower: [d[0], d[1], d[2]],
+            _bound_upper: [d[3], d[4], d[5]],
+        };
+        let mut out = vec![];
+        self.range(0, self._points.len(), &b, 1, &mut out);
+        Ok(out)
+    }
+    ///builds over degenerate point bounds
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        let mut points = vec![];
+        for i in objs {
+            match i.1.get_type() {
+                BoundType::AxisAlignBox => (),
+                _ => return Err("unsupported bound type"),
+            }
+            let d = i.1.get_bound_data();
+            if d[0] != d[3] || d[1] != d[4] || d[2] != d[5] {
+                return Err("kdtree requires point bounds");
+            }
+            points.push((i.0.clone(), [d[0], d[1], d[2]]));
+        }
+        self.build(&points[..])
+    }
+}
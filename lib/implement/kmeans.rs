@@ -0,0 +1,351 @@
+extern crate mazth;
+extern crate rand;
+extern crate rand_chacha;
+
+use self::mazth::i_bound::IBound;
+
+use self::rand::{Rng, SeedableRng};
+use self::rand_chacha::ChaChaRng;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f64;
+
+use implement::cluster_select::{self, centroids, distance, Criterion};
+use interface::i_cluster::{ClusterMethod, ClusterMetric, ClusterOrder, ClusterSize, ICluster};
+
+/// k-means clustering of bound centroids, centres are the means of their members under L2 and
+/// the coordinate-wise medians under L1
+pub struct KMeans {
+    _max_iter: usize,
+    _metric: ClusterMetric,
+    _ids: HashMap<u64, usize>, //object id to input index
+    _points: Vec<[f64; 3]>,
+    _assign: Vec<usize>,
+    _centres: Vec<[f64; 3]>,
+    _iterations: usize,
+    _auto: (usize, usize, Criterion),
+    _scores: Vec<(usize, f64)>,
+    _seed: u64,
+    _sample: usize,
+    _batch: usize, //0 for full Lloyd iterations
+}
+
+///index of the closest centre, ties resolved to the lower index
+fn nearest(metric: ClusterMetric, p: &[f64; 3], centres: &[[f64; 3]]) -> usize {
+    let mut best = (f64::INFINITY, 0);
+    for (i, c) in centres.iter().enumerate() {
+        let d = distance(metric, p, c);
+        if d < best.0 {
+            best = (d, i);
+        }
+    }
+    best.1
+}
+
+fn median(v: &mut [f64]) -> f64 {
+    v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let n = v.len();
+    if n % 2 == 1 {
+        v[n / 2]
+    } else {
+        (v[n / 2 - 1] + v[n / 2]) / 2.
+    }
+}
+
+impl KMeans {
+    ///clustering that stops when assignments no longer change or after max_iter updates,
+    ///ClusterSize::Auto scores k from 2 to 10 by silhouette unless changed with set_auto
+    pub fn init(max_iter: usize) -> KMeans {
+        assert!(max_iter > 0);
+        KMeans {
+            _max_iter: max_iter,
+            _metric: ClusterMetric::L2,
+            _ids: HashMap::new(),
+            _points: vec![],
+            _assign: vec![],
+            _centres: vec![],
+            _iterations: 0,
+            _auto: (2, 10, Criterion::Silhouette),
+            _scores: vec![],
+            _seed: 0,
+            _sample: usize::MAX,
+            _batch: 0,
+        }
+    }
+    ///seed of the ChaCha rng used for PreSample seeding and mini-batches, each fit restarts
+    ///from it so results are reproducible across platforms
+    pub fn set_seed(&mut self, seed: u64) {
+        self._seed = seed;
+    }
+    ///number of objects drawn for k-means++ seeding under PreSample, all objects by default,
+    ///at least k objects are drawn
+    pub fn set_sample_size(&mut self, sample: usize) -> Result<(), &'static str> {
+        if sample == 0 {
+            return Err("sample size must be positive");
+        }
+        self._sample = sample;
+        Ok(())
+    }
+    ///replaces Lloyd iterations with max_iter mini-batch updates of batch objects drawn with
+    ///replacement, centres move towards their batch members at a rate decaying with the
+    ///number of members seen, 0 restores Lloyd iterations, only L2 is supported
+    pub fn set_mini_batch(&mut self, batch: usize) {
+        self._batch = batch;
+    }
+    ///range of k and criterion used for ClusterSize::Auto, Bic is not supported
+    pub fn set_auto(
+        &mut self,
+        k_min: usize,
+        k_max: usize,
+        criterion: Criterion,
+    ) -> Result<(), &'static str> {
+        if k_min == 0 || k_min > k_max {
+            return Err("invalid cluster range");
+        }
+        match criterion {
+            Criterion::Bic => return Err("unsupported criterion"),
+            Criterion::Gap { references: 0, .. } => return Err("gap requires reference sets"),
+            _ => (),
+        }
+        self._auto = (k_min, k_max, criterion);
+        Ok(())
+    }
+    ///k and its score for each cluster count tried by the last ClusterSize::Auto build
+    pub fn get_scores(&self) -> &[(usize, f64)] {
+        &self._scores[..]
+    }
+    pub fn get_centres(&self) -> &[[f64; 3]] {
+        &self._centres[..]
+    }
+    ///number of centre updates done by the last build
+    pub fn get_iterations(&self) -> usize {
+        self._iterations
+    }
+    ///sum over objects of the distance to their centre, squared under L2
+    pub fn get_cost(&self) -> f64 {
+        self._points
+            .iter()
+            .zip(self._assign.iter())
+            .map(|(p, a)| {
+                let d = distance(self._metric, p, &self._centres[*a]);
+                match self._metric {
+                    ClusterMetric::L1 => d,
+                    ClusterMetric::L2 => d * d,
+                }
+            })
+            .sum()
+    }
+    ///centre of each cluster from its members, empty clusters keep their previous centre
+    fn update_centres(&mut self) {
+        let k = self._centres.len();
+        let mut members: Vec<Vec<usize>> = vec![vec![]; k];
+        for (i, a) in self._assign.iter().enumerate() {
+            members[*a].push(i);
+        }
+        let points = &self._points;
+        for (c, m) in self._centres.iter_mut().zip(members.iter()) {
+            if m.is_empty() {
+                continue;
+            }
+            for (axis, v) in c.iter_mut().enumerate() {
+                let mut x = m.iter().map(|i| points[*i][axis]).collect::<Vec<_>>();
+                *v = match self._metric {
+                    ClusterMetric::L1 => median(&mut x[..]),
+                    ClusterMetric::L2 => x.iter().sum::<f64>() / x.len() as f64,
+                };
+            }
+        }
+    }
+    ///clusters the current objects into k clusters seeded in the given order
+    fn fit(&mut self, k: usize, order: ClusterOrder) {
+        let mut rng = ChaChaRng::seed_from_u64(self._seed);
+        self._centres = match order {
+            ClusterOrder::InOrder => self._points[..k].to_vec(),
+            ClusterOrder::PreSample => cluster_select::seed_plus_plus(
+                self._metric,
+                &self._points[..],
+                k,
+                self._sample,
+                &mut rng,
+            ),
+        };
+        if self._batch > 0 {
+            self.iterate_mini_batch(&mut rng);
+        } else {
+            self.iterate();
+        }
+    }
+    ///gap statistic and its standard error for each k in lo..=hi
+    fn gaps(
+        &mut self,
+        lo: usize,
+        hi: usize,
+        order: ClusterOrder,
+        references: usize,
+        seed: u64,
+    ) -> Vec<(f64, f64)> {
+        let mut lower = [f64::INFINITY; 3];
+        let mut upper = [f64::NEG_INFINITY; 3];
+        for p in self._points.iter() {
+            for i in 0..3 {
+                lower[i] = lower[i].min(p[i]);
+                upper[i] = upper[i].max(p[i]);
+            }
+        }
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let mut reference = KMeans::init(self._max_iter);
+        reference._metric = self._metric;
+        reference._seed = self._seed;
+        reference._sample = self._sample;
+        reference._batch = self._batch;
+        let mut log_costs = vec![vec![]; hi - lo + 1];
+        for _ in 0..references {
+            reference._points = (0..self._points.len())
+                .map(|_| {
+                    let mut p = [0.; 3];
+                    for i in 0..3 {
+                        p[i] = if upper[i] > lower[i] {
+                            rng.gen_range(lower[i], upper[i])
+                        } else {
+                            lower[i]
+                        };
+                    }
+                    p
+                })
+                .collect();
+            for (k, l) in (lo..=hi).zip(log_costs.iter_mut()) {
+                reference.fit(k, order);
+                l.push(reference.get_cost().ln());
+            }
+        }
+        let b = references as f64;
+        let mut gaps = vec![];
+        for (k, l) in (lo..=hi).zip(log_costs.iter()) {
+            self.fit(k, order);
+            let mean = l.iter().sum::<f64>() / b;
+            let sd = (l.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / b).sqrt();
+            gaps.push((mean - self.get_cost().ln(), sd * (1. + 1. / b).sqrt()));
+        }
+        gaps
+    }
+    ///mini-batch updates from the current centres followed by assignment of all objects
+    fn iterate_mini_batch(&mut self, rng: &mut ChaChaRng) {
+        let n = self._points.len();
+        let mut counts = vec![0usize; self._centres.len()];
+        self._iterations = 0;
+        while self._iterations < self._max_iter {
+            let batch = (0..self._batch)
+                .map(|_| self._points[rng.gen_range(0, n)])
+                .collect::<Vec<_>>();
+            //members are found before any centre of this batch moves
+            let nearest = batch
+                .iter()
+                .map(|p| nearest(self._metric, p, &self._centres[..]))
+                .collect::<Vec<_>>();
+            for (p, c) in batch.iter().zip(nearest.iter()) {
+                counts[*c] += 1;
+                let rate = 1. / counts[*c] as f64;
+                for (v, x) in self._centres[*c].iter_mut().zip(p.iter()) {
+                    *v += rate * (x - *v);
+                }
+            }
+            self._iterations += 1;
+        }
+        self._assign = self
+            ._points
+            .iter()
+            .map(|p| nearest(self._metric, p, &self._centres[..]))
+            .collect();
+    }
+    ///Lloyd iterations from the current centres
+    fn iterate(&mut self) {
+        self._iterations = 0;
+        self._assign = self
+            ._points
+            .iter()
+            .map(|p| nearest(self._metric, p, &self._centres[..]))
+            .collect();
+        while self._iterations < self._max_iter {
+            self.update_centres();
+            self._iterations += 1;
+            let assign = self
+                ._points
+                .iter()
+                .map(|p| nearest(self._metric, p, &self._centres[..]))
+                .collect::<Vec<_>>();
+            if assign == self._assign {
+                break;
+            }
+            self._assign = assign;
+        }
+    }
+}
+
+impl ICluster for KMeans {
+    fn query_cluster(&self, input: u64) -> Result<u64, &'static str> {
+        match self._ids.get(&input) {
+            Some(i) => Ok(self._assign[*i] as u64),
+            _ => Err("unknown object id"),
+        }
+    }
+    ///clusters are numbered from 0, initial centres are the first k objects for InOrder and
+    ///k-means++ picks over a seeded random sample for PreSample, for Auto each k in the
+    ///configured range is clustered and scored and the best k is kept
+    fn build_all(
+        &mut self,
+        size: ClusterSize,
+        method: ClusterMethod,
+        metric: ClusterMetric,
+        order: ClusterOrder,
+        input: &[(u64, &dyn IBound)],
+    ) -> Result<(), &'static str> {
+        match method {
+            ClusterMethod::Average => (),
+            _ => return Err("unsupported cluster method"),
+        }
+        let (ids, points) = centroids(input)?;
+        let (lo, hi, criterion) = match size {
+            ClusterSize::Manual(0) => return Err("cluster count must be positive"),
+            ClusterSize::Manual(k) if k > input.len() => {
+                return Err("cluster count exceeds object count")
+            }
+            ClusterSize::Manual(k) => (k, k, None),
+            ClusterSize::Auto => {
+                let (lo, hi) = cluster_select::candidates(
+                    self._auto.2,
+                    (self._auto.0, self._auto.1),
+                    &points[..],
+                )?;
+                (lo, hi, Some(self._auto.2))
+            }
+        };
+        if self._batch > 0 && metric != ClusterMetric::L2 {
+            return Err("mini-batch requires L2");
+        }
+        self._metric = metric;
+        self._ids = ids;
+        self._points = points;
+        self._scores = vec![];
+        let best = match criterion {
+            None => lo,
+            Some(Criterion::Gap { references, seed }) => {
+                let gaps = self.gaps(lo, hi, order, references, seed);
+                self._scores = (lo..=hi).zip(gaps.iter().map(|x| x.0)).collect();
+                lo + cluster_select::select_gap(&gaps[..])
+            }
+            Some(_) => {
+                for k in lo..=hi {
+                    self.fit(k, order);
+                    let s =
+                        cluster_select::silhouette(metric, &self._points[..], &self._assign[..], k);
+                    self._scores.push((k, s));
+                }
+                let s = self._scores.iter().map(|x| x.1).collect::<Vec<_>>();
+                lo + cluster_select::select_max(&s[..])
+            }
+        };
+        self.fit(best, order);
+        Ok(())
+    }
+}
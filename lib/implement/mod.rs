@@ -1,2 +1,25 @@
+pub mod balltree;
+pub mod bih;
+pub(crate) mod bound_util;
+pub mod bsp;
 pub mod bvh;
+pub mod bvh_aggregate;
+pub mod bvh_instance;
 pub mod bvh_median;
+pub mod bvh_motion;
+pub mod cluster_select;
+pub mod dbscan;
+pub mod gmm;
+pub mod grid;
+pub mod grid_hierarchy;
+pub mod interval_tree;
+pub mod kdtree;
+pub mod kmeans;
+pub mod octree;
+pub mod octree_loose;
+pub mod rtree;
+pub mod rtree_packed;
+pub mod spacefill;
+pub mod svo;
+pub mod sweep_prune;
+pub mod vptree;
@@ -0,0 +1,426 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::collections::BinaryHeap;
+use std::f64;
+
+use implement::bound_util::{bound_copy, Dist};
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// implementation of spatial acceleration using an octree stored in a flat node array,
+/// objects are kept in the deepest node whose bound fully contains them
+pub struct Octree<T>
+where
+    T: Default + Clone,
+{
+    _nodes: Vec<NodeOctree>,
+    _free_blocks: Vec<usize>,
+    _objs: Vec<Option<ObjOctree<T>>>,
+    _free: Vec<usize>,
+    _max_depth: u32,
+    _max_objs: usize,
+}
+
+///internal node structure for Octree, children occupy 8 consecutive slots of the node array
+pub struct NodeOctree {
+    _bound: AxisAlignedBBox,
+    _parent: Option<usize>,
+    _children: Option<usize>,
+    _objs: Vec<usize>,
+    _count: usize, //number of objects in subtree
+    _depth: u32,
+}
+
+struct ObjOctree<T> {
+    _obj: T,
+    _bound: AxisAlignedBBox,
+    _node: usize,
+}
+
+fn bound_contains(a: &AxisAlignedBBox, b: &AxisAlignedBBox) -> bool {
+    (0..3).all(|i| a._bound_lower[i] <= b._bound_lower[i] && b._bound_upper[i] <= a._bound_upper[i])
+}
+
+///squared distance from point to the closest point of bound
+fn dist_sq_point_bound(p: &[f64; 3], b: &AxisAlignedBBox) -> f64 {
+    (0..3).fold(0., |acc, i| {
+        let d = (b._bound_lower[i] - p[i])
+            .max(p[i] - b._bound_upper[i])
+            .max(0.);
+        acc + d * d
+    })
+}
+
+///octant of bound for child index with bits (x,y,z) in positions (0,1,2)
+fn bound_octant(b: &AxisAlignedBBox, idx: usize) -> AxisAlignedBBox {
+    let mut o = b.clone();
+    for i in 0..3 {
+        let mid = (b._bound_lower[i] + b._bound_upper[i]) / 2.;
+        if idx & (1 << i) == 0 {
+            o._bound_upper[i] = mid;
+        } else {
+            o._bound_lower[i] = mid;
+        }
+    }
+    o
+}
+
+impl NodeOctree {
+    fn init(bound: AxisAlignedBBox, parent: Option<usize>, depth: u32) -> NodeOctree {
+        NodeOctree {
+            _bound: bound,
+            _parent: parent,
+            _children: None,
+            _objs: vec![],
+            _count: 0,
+            _depth: depth,
+        }
+    }
+    pub fn get_bound(&self) -> &AxisAlignedBBox {
+        &self._bound
+    }
+    pub fn get_depth(&self) -> u32 {
+        self._depth
+    }
+    pub fn is_leaf(&self) -> bool {
+        self._children.is_none()
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Default + Clone,
+{
+    ///octree over bound with subdivision limited by max_depth and triggered when a node holds more than max_objs
+    pub fn init(bound: AxisAlignedBBox, max_depth: u32, max_objs: usize) -> Octree<T> {
+        assert!(max_objs != 0);
+        Octree {
+            _nodes: vec![NodeOctree::init(bound, None, 0)],
+            _free_blocks: vec![],
+            _objs: vec![],
+            _free: vec![],
+            _max_depth: max_depth,
+            _max_objs: max_objs,
+        }
+    }
+    pub fn get_bound(&self) -> &AxisAlignedBBox {
+        &self._nodes[0]._bound
+    }
+    ///number of objects stored
+    pub fn len(&self) -> usize {
+        self._nodes[0]._count
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    ///number of nodes currently in use
+    pub fn node_count(&self) -> usize {
+        self._nodes.len() - self._free_blocks.len() * 8
+    }
+    ///deepest level of any node in use
+    pub fn depth(&self) -> u32 {
+        let mut d = 0;
+        let mut q = vec![0];
+        while let Some(n) = q.pop() {
+            d = d.max(self._nodes[n]._depth);
+            if let Some(c) = self._nodes[n]._children {
+                q.extend(c..c + 8);
+            }
+        }
+        d
+    }
+    fn reset(&mut self, bound: AxisAlignedBBox) {
+        self._nodes = vec![NodeOctree::init(bound, None, 0)];
+        self._free_blocks.clear();
+        self._objs.clear();
+        self._free.clear();
+    }
+    fn alloc_children(&mut self, parent: usize) -> usize {
+        let depth = self._nodes[parent]._depth + 1;
+        let bound = self._nodes[parent]._bound.clone();
+        let children = (0..8)
+            .map(|i| NodeOctree::init(bound_octant(&bound, i), Some(parent), depth))
+            .collect::<Vec<_>>();
+        match self._free_blocks.pop() {
+            Some(c) => {
+                for (i, n) in children.into_iter().enumerate() {
+                    self._nodes[c + i] = n;
+                }
+                c
+            }
+            _ => {
+                let c = self._nodes.len();
+                self._nodes.extend(children);
+                c
+            }
+        }
+    }
+    ///child of node n fully containing b, if any
+    fn child_containing(&self, n: usize, b: &AxisAlignedBBox) -> Option<usize> {
+        self._nodes[n]
+            ._children
+            .and_then(|c| (c..c + 8).find(|x| bound_contains(&self._nodes[*x]._bound, b)))
+    }
+    fn split(&mut self, n: usize) {
+        let c = self.alloc_children(n);
+        self._nodes[n]._children = Some(c);
+        let objs = ::std::mem::take(&mut self._nodes[n]._objs);
+        for h in objs {
+            let target = self
+                .child_containing(n, &self._objs[h].as_ref().unwrap()._bound)
+                .unwrap_or(n);
+            self._nodes[target]._objs.push(h);
+            if target != n {
+                self._nodes[target]._count += 1;
+            }
+            self._objs[h].as_mut().unwrap()._node = target;
+        }
+    }
+    ///pull all objects of the subtree of n into n and release its descendants
+    fn collapse(&mut self, n: usize) {
+        let c = match self._nodes[n]._children.take() {
+            Some(c) => c,
+            _ => return,
+        };
+        for i in c..c + 8 {
+            self.collapse(i);
+            let objs = ::std::mem::take(&mut self._nodes[i]._objs);
+            for h in objs.iter() {
+                self._objs[*h].as_mut().unwrap()._node = n;
+            }
+            self._nodes[n]._objs.extend(objs);
+            self._nodes[i]._count = 0;
+        }
+        self._free_blocks.push(c);
+    }
+    ///add an object and return its handle
+    pub fn insert(&mut self, obj: T, b: &dyn IBound) -> Result<usize, &'static str> {
+        match b.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let bound = bound_copy(b);
+        if !bound_contains(&self._nodes[0]._bound, &bound) {
+            return Err("object outside of octree bound");
+        }
+        let o = ObjOctree {
+            _obj: obj,
+            _bound: bound,
+            _node: 0,
+        };
+        let h = match self._free.pop() {
+            Some(h) => {
+                self._objs[h] = Some(o);
+                h
+            }
+            _ => {
+                self._objs.push(Some(o));
+                self._objs.len() - 1
+            }
+        };
+        self.insert_handle(h);
+        Ok(h)
+    }
+    ///add a point object and return its handle
+    pub fn insert_point(&mut self, obj: T, p: &[f64; 3]) -> Result<usize, &'static str> {
+        let b = AxisAlignedBBox {
+            _bound_lower: *p,
+            _bound_upper: *p,
+        };
+        self.insert(obj, &b)
+    }
+    fn insert_handle(&mut self, h: usize) {
+        let mut n = 0;
+        loop {
+            self._nodes[n]._count += 1;
+            match self.child_containing(n, &self._objs[h].as_ref().unwrap()._bound) {
+                Some(c) => n = c,
+                _ => break,
+            }
+        }
+        self._nodes[n]._objs.push(h);
+        self._objs[h].as_mut().unwrap()._node = n;
+        if self._nodes[n].is_leaf()
+            && self._nodes[n]._objs.len() > self._max_objs
+            && self._nodes[n]._depth < self._max_depth
+        {
+            self.split(n);
+            //objects may still crowd a single child, keep splitting along that path
+            let crowded = self._nodes[n]
+                ._children
+                .and_then(|c| (c..c + 8).find(|x| self._nodes[*x]._objs.len() > self._max_objs));
+            if let Some(c) = crowded {
+                self.resplit(c);
+            }
+        }
+    }
+    fn resplit(&mut self, n: usize) {
+        if self._nodes[n]._objs.len() <= self._max_objs || self._nodes[n]._depth >= self._max_depth
+        {
+            return;
+        }
+        self.split(n);
+        if let Some(c) = self._nodes[n]._children {
+            for i in c..c + 8 {
+                self.resplit(i);
+            }
+        }
+    }
+    ///remove an object by handle and return it
+    pub fn remove(&mut self, handle: usize) -> Result<T, &'static str> {
+        let node = match self._objs.get(handle) {
+            Some(Some(o)) => o._node,
+            _ => return Err("invalid handle"),
+        };
+        self.remove_handle(handle, node);
+        self._free.push(handle);
+        Ok(self._objs[handle].take().unwrap()._obj)
+    }
+    fn remove_handle(&mut self, h: usize, node: usize) {
+        self._nodes[node]._objs.retain(|x| *x != h);
+        //update counts towards the root and collapse the highest subtree that became sparse
+        let mut collapse_at = None;
+        let mut n = Some(node);
+        while let Some(i) = n {
+            self._nodes[i]._count -= 1;
+            if self._nodes[i]._children.is_some() && self._nodes[i]._count <= self._max_objs {
+                collapse_at = Some(i);
+            }
+            n = self._nodes[i]._parent;
+        }
+        if let Some(i) = collapse_at {
+            self.collapse(i);
+        }
+    }
+    ///move an object to a new bound
+    pub fn update(&mut self, handle: usize, b: &dyn IBound) -> Result<(), &'static str> {
+        match b.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let bound = bound_copy(b);
+        if !bound_contains(&self._nodes[0]._bound, &bound) {
+            return Err("object outside of octree bound");
+        }
+        let node = match self._objs.get(handle) {
+            Some(Some(o)) => o._node,
+            _ => return Err("invalid handle"),
+        };
+        self.remove_handle(handle, node);
+        self._objs[handle].as_mut().unwrap()._bound = bound;
+        self.insert_handle(handle);
+        Ok(())
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        let mut q = vec![0];
+        while let Some(n) = q.pop() {
+            let node = &self._nodes[n];
+            if node._count == 0 || !node._bound.intersect(input) {
+                continue;
+            }
+            for h in node._objs.iter() {
+                let o = self._objs[*h].as_ref().unwrap();
+                if o._bound.intersect(input) {
+                    out.push(o._obj.clone());
+                    if single {
+                        return Ok(out);
+                    }
+                }
+            }
+            if let Some(c) = node._children {
+                q.extend(c..c + 8);
+            }
+        }
+        Ok(out)
+    }
+    ///k objects closest to point p with their distances in increasing order
+    pub fn query_nearest(&self, p: &[f64; 3], k: usize) -> Vec<(T, f64)> {
+        let mut best: BinaryHeap<Dist> = BinaryHeap::new();
+        let mut q = BinaryHeap::new();
+        q.push(::std::cmp::Reverse(Dist(
+            dist_sq_point_bound(p, &self._nodes[0]._bound),
+            0,
+        )));
+        while let Some(::std::cmp::Reverse(Dist(d, n))) = q.pop() {
+            if k == 0 || (best.len() == k && d > best.peek().unwrap().0) {
+                break;
+            }
+            let node = &self._nodes[n];
+            for h in node._objs.iter() {
+                let dh = dist_sq_point_bound(p, &self._objs[*h].as_ref().unwrap()._bound);
+                if best.len() < k {
+                    best.push(Dist(dh, *h));
+                } else if dh < best.peek().unwrap().0 {
+                    best.pop();
+                    best.push(Dist(dh, *h));
+                }
+            }
+            if let Some(c) = node._children {
+                for i in c..c + 8 {
+                    if self._nodes[i]._count > 0 {
+                        q.push(::std::cmp::Reverse(Dist(
+                            dist_sq_point_bound(p, &self._nodes[i]._bound),
+                            i,
+                        )));
+                    }
+                }
+            }
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|x| (self._objs[x.1].as_ref().unwrap()._obj.clone(), x.0.sqrt()))
+            .collect()
+    }
+}
+
+impl<T> ISpatialAccel<T> for Octree<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    ///rebuilds over a cube enclosing the input, handles of the objects follow input order
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        for i in objs {
+            match i.1.get_type() {
+                BoundType::AxisAlignBox => (),
+                _ => return Err("unsupported bound type"),
+            }
+        }
+        let mut bound = self._nodes[0]._bound.clone();
+        if !objs.is_empty() {
+            let b = objs.iter().map(|x| x.1).collect::<Vec<&dyn IBound>>();
+            let mut u: AxisAlignedBBox = Default::default();
+            u.get_union(&b[..]);
+            let c = u.get_centroid();
+            let half = (0..3).fold(0f64, |acc, i| {
+                acc.max(u._bound_upper[i] - u._bound_lower[i])
+            }) / 2.;
+            if !half.is_finite() {
+                return Err("octree requires finite bounds");
+            }
+            //pad so objects on the boundary are contained despite rounding
+            let half = half * (1. + 1e-9) + 1e-9;
+            bound = AxisAlignedBBox {
+                _bound_lower: [c[0] - half, c[1] - half, c[2] - half],
+                _bound_upper: [c[0] + half, c[1] + half, c[2] + half],
+            };
+        }
+        self.reset(bound);
+        for i in objs {
+            self.insert(i.0.clone(), i.1)?;
+        }
+        Ok(())
+    }
+}
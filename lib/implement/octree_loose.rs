@@ -0,0 +1,302 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::collections::HashMap;
+use std::f64;
+
+use implement::bound_util::bound_copy;
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// implementation of spatial acceleration using a loose octree, where node bounds are enlarged by a
+/// looseness factor so each object is placed directly at the depth matching its size
+pub struct OctreeLoose<T>
+where
+    T: Default + Clone,
+{
+    _bound: AxisAlignedBBox,
+    _looseness: f64,
+    _max_depth: u32,
+    _nodes: HashMap<(u32, [u64; 3]), NodeOctreeLoose>,
+    _objs: Vec<Option<ObjOctreeLoose<T>>>,
+    _free: Vec<usize>,
+}
+
+///node of the loose octree, addressed by depth and cell coordinate at that depth
+pub struct NodeOctreeLoose {
+    _objs: Vec<usize>,
+    _count: usize, //number of objects in subtree
+}
+
+struct ObjOctreeLoose<T> {
+    _obj: T,
+    _bound: AxisAlignedBBox,
+    _node: (u32, [u64; 3]),
+}
+
+impl<T> OctreeLoose<T>
+where
+    T: Default + Clone,
+{
+    ///loose octree over bound, looseness is the ratio of loose node size to cell size and must be greater than 1
+    pub fn init(bound: AxisAlignedBBox, looseness: f64, max_depth: u32) -> OctreeLoose<T> {
+        assert!(looseness > 1.);
+        assert!(max_depth < 64);
+        OctreeLoose {
+            _bound: bound,
+            _looseness: looseness,
+            _max_depth: max_depth,
+            _nodes: HashMap::new(),
+            _objs: vec![],
+            _free: vec![],
+        }
+    }
+    pub fn get_bound(&self) -> &AxisAlignedBBox {
+        &self._bound
+    }
+    ///number of objects stored
+    pub fn len(&self) -> usize {
+        self._objs.len() - self._free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    ///number of nodes holding objects in their subtree
+    pub fn node_count(&self) -> usize {
+        self._nodes.len()
+    }
+    ///depth at which the object of given handle is stored
+    pub fn get_depth(&self, handle: usize) -> Option<u32> {
+        match self._objs.get(handle) {
+            Some(Some(o)) => Some(o._node.0),
+            _ => None,
+        }
+    }
+    fn cell_size(&self, depth: u32, axis: usize) -> f64 {
+        (self._bound._bound_upper[axis] - self._bound._bound_lower[axis]) / (1u64 << depth) as f64
+    }
+    ///loose bound of a node: its cell enlarged about the centre by the looseness factor
+    pub fn get_node_bound(&self, depth: u32, coord: &[u64; 3]) -> AxisAlignedBBox {
+        let mut b = self._bound.clone();
+        for (i, c) in coord.iter().enumerate() {
+            let s = self.cell_size(depth, i);
+            let pad = s * (self._looseness - 1.) / 2.;
+            b._bound_lower[i] = self._bound._bound_lower[i] + *c as f64 * s - pad;
+            b._bound_upper[i] = self._bound._bound_lower[i] + (*c + 1) as f64 * s + pad;
+        }
+        b
+    }
+    ///node for an object, the depth is chosen directly from the object extent so that the object
+    ///fits within the loose bound of the cell containing its centre
+    fn node_for(&self, b: &AxisAlignedBBox) -> (u32, [u64; 3]) {
+        let c = b.get_centroid();
+        let mut ratio = f64::INFINITY;
+        for i in 0..3 {
+            let extent = b._bound_upper[i] - b._bound_lower[i];
+            let world = self._bound._bound_upper[i] - self._bound._bound_lower[i];
+            if extent > 0. {
+                ratio = ratio.min((self._looseness - 1.) * world / extent);
+            }
+        }
+        let depth = if ratio < 1. {
+            0
+        } else {
+            (ratio.log2().floor() as u64).min(self._max_depth as u64) as u32
+        };
+        let mut coord = [0u64; 3];
+        let n = 1u64 << depth;
+        for i in 0..3 {
+            let s = self.cell_size(depth, i);
+            let x = ((c[i] - self._bound._bound_lower[i]) / s).floor();
+            coord[i] = (x.max(0.) as u64).min(n - 1);
+        }
+        (depth, coord)
+    }
+    fn link(&mut self, h: usize) {
+        let (depth, coord) = self._objs[h].as_ref().unwrap()._node;
+        for d in 0..=depth {
+            let shift = depth - d;
+            let key = (d, [coord[0] >> shift, coord[1] >> shift, coord[2] >> shift]);
+            let n = self._nodes.entry(key).or_insert_with(|| NodeOctreeLoose {
+                _objs: vec![],
+                _count: 0,
+            });
+            n._count += 1;
+            if d == depth {
+                n._objs.push(h);
+            }
+        }
+    }
+    fn unlink(&mut self, h: usize) {
+        let (depth, coord) = self._objs[h].as_ref().unwrap()._node;
+        for d in 0..=depth {
+            let shift = depth - d;
+            let key = (d, [coord[0] >> shift, coord[1] >> shift, coord[2] >> shift]);
+            let remove = match self._nodes.get_mut(&key) {
+                Some(n) => {
+                    n._count -= 1;
+                    if d == depth {
+                        n._objs.retain(|x| *x != h);
+                    }
+                    n._count == 0
+                }
+                _ => false,
+            };
+            if remove {
+                self._nodes.remove(&key);
+            }
+        }
+    }
+    fn check_bound(&self, b: &dyn IBound) -> Result<AxisAlignedBBox, &'static str> {
+        match b.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let a = bound_copy(b);
+        let c = a.get_centroid();
+        if (0..3)
+            .any(|i| !(self._bound._bound_lower[i] <= c[i] && c[i] <= self._bound._bound_upper[i]))
+        {
+            return Err("object centre outside of octree bound");
+        }
+        Ok(a)
+    }
+    ///add an object and return its handle
+    pub fn insert(&mut self, obj: T, b: &dyn IBound) -> Result<usize, &'static str> {
+        let bound = self.check_bound(b)?;
+        let o = ObjOctreeLoose {
+            _obj: obj,
+            _node: self.node_for(&bound),
+            _bound: bound,
+        };
+        let h = match self._free.pop() {
+            Some(h) => {
+                self._objs[h] = Some(o);
+                h
+            }
+            _ => {
+                self._objs.push(Some(o));
+                self._objs.len() - 1
+            }
+        };
+        self.link(h);
+        Ok(h)
+    }
+    ///remove an object by handle and return it
+    pub fn remove(&mut self, handle: usize) -> Result<T, &'static str> {
+        match self._objs.get(handle) {
+            Some(Some(_)) => (),
+            _ => return Err("invalid handle"),
+        }
+        self.unlink(handle);
+        self._free.push(handle);
+        Ok(self._objs[handle].take().unwrap()._obj)
+    }
+    ///move an object to a new bound, only relinking when it leaves its current node
+    pub fn update(&mut self, handle: usize, b: &dyn IBound) -> Result<(), &'static str> {
+        let bound = self.check_bound(b)?;
+        let node_old = match self._objs.get(handle) {
+            Some(Some(o)) => o._node,
+            _ => return Err("invalid handle"),
+        };
+        let node_new = self.node_for(&bound);
+        if node_new == node_old {
+            self._objs[handle].as_mut().unwrap()._bound = bound;
+            return Ok(());
+        }
+        self.unlink(handle);
+        {
+            let o = self._objs[handle].as_mut().unwrap();
+            o._bound = bound;
+            o._node = node_new;
+        }
+        self.link(handle);
+        Ok(())
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        let mut q = vec![(0u32, [0u64; 3])];
+        while let Some(key) = q.pop() {
+            let n = match self._nodes.get(&key) {
+                Some(n) => n,
+                _ => continue,
+            };
+            //objects larger than the root are kept at the root regardless of its loose bound
+            if key.0 > 0 && !self.get_node_bound(key.0, &key.1).intersect(input) {
+                continue;
+            }
+            for h in n._objs.iter() {
+                let o = self._objs[*h].as_ref().unwrap();
+                if o._bound.intersect(input) {
+                    out.push(o._obj.clone());
+                    if single {
+                        return Ok(out);
+                    }
+                }
+            }
+            if n._count > n._objs.len() && key.0 < self._max_depth {
+                for i in 0..8u64 {
+                    q.push((
+                        key.0 + 1,
+                        [
+                            key.1[0] * 2 + (i & 1),
+                            key.1[1] * 2 + ((i >> 1) & 1),
+                            key.1[2] * 2 + ((i >> 2) & 1),
+                        ],
+                    ));
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl<T> ISpatialAccel<T> for OctreeLoose<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    ///rebuilds over a cube enclosing the input, handles of the objects follow input order
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        for i in objs {
+            match i.1.get_type() {
+                BoundType::AxisAlignBox => (),
+                _ => return Err("unsupported bound type"),
+            }
+        }
+        if !objs.is_empty() {
+            let b = objs.iter().map(|x| x.1).collect::<Vec<&dyn IBound>>();
+            let mut u: AxisAlignedBBox = Default::default();
+            u.get_union(&b[..]);
+            let c = u.get_centroid();
+            let half = (0..3).fold(0f64, |acc, i| {
+                acc.max(u._bound_upper[i] - u._bound_lower[i])
+            }) / 2.;
+            if !half.is_finite() {
+                return Err("octree requires finite bounds");
+            }
+            let half = half.max(f64::EPSILON);
+            self._bound = AxisAlignedBBox {
+                _bound_lower: [c[0] - half, c[1] - half, c[2] - half],
+                _bound_upper: [c[0] + half, c[1] + half, c[2] + half],
+            };
+        }
+        self._nodes.clear();
+        self._objs.clear();
+        self._free.clear();
+        for i in objs {
+            self.insert(i.0.clone(), i.1)?;
+        }
+        Ok(())
+    }
+}
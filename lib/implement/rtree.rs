@@ -0,0 +1,499 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::cmp::Ordering;
+use std::f64;
+
+use implement::bound_util::{bound_copy, bound_union};
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// implementation of spatial acceleration using an R*-tree with forced reinsertion
+pub struct RTree<T>
+where
+    T: Default + Clone,
+{
+    _min_fanout: usize,
+    _max_fanout: usize,
+    _root: usize,
+    _nodes: Vec<NodeRTree>,
+    _free_nodes: Vec<usize>,
+    _objs: Vec<Option<ObjRTree<T>>>,
+    _free: Vec<usize>,
+}
+
+///node of the tree, entries are object handles at level 0 and node indices above
+pub struct NodeRTree {
+    _bound: AxisAlignedBBox,
+    _level: u32,
+    _parent: Option<usize>,
+    _entries: Vec<usize>,
+}
+
+struct ObjRTree<T> {
+    _obj: T,
+    _bound: AxisAlignedBBox,
+    _leaf: usize,
+}
+
+fn bound_empty() -> AxisAlignedBBox {
+    AxisAlignedBBox {
+        _bound_lower: [f64::INFINITY; 3],
+        _bound_upper: [f64::NEG_INFINITY; 3],
+    }
+}
+
+fn bound_contains(a: &AxisAlignedBBox, b: &AxisAlignedBBox) -> bool {
+    (0..3).all(|i| a._bound_lower[i] <= b._bound_lower[i] && b._bound_upper[i] <= a._bound_upper[i])
+}
+
+fn volume(b: &AxisAlignedBBox) -> f64 {
+    (0..3).fold(1., |acc, i| {
+        acc * (b._bound_upper[i] - b._bound_lower[i]).max(0.)
+    })
+}
+
+fn margin(b: &AxisAlignedBBox) -> f64 {
+    (0..3).fold(0., |acc, i| {
+        acc + (b._bound_upper[i] - b._bound_lower[i]).max(0.)
+    })
+}
+
+///volume of the intersection of two bounds
+fn overlap(a: &AxisAlignedBBox, b: &AxisAlignedBBox) -> f64 {
+    (0..3).fold(1., |acc, i| {
+        let l = a._bound_lower[i].max(b._bound_lower[i]);
+        let u = a._bound_upper[i].min(b._bound_upper[i]);
+        acc * (u - l).max(0.)
+    })
+}
+
+fn centre(b: &AxisAlignedBBox) -> [f64; 3] {
+    [
+        (b._bound_lower[0] + b._bound_upper[0]) / 2.,
+        (b._bound_lower[1] + b._bound_upper[1]) / 2.,
+        (b._bound_lower[2] + b._bound_upper[2]) / 2.,
+    ]
+}
+
+///unions of the first k bounds for k in 1..=n and of the bounds from k onwards for k in 0..n
+fn prefix_suffix(
+    bounds: &[AxisAlignedBBox],
+    order: &[usize],
+) -> (Vec<AxisAlignedBBox>, Vec<AxisAlignedBBox>) {
+    let n = order.len();
+    let mut pre = vec![bound_empty(); n + 1];
+    let mut suf = vec![bound_empty(); n + 1];
+    for k in 0..n {
+        pre[k + 1] = bound_union(&pre[k], &bounds[order[k]]);
+    }
+    for k in (0..n).rev() {
+        suf[k] = bound_union(&suf[k + 1], &bounds[order[k]]);
+    }
+    (pre, suf)
+}
+
+impl<T> RTree<T>
+where
+    T: Default + Clone,
+{
+    ///tree with nodes holding between min_fanout and max_fanout entries, except for the root
+    pub fn init(min_fanout: usize, max_fanout: usize) -> RTree<T> {
+        assert!(min_fanout >= 1);
+        assert!(max_fanout >= 2 * min_fanout);
+        RTree {
+            _min_fanout: min_fanout,
+            _max_fanout: max_fanout,
+            _root: 0,
+            _nodes: vec![NodeRTree {
+                _bound: bound_empty(),
+                _level: 0,
+                _parent: None,
+                _entries: vec![],
+            }],
+            _free_nodes: vec![],
+            _objs: vec![],
+            _free: vec![],
+        }
+    }
+    ///number of objects stored
+    pub fn len(&self) -> usize {
+        self._objs.len() - self._free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    ///number of levels of the tree, 1 when the root is a leaf
+    pub fn height(&self) -> u32 {
+        self._nodes[self._root]._level + 1
+    }
+    ///number of nodes in use
+    pub fn node_count(&self) -> usize {
+        self._nodes.len() - self._free_nodes.len()
+    }
+    fn alloc_node(&mut self, level: u32, parent: Option<usize>) -> usize {
+        let n = NodeRTree {
+            _bound: bound_empty(),
+            _level: level,
+            _parent: parent,
+            _entries: vec![],
+        };
+        match self._free_nodes.pop() {
+            Some(i) => {
+                self._nodes[i] = n;
+                i
+            }
+            _ => {
+                self._nodes.push(n);
+                self._nodes.len() - 1
+            }
+        }
+    }
+    fn free_node(&mut self, n: usize) {
+        self._nodes[n]._entries.clear();
+        self._nodes[n]._parent = None;
+        self._free_nodes.push(n);
+    }
+    ///bound of an entry of a node at given level
+    fn entry_bound(&self, level: u32, e: usize) -> &AxisAlignedBBox {
+        if level == 0 {
+            &self._objs[e].as_ref().unwrap()._bound
+        } else {
+            &self._nodes[e]._bound
+        }
+    }
+    fn set_parent(&mut self, level: u32, e: usize, parent: usize) {
+        if level == 0 {
+            self._objs[e].as_mut().unwrap()._leaf = parent;
+        } else {
+            self._nodes[e]._parent = Some(parent);
+        }
+    }
+    fn refit(&mut self, n: usize) {
+        let level = self._nodes[n]._level;
+        let b = self._nodes[n]
+            ._entries
+            .iter()
+            .fold(bound_empty(), |acc, e| {
+                bound_union(&acc, self.entry_bound(level, *e))
+            });
+        self._nodes[n]._bound = b;
+    }
+    fn refit_up(&mut self, n: usize) {
+        let mut n = Some(n);
+        while let Some(i) = n {
+            self.refit(i);
+            n = self._nodes[i]._parent;
+        }
+    }
+    ///descends to a node at given level, minimising overlap enlargement when the children are
+    ///leaves and area enlargement otherwise
+    fn choose_subtree(&self, b: &AxisAlignedBBox, level: u32) -> usize {
+        let mut n = self._root;
+        while self._nodes[n]._level > level {
+            let node = &self._nodes[n];
+            let mut best = (f64::INFINITY, f64::INFINITY, f64::INFINITY);
+            let mut best_child = node._entries[0];
+            for c in node._entries.iter() {
+                let cb = &self._nodes[*c]._bound;
+                let enlarged = bound_union(cb, b);
+                let area = volume(cb);
+                let area_delta = volume(&enlarged) - area;
+                let overlap_delta = if node._level == 1 {
+                    node._entries.iter().filter(|o| *o != c).fold(0., |acc, o| {
+                        let ob = &self._nodes[*o]._bound;
+                        acc + overlap(&enlarged, ob) - overlap(cb, ob)
+                    })
+                } else {
+                    0.
+                };
+                let cost = (overlap_delta, area_delta, area);
+                if cost < best {
+                    best = cost;
+                    best_child = *c;
+                }
+            }
+            n = best_child;
+        }
+        n
+    }
+    fn insert_entry(&mut self, e: usize, level: u32, reinserted: &mut Vec<bool>) {
+        let b = self.entry_bound(level, e).clone();
+        let n = self.choose_subtree(&b, level);
+        self._nodes[n]._entries.push(e);
+        self.set_parent(level, e, n);
+        self.overflow(n, reinserted);
+    }
+    ///resolves overflow of node n and its ancestors by forced reinsertion once per level and
+    ///splitting otherwise
+    fn overflow(&mut self, n: usize, reinserted: &mut Vec<bool>) {
+        let mut n = n;
+        loop {
+            if self._nodes[n]._entries.len() <= self._max_fanout {
+                self.refit_up(n);
+                return;
+            }
+            let level = self._nodes[n]._level;
+            if reinserted.len() <= level as usize {
+                reinserted.resize(level as usize + 1, false);
+            }
+            if n != self._root && !reinserted[level as usize] {
+                reinserted[level as usize] = true;
+                let removed = self.take_farthest(n);
+                self.refit_up(n);
+                //close reinsert, nearest of the removed entries first
+                for e in removed.into_iter().rev() {
+                    self.insert_entry(e, level, reinserted);
+                }
+                return;
+            }
+            let m = self.split(n);
+            match self._nodes[n]._parent {
+                Some(p) => {
+                    self._nodes[p]._entries.push(m);
+                    self._nodes[m]._parent = Some(p);
+                    n = p;
+                }
+                _ => {
+                    let r = self.alloc_node(level + 1, None);
+                    self._nodes[r]._entries = vec![n, m];
+                    self._nodes[n]._parent = Some(r);
+                    self._nodes[m]._parent = Some(r);
+                    self._root = r;
+                    self.refit(r);
+                    return;
+                }
+            }
+        }
+    }
+    ///removes the entries farthest from the centre of the node, in decreasing distance
+    fn take_farthest(&mut self, n: usize) -> Vec<usize> {
+        self.refit(n);
+        let level = self._nodes[n]._level;
+        let c = centre(&self._nodes[n]._bound);
+        let mut d = self._nodes[n]
+            ._entries
+            .iter()
+            .map(|e| {
+                let ce = centre(self.entry_bound(level, *e));
+                let dist = (0..3).fold(0., |acc, i| acc + (ce[i] - c[i]) * (ce[i] - c[i]));
+                (dist, *e)
+            })
+            .collect::<Vec<_>>();
+        d.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        let p = (self._max_fanout * 3 / 10).max(1);
+        self._nodes[n]._entries = d[p..].iter().map(|x| x.1).collect();
+        d[..p].iter().map(|x| x.1).collect()
+    }
+    ///R* split: chooses the axis with least total margin over all distributions, then the
+    ///distribution on that axis with least overlap, breaking ties by area
+    fn split(&mut self, n: usize) -> usize {
+        let level = self._nodes[n]._level;
+        let entries = std::mem::take(&mut self._nodes[n]._entries);
+        let bounds = entries
+            .iter()
+            .map(|e| self.entry_bound(level, *e).clone())
+            .collect::<Vec<_>>();
+        let count = entries.len();
+        let m = self._min_fanout;
+
+        let sorted = |axis: usize, upper: bool| {
+            let mut order = (0..count).collect::<Vec<_>>();
+            order.sort_by(|a, b| {
+                let (x, y) = if upper {
+                    (bounds[*a]._bound_upper[axis], bounds[*b]._bound_upper[axis])
+                } else {
+                    (bounds[*a]._bound_lower[axis], bounds[*b]._bound_lower[axis])
+                };
+                x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+            });
+            order
+        };
+
+        let mut best_axis = 0;
+        let mut best_margin = f64::INFINITY;
+        for axis in 0..3 {
+            let mut s = 0.;
+            for upper in [false, true] {
+                let (pre, suf) = prefix_suffix(&bounds[..], &sorted(axis, upper)[..]);
+                for k in m..=count - m {
+                    s += margin(&pre[k]) + margin(&suf[k]);
+                }
+            }
+            if s < best_margin {
+                best_margin = s;
+                best_axis = axis;
+            }
+        }
+
+        let mut best = (f64::INFINITY, f64::INFINITY);
+        let mut best_split = (vec![], m);
+        for upper in [false, true] {
+            let order = sorted(best_axis, upper);
+            let (pre, suf) = prefix_suffix(&bounds[..], &order[..]);
+            for k in m..=count - m {
+                let cost = (overlap(&pre[k], &suf[k]), volume(&pre[k]) + volume(&suf[k]));
+                if cost < best {
+                    best = cost;
+                    best_split = (order.clone(), k);
+                }
+            }
+        }
+
+        let (order, k) = best_split;
+        let parent = self._nodes[n]._parent;
+        let sibling = self.alloc_node(level, parent);
+        self._nodes[n]._entries = order[..k].iter().map(|i| entries[*i]).collect();
+        self._nodes[sibling]._entries = order[k..].iter().map(|i| entries[*i]).collect();
+        for i in order[k..].iter() {
+            self.set_parent(level, entries[*i], sibling);
+        }
+        self.refit(n);
+        self.refit(sibling);
+        sibling
+    }
+    ///detaches an object from its leaf, dissolving underfull nodes and reinserting their entries
+    fn unlink(&mut self, handle: usize) {
+        let leaf = self._objs[handle].as_ref().unwrap()._leaf;
+        self._nodes[leaf]._entries.retain(|x| *x != handle);
+
+        let mut orphans = vec![];
+        let mut n = leaf;
+        while let Some(p) = self._nodes[n]._parent {
+            if self._nodes[n]._entries.len() < self._min_fanout {
+                self._nodes[p]._entries.retain(|x| *x != n);
+                let level = self._nodes[n]._level;
+                for e in std::mem::take(&mut self._nodes[n]._entries) {
+                    orphans.push((level, e));
+                }
+                self.free_node(n);
+            } else {
+                self.refit(n);
+            }
+            n = p;
+        }
+        self.refit(n);
+
+        //reinsert whole subtrees first so that nodes at their levels exist for the rest
+        orphans.sort_by_key(|x| ::std::cmp::Reverse(x.0));
+        if self._nodes[self._root]._entries.is_empty() {
+            self._nodes[self._root]._level = orphans.first().map_or(0, |x| x.0);
+        }
+        for (level, e) in orphans {
+            self.insert_entry(e, level, &mut vec![]);
+        }
+
+        //shorten the tree while the root has a single child
+        while self._nodes[self._root]._level > 0 && self._nodes[self._root]._entries.len() == 1 {
+            let old = self._root;
+            self._root = self._nodes[old]._entries[0];
+            self._nodes[self._root]._parent = None;
+            self.free_node(old);
+        }
+    }
+    fn check_bound(b: &dyn IBound) -> Result<AxisAlignedBBox, &'static str> {
+        match b.get_type() {
+            BoundType::AxisAlignBox => Ok(bound_copy(b)),
+            _ => Err("unsupported bound type"),
+        }
+    }
+    ///add an object and return its handle
+    pub fn insert(&mut self, obj: T, b: &dyn IBound) -> Result<usize, &'static str> {
+        let bound = RTree::<T>::check_bound(b)?;
+        let o = ObjRTree {
+            _obj: obj,
+            _bound: bound,
+            _leaf: 0,
+        };
+        let h = match self._free.pop() {
+            Some(h) => {
+                self._objs[h] = Some(o);
+                h
+            }
+            _ => {
+                self._objs.push(Some(o));
+                self._objs.len() - 1
+            }
+        };
+        self.insert_entry(h, 0, &mut vec![]);
+        Ok(h)
+    }
+    ///remove an object by handle and return it
+    pub fn remove(&mut self, handle: usize) -> Result<T, &'static str> {
+        match self._objs.get(handle) {
+            Some(Some(_)) => (),
+            _ => return Err("invalid handle"),
+        }
+        self.unlink(handle);
+        self._free.push(handle);
+        Ok(self._objs[handle].take().unwrap()._obj)
+    }
+    ///move an object to a new bound, only reinserting when it leaves the bound of its leaf
+    pub fn update(&mut self, handle: usize, b: &dyn IBound) -> Result<(), &'static str> {
+        let bound = RTree::<T>::check_bound(b)?;
+        let leaf = match self._objs.get(handle) {
+            Some(Some(o)) => o._leaf,
+            _ => return Err("invalid handle"),
+        };
+        if bound_contains(&self._nodes[leaf]._bound, &bound) {
+            self._objs[handle].as_mut().unwrap()._bound = bound;
+            return Ok(());
+        }
+        self.unlink(handle);
+        self._objs[handle].as_mut().unwrap()._bound = bound;
+        self.insert_entry(handle, 0, &mut vec![]);
+        Ok(())
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        let mut q = vec![self._root];
+        while let Some(n) = q.pop() {
+            let node = &self._nodes[n];
+            if node._entries.is_empty() || !node._bound.intersect(input) {
+                continue;
+            }
+            if node._level > 0 {
+                q.extend_from_slice(&node._entries[..]);
+                continue;
+            }
+            for h in node._entries.iter() {
+                let o = self._objs[*h].as_ref().unwrap();
+                if o._bound.intersect(input) {
+                    out.push(o._obj.clone());
+                    if single {
+                        return Ok(out);
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl<T> ISpatialAccel<T> for RTree<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    ///replaces content of the tree by successive insertion, handles of the objects follow input order
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        for i in objs {
+            RTree::<T>::check_bound(i.1)?;
+        }
+        *self = RTree::init(self._min_fanout, self._max_fanout);
+        for i in objs {
+            self.insert(i.0.clone(), i.1)?;
+        }
+        Ok(())
+    }
+}
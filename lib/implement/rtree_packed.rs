@@ -0,0 +1,190 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::cmp::Ordering;
+use std::f64;
+
+use implement::bound_util::{bound_copy, bound_union};
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// implementation of spatial acceleration using a static R-tree bulk loaded with
+/// Sort-Tile-Recursive packing, so that all nodes but the last of each level are full
+pub struct RTreePacked<T>
+where
+    T: Default + Clone,
+{
+    _fanout: usize,
+    _objs: Vec<(T, AxisAlignedBBox)>,
+    _nodes: Vec<NodeRTreePacked>, //stored level by level from the leaves up, root last
+    _leaf_count: usize,
+    _height: u32,
+}
+
+///node of the packed tree covering a contiguous range of objects for leaves or of nodes otherwise
+pub struct NodeRTreePacked {
+    _bound: AxisAlignedBBox,
+    _first: usize,
+    _count: usize,
+}
+
+fn sort_by_centre(items: &mut [usize], bounds: &[AxisAlignedBBox], axis: usize) {
+    let c = |i: usize| bounds[i]._bound_lower[axis] + bounds[i]._bound_upper[axis];
+    items.sort_by(|a, b| c(*a).partial_cmp(&c(*b)).unwrap_or(Ordering::Equal));
+}
+
+///orders items into groups of fanout by tiling along x, then y, then z
+fn str_order(bounds: &[AxisAlignedBBox], fanout: usize) -> Vec<usize> {
+    let n = bounds.len();
+    let mut order = (0..n).collect::<Vec<_>>();
+    let pages = n.div_ceil(fanout);
+    let slices = (pages as f64).cbrt().ceil().max(1.) as usize;
+    let slab = slices * slices * fanout;
+    let run = slices * fanout;
+    sort_by_centre(&mut order[..], bounds, 0);
+    for s in order.chunks_mut(slab) {
+        sort_by_centre(s, bounds, 1);
+        for r in s.chunks_mut(run) {
+            sort_by_centre(r, bounds, 2);
+        }
+    }
+    order
+}
+
+impl<T> RTreePacked<T>
+where
+    T: Default + Clone,
+{
+    ///empty tree packing fanout entries per node
+    pub fn init(fanout: usize) -> RTreePacked<T> {
+        assert!(fanout >= 2);
+        RTreePacked {
+            _fanout: fanout,
+            _objs: vec![],
+            _nodes: vec![],
+            _leaf_count: 0,
+            _height: 0,
+        }
+    }
+    pub fn get_fanout(&self) -> usize {
+        self._fanout
+    }
+    ///number of objects stored
+    pub fn len(&self) -> usize {
+        self._objs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._objs.is_empty()
+    }
+    ///number of levels of nodes, 0 when empty
+    pub fn height(&self) -> u32 {
+        self._height
+    }
+    pub fn node_count(&self) -> usize {
+        self._nodes.len()
+    }
+    ///packs consecutive groups of fanout bounds into nodes, returning their bounds
+    fn pack_level(&mut self, bounds: &[AxisAlignedBBox], offset: usize) -> Vec<AxisAlignedBBox> {
+        let mut out = vec![];
+        let mut first = 0;
+        for g in bounds.chunks(self._fanout) {
+            let b = g[1..]
+                .iter()
+                .fold(g[0].clone(), |acc, x| bound_union(&acc, x));
+            self._nodes.push(NodeRTreePacked {
+                _bound: b.clone(),
+                _first: offset + first,
+                _count: g.len(),
+            });
+            out.push(b);
+            first += g.len();
+        }
+        out
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let mut out = vec![];
+        if self._nodes.is_empty() {
+            return Ok(out);
+        }
+        let mut q = vec![self._nodes.len() - 1];
+        while let Some(n) = q.pop() {
+            let node = &self._nodes[n];
+            if !node._bound.intersect(input) {
+                continue;
+            }
+            let r = node._first..node._first + node._count;
+            if n >= self._leaf_count {
+                q.extend(r);
+                continue;
+            }
+            for o in self._objs[r].iter() {
+                if o.1.intersect(input) {
+                    out.push(o.0.clone());
+                    if single {
+                        return Ok(out);
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl<T> ISpatialAccel<T> for RTreePacked<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    ///bulk loads the tree, each level is tiled again from the node bounds of the level below
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        for i in objs {
+            match i.1.get_type() {
+                BoundType::AxisAlignBox => (),
+                _ => return Err("unsupported bound type"),
+            }
+        }
+        let bounds = objs.iter().map(|x| bound_copy(x.1)).collect::<Vec<_>>();
+        let order = str_order(&bounds[..], self._fanout);
+        self._objs = order
+            .iter()
+            .map(|i| (objs[*i].0.clone(), bounds[*i].clone()))
+            .collect();
+        self._nodes.clear();
+        self._height = 0;
+        if objs.is_empty() {
+            self._leaf_count = 0;
+            return Ok(());
+        }
+
+        let sorted = self._objs.iter().map(|x| x.1.clone()).collect::<Vec<_>>();
+        let mut level = self.pack_level(&sorted[..], 0);
+        self._leaf_count = self._nodes.len();
+        self._height = 1;
+        let mut offset = 0;
+        while level.len() > 1 {
+            //reorder the nodes of the level below before grouping them
+            let order = str_order(&level[..], self._fanout);
+            let nodes = self._nodes.split_off(offset);
+            let mut nodes = nodes.into_iter().map(Some).collect::<Vec<_>>();
+            for i in order.iter() {
+                self._nodes.push(nodes[*i].take().unwrap());
+            }
+            let reordered = order.iter().map(|i| level[*i].clone()).collect::<Vec<_>>();
+            let next = self._nodes.len();
+            level = self.pack_level(&reordered[..], offset);
+            offset = next;
+            self._height += 1;
+        }
+        Ok(())
+    }
+}
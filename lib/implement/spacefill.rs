@@ -0,0 +1,244 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+///space-filling curve used for keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    Morton,
+    Hilbert,
+}
+
+/// 3D space-filling curve keys over a bound quantised to 2^bits cells per axis
+///
+/// both curves visit every octree cell of the quantised grid in a contiguous run of keys, which
+/// is what allows box queries to be decomposed into key intervals
+pub struct SpaceFill {
+    _curve: Curve,
+    _bits: u32,
+    _bound: AxisAlignedBBox,
+}
+
+///maximum bits per axis so that keys fit in 63 bits
+pub const MAX_BITS: u32 = 21;
+
+///spreads the lower 21 bits of v so that there are 2 zero bits between each
+fn spread(v: u32) -> u64 {
+    let mut x = u64::from(v) & 0x1f_ffff;
+    x = (x | x << 32) & 0x1f_0000_0000_ffff;
+    x = (x | x << 16) & 0x1f_0000_ff00_00ff;
+    x = (x | x << 8) & 0x100f_00f0_0f00_f00f;
+    x = (x | x << 4) & 0x10c3_0c30_c30c_30c3;
+    x = (x | x << 2) & 0x1249_2492_4924_9249;
+    x
+}
+
+fn compact(v: u64) -> u32 {
+    let mut x = v & 0x1249_2492_4924_9249;
+    x = (x | x >> 2) & 0x10c3_0c30_c30c_30c3;
+    x = (x | x >> 4) & 0x100f_00f0_0f00_f00f;
+    x = (x | x >> 8) & 0x1f_0000_ff00_00ff;
+    x = (x | x >> 16) & 0x1f_0000_0000_ffff;
+    x = (x | x >> 32) & 0x1f_ffff;
+    x as u32
+}
+
+///key with bits of a, b and c interleaved, c in the lowest bit
+fn interleave(a: u32, b: u32, c: u32) -> u64 {
+    spread(a) << 2 | spread(b) << 1 | spread(c)
+}
+
+fn deinterleave(k: u64) -> [u32; 3] {
+    [compact(k >> 2), compact(k >> 1), compact(k)]
+}
+
+///converts coordinates in place to the transposed Hilbert index (Skilling, 2004)
+fn axes_to_transpose(x: &mut [u32; 3], bits: u32) {
+    let m = 1u32 << (bits - 1);
+    let mut q = m;
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..3 {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+    x[1] ^= x[0];
+    x[2] ^= x[1];
+    let mut t = 0;
+    let mut q = m;
+    while q > 1 {
+        if x[2] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for v in x.iter_mut() {
+        *v ^= t;
+    }
+}
+
+///inverse of axes_to_transpose
+fn transpose_to_axes(x: &mut [u32; 3], bits: u32) {
+    let n = 2u32 << (bits - 1);
+    let t = x[2] >> 1;
+    x[2] ^= x[1];
+    x[1] ^= x[0];
+    x[0] ^= t;
+    let mut q = 2;
+    while q != n {
+        let p = q - 1;
+        for i in (0..3).rev() {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q <<= 1;
+    }
+}
+
+impl SpaceFill {
+    ///keys over bound with 2^bits cells per axis, bits in 1..=MAX_BITS
+    pub fn init(curve: Curve, bits: u32, bound: AxisAlignedBBox) -> SpaceFill {
+        assert!((1..=MAX_BITS).contains(&bits));
+        SpaceFill {
+            _curve: curve,
+            _bits: bits,
+            _bound: bound,
+        }
+    }
+    pub fn get_bits(&self) -> u32 {
+        self._bits
+    }
+    pub fn get_curve(&self) -> Curve {
+        self._curve
+    }
+    ///cell containing a point, points outside the bound are clamped to the border cells
+    pub fn quantize(&self, p: &[f64; 3]) -> [u32; 3] {
+        let n = 1u64 << self._bits;
+        let mut c = [0u32; 3];
+        for i in 0..3 {
+            let extent = self._bound._bound_upper[i] - self._bound._bound_lower[i];
+            if extent > 0. {
+                let x = ((p[i] - self._bound._bound_lower[i]) / extent * n as f64).floor();
+                c[i] = x.max(0.).min((n - 1) as f64) as u32;
+            }
+        }
+        c
+    }
+    ///bound of a cell
+    pub fn get_cell_bound(&self, cell: &[u32; 3]) -> AxisAlignedBBox {
+        let n = (1u64 << self._bits) as f64;
+        let mut b = self._bound.clone();
+        for (i, c) in cell.iter().enumerate() {
+            let s = (self._bound._bound_upper[i] - self._bound._bound_lower[i]) / n;
+            b._bound_lower[i] = self._bound._bound_lower[i] + f64::from(*c) * s;
+            b._bound_upper[i] = self._bound._bound_lower[i] + f64::from(*c + 1) * s;
+        }
+        b
+    }
+    ///key of a cell, coordinates must be below 2^bits
+    pub fn encode_cell(&self, cell: &[u32; 3]) -> u64 {
+        match self._curve {
+            Curve::Morton => interleave(cell[2], cell[1], cell[0]),
+            Curve::Hilbert => {
+                let mut x = *cell;
+                axes_to_transpose(&mut x, self._bits);
+                interleave(x[0], x[1], x[2])
+            }
+        }
+    }
+    ///cell of a key
+    pub fn decode(&self, key: u64) -> [u32; 3] {
+        let d = deinterleave(key);
+        match self._curve {
+            Curve::Morton => [d[2], d[1], d[0]],
+            Curve::Hilbert => {
+                let mut x = d;
+                transpose_to_axes(&mut x, self._bits);
+                x
+            }
+        }
+    }
+    ///key of the cell containing a point
+    pub fn encode(&self, p: &[f64; 3]) -> u64 {
+        self.encode_cell(&self.quantize(p))
+    }
+    ///key of the centroid of a bound
+    pub fn encode_bound(&self, b: &dyn IBound) -> Result<u64, &'static str> {
+        match b.get_type() {
+            BoundType::AxisAlignBox => Ok(self.encode(&b.get_centroid())),
+            _ => Err("unsupported bound type"),
+        }
+    }
+    ///indices of the bounds ordered by key, for reordering input before building a structure
+    pub fn sort_indices(&self, bounds: &[&dyn IBound]) -> Result<Vec<usize>, &'static str> {
+        let mut keys = vec![];
+        for (i, b) in bounds.iter().enumerate() {
+            keys.push((self.encode_bound(*b)?, i));
+        }
+        keys.sort();
+        Ok(keys.into_iter().map(|x| x.1).collect())
+    }
+    ///sorted disjoint inclusive key intervals covering all cells overlapping the box
+    ///
+    ///octree cells below max_depth that partially overlap the box are emitted whole, so a smaller
+    ///depth gives fewer intervals at the cost of covering extra cells
+    pub fn decompose(&self, b: &AxisAlignedBBox, max_depth: u32) -> Vec<(u64, u64)> {
+        let mut out = vec![];
+        if (0..3).any(|i| {
+            b._bound_upper[i] < self._bound._bound_lower[i]
+                || b._bound_lower[i] > self._bound._bound_upper[i]
+        }) {
+            return out;
+        }
+        let lo = self.quantize(&b._bound_lower);
+        let hi = self.quantize(&b._bound_upper);
+        let mut q = vec![(0u32, [0u32; 3])];
+        while let Some((depth, corner)) = q.pop() {
+            let size = 1u32 << (self._bits - depth);
+            let disjoint = (0..3).any(|i| corner[i] > hi[i] || corner[i] + (size - 1) < lo[i]);
+            if disjoint {
+                continue;
+            }
+            let inside = (0..3).all(|i| lo[i] <= corner[i] && corner[i] + (size - 1) <= hi[i]);
+            if inside || depth >= max_depth || depth == self._bits {
+                let span = 1u64 << (3 * (self._bits - depth));
+                let k = self.encode_cell(&corner) & !(span - 1);
+                out.push((k, k + span - 1));
+                continue;
+            }
+            let half = size / 2;
+            for c in 0..8u32 {
+                q.push((
+                    depth + 1,
+                    [
+                        corner[0] + (c & 1) * half,
+                        corner[1] + ((c >> 1) & 1) * half,
+                        corner[2] + ((c >> 2) & 1) * half,
+                    ],
+                ));
+            }
+        }
+        out.sort();
+        let mut merged: Vec<(u64, u64)> = vec![];
+        for r in out {
+            match merged.last_mut() {
+                Some(l) if l.1 + 1 == r.0 => l.1 = r.1,
+                _ => merged.push(r),
+            }
+        }
+        merged
+    }
+}
@@ -0,0 +1,329 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::ray::Ray3;
+
+use std::cmp::Ordering;
+use std::f64;
+
+/// sparse voxel octree storing an attribute per occupied voxel, regions that are uniformly empty
+/// or filled with one attribute are stored as a single node
+pub struct Svo<A>
+where
+    A: Clone + PartialEq,
+{
+    _bound: AxisAlignedBBox,
+    _depth: u32,
+    _nodes: Vec<NodeSvo<A>>, //children of a branch are 8 consecutive nodes
+    _free_blocks: Vec<usize>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum NodeSvo<A> {
+    Empty,
+    Leaf(A),
+    Branch(usize),
+}
+
+///content of a region of voxels
+#[derive(Debug, PartialEq)]
+pub enum VoxelState<'a, A> {
+    Empty,
+    Uniform(&'a A),
+    Mixed,
+}
+
+///region hit by a ray
+#[derive(Debug)]
+pub struct VoxelHit<'a, A> {
+    ///level of the uniform region hit, its voxels span 2^(depth - level) finest voxels per axis
+    pub _level: u32,
+    pub _coord: [u32; 3],
+    ///distance along the ray where it enters the region
+    pub _t: f64,
+    pub _attr: &'a A,
+}
+
+///maximum depth so that voxel coordinates fit in u32 per axis
+pub const MAX_DEPTH: u32 = 21;
+
+///index of the child containing coord at level, descending from depth d
+fn child_index(coord: &[u32; 3], level: u32, d: u32) -> usize {
+    let s = level - 1 - d;
+    (((coord[0] >> s) & 1) | ((coord[1] >> s) & 1) << 1 | ((coord[2] >> s) & 1) << 2) as usize
+}
+
+impl<A> Svo<A>
+where
+    A: Clone + PartialEq,
+{
+    ///empty octree over bound with 2^depth voxels per axis at the finest level
+    pub fn init(bound: AxisAlignedBBox, depth: u32) -> Svo<A> {
+        assert!(depth <= MAX_DEPTH);
+        Svo {
+            _bound: bound,
+            _depth: depth,
+            _nodes: vec![NodeSvo::Empty],
+            _free_blocks: vec![],
+        }
+    }
+    pub fn get_bound(&self) -> &AxisAlignedBBox {
+        &self._bound
+    }
+    pub fn get_depth(&self) -> u32 {
+        self._depth
+    }
+    ///number of nodes in use
+    pub fn node_count(&self) -> usize {
+        self._nodes.len() - self._free_blocks.len() * 8
+    }
+    ///number of occupied voxels at the finest level
+    pub fn voxel_count(&self) -> u64 {
+        let mut count = 0;
+        let mut q = vec![(0, 0)];
+        while let Some((n, level)) = q.pop() {
+            match self._nodes[n] {
+                NodeSvo::Leaf(_) => count += 1u64 << (3 * (self._depth - level)),
+                NodeSvo::Branch(c) => q.extend((c..c + 8).map(|x| (x, level + 1))),
+                NodeSvo::Empty => (),
+            }
+        }
+        count
+    }
+    ///bound of the voxel at coord of given level
+    pub fn get_voxel_bound(&self, level: u32, coord: &[u32; 3]) -> AxisAlignedBBox {
+        let n = f64::from(1u32 << level);
+        let mut b = self._bound.clone();
+        for (i, c) in coord.iter().enumerate() {
+            let s = (self._bound._bound_upper[i] - self._bound._bound_lower[i]) / n;
+            b._bound_lower[i] = self._bound._bound_lower[i] + f64::from(*c) * s;
+            b._bound_upper[i] = self._bound._bound_lower[i] + f64::from(*c + 1) * s;
+        }
+        b
+    }
+    ///finest voxel containing a point
+    pub fn locate(&self, p: &[f64; 3]) -> Option<[u32; 3]> {
+        let n = 1u64 << self._depth;
+        let mut c = [0u32; 3];
+        for i in 0..3 {
+            let l = self._bound._bound_lower[i];
+            let u = self._bound._bound_upper[i];
+            if !(l <= p[i] && p[i] <= u) {
+                return None;
+            }
+            let x = ((p[i] - l) / (u - l) * n as f64).floor() as u64;
+            c[i] = x.min(n - 1) as u32;
+        }
+        Some(c)
+    }
+    fn check(&self, level: u32, coord: &[u32; 3]) -> Result<(), &'static str> {
+        if level > self._depth {
+            return Err("level exceeds octree depth");
+        }
+        if coord.iter().any(|x| u64::from(*x) >= 1u64 << level) {
+            return Err("voxel out of range");
+        }
+        Ok(())
+    }
+    fn alloc_block(&mut self, fill: NodeSvo<A>) -> usize {
+        match self._free_blocks.pop() {
+            Some(c) => {
+                for i in c..c + 8 {
+                    self._nodes[i] = fill.clone();
+                }
+                c
+            }
+            _ => {
+                let c = self._nodes.len();
+                self._nodes.extend((0..8).map(|_| fill.clone()));
+                c
+            }
+        }
+    }
+    fn free_subtree(&mut self, n: usize) {
+        if let NodeSvo::Branch(c) = self._nodes[n] {
+            for i in c..c + 8 {
+                self.free_subtree(i);
+                self._nodes[i] = NodeSvo::Empty;
+            }
+            self._free_blocks.push(c);
+        }
+    }
+    ///replaces the region at coord of given level, subdividing uniform ancestors and merging
+    ///ancestors that become uniform
+    fn assign(
+        &mut self,
+        level: u32,
+        coord: &[u32; 3],
+        value: NodeSvo<A>,
+    ) -> Result<(), &'static str> {
+        self.check(level, coord)?;
+        let mut path = vec![];
+        let mut n = 0;
+        for d in 0..level {
+            let c = match self._nodes[n] {
+                NodeSvo::Branch(c) => c,
+                ref x if *x == value => return Ok(()),
+                _ => {
+                    let fill = self._nodes[n].clone();
+                    let c = self.alloc_block(fill);
+                    self._nodes[n] = NodeSvo::Branch(c);
+                    c
+                }
+            };
+            path.push(n);
+            n = c + child_index(coord, level, d);
+        }
+        self.free_subtree(n);
+        self._nodes[n] = value;
+
+        for p in path.into_iter().rev() {
+            let c = match self._nodes[p] {
+                NodeSvo::Branch(c) => c,
+                _ => break,
+            };
+            let first = &self._nodes[c];
+            let uniform = match *first {
+                NodeSvo::Branch(_) => false,
+                _ => self._nodes[c + 1..c + 8].iter().all(|x| x == first),
+            };
+            if !uniform {
+                break;
+            }
+            self._nodes[p] = first.clone();
+            for i in c..c + 8 {
+                self._nodes[i] = NodeSvo::Empty;
+            }
+            self._free_blocks.push(c);
+        }
+        Ok(())
+    }
+    ///fills the voxel at coord of given level with attr, level 0 being the whole bound
+    pub fn set(&mut self, level: u32, coord: &[u32; 3], attr: A) -> Result<(), &'static str> {
+        self.assign(level, coord, NodeSvo::Leaf(attr))
+    }
+    ///empties the voxel at coord of given level
+    pub fn clear(&mut self, level: u32, coord: &[u32; 3]) -> Result<(), &'static str> {
+        self.assign(level, coord, NodeSvo::Empty)
+    }
+    ///content of the voxel at coord of given level
+    pub fn get(&self, level: u32, coord: &[u32; 3]) -> Result<VoxelState<'_, A>, &'static str> {
+        self.check(level, coord)?;
+        let mut n = 0;
+        for d in 0..=level {
+            match self._nodes[n] {
+                NodeSvo::Empty => return Ok(VoxelState::Empty),
+                NodeSvo::Leaf(ref a) => return Ok(VoxelState::Uniform(a)),
+                NodeSvo::Branch(c) => {
+                    if d == level {
+                        break;
+                    }
+                    n = c + child_index(coord, level, d);
+                }
+            }
+        }
+        Ok(VoxelState::Mixed)
+    }
+    ///entry and exit distances of the ray through a box clipped to [0, t_max]
+    fn clip(b: &AxisAlignedBBox, o: &[f64; 3], d: &[f64; 3], t_max: f64) -> Option<(f64, f64)> {
+        let mut t0 = 0f64;
+        let mut t1 = t_max;
+        for i in 0..3 {
+            if d[i] == 0. {
+                if o[i] < b._bound_lower[i] || o[i] > b._bound_upper[i] {
+                    return None;
+                }
+                continue;
+            }
+            let mut a = (b._bound_lower[i] - o[i]) / d[i];
+            let mut c = (b._bound_upper[i] - o[i]) / d[i];
+            if a > c {
+                ::std::mem::swap(&mut a, &mut c);
+            }
+            t0 = t0.max(a);
+            t1 = t1.min(c);
+        }
+        if t0 <= t1 {
+            Some((t0, t1))
+        } else {
+            None
+        }
+    }
+    ///nearest uniform region along the ray up to distance t_max whose attribute is accepted by
+    ///f, children are visited front to back so traversal stops at the first accepted region
+    pub fn cast_ray<F>(
+        &self,
+        r: &Ray3,
+        t_max: f64,
+        mut f: F,
+    ) -> Result<Option<VoxelHit<'_, A>>, &'static str>
+    where
+        F: FnMut(&A) -> bool,
+    {
+        if t_max.is_nan() || t_max < 0. {
+            return Err("ray traversal distance must be non-negative");
+        }
+        let o = [r._ori[0], r._ori[1], r._ori[2]];
+        let d = [r._dir[0], r._dir[1], r._dir[2]];
+        let t = match Svo::<A>::clip(&self._bound, &o, &d, t_max) {
+            Some(t) => t,
+            _ => return Ok(None),
+        };
+        Ok(self.cast(0, 0, [0; 3], t.0, &o, &d, t_max, &mut f))
+    }
+    #[allow(clippy::too_many_arguments)]
+    fn cast<F>(
+        &self,
+        n: usize,
+        level: u32,
+        coord: [u32; 3],
+        t_entry: f64,
+        o: &[f64; 3],
+        d: &[f64; 3],
+        t_max: f64,
+        f: &mut F,
+    ) -> Option<VoxelHit<'_, A>>
+    where
+        F: FnMut(&A) -> bool,
+    {
+        match self._nodes[n] {
+            NodeSvo::Empty => None,
+            NodeSvo::Leaf(ref a) => {
+                if f(a) {
+                    Some(VoxelHit {
+                        _level: level,
+                        _coord: coord,
+                        _t: t_entry,
+                        _attr: a,
+                    })
+                } else {
+                    None
+                }
+            }
+            NodeSvo::Branch(c) => {
+                let mut children = vec![];
+                for i in 0..8u32 {
+                    if self._nodes[c + i as usize] == NodeSvo::Empty {
+                        continue;
+                    }
+                    let cc = [
+                        coord[0] * 2 + (i & 1),
+                        coord[1] * 2 + ((i >> 1) & 1),
+                        coord[2] * 2 + ((i >> 2) & 1),
+                    ];
+                    let b = self.get_voxel_bound(level + 1, &cc);
+                    if let Some(t) = Svo::<A>::clip(&b, o, d, t_max) {
+                        children.push((t.0, c + i as usize, cc));
+                    }
+                }
+                children.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+                for (t, i, cc) in children {
+                    if let Some(h) = self.cast(i, level + 1, cc, t, o, d, t_max, f) {
+                        return Some(h);
+                    }
+                }
+                None
+            }
+        }
+    }
+}
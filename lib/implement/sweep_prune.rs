@@ -0,0 +1,362 @@
+extern crate mazth;
+
+use self::mazth::bound::AxisAlignedBBox;
+use self::mazth::i_bound::{BoundType, IBound};
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use implement::bound_util::bound_copy;
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// implementation of a sort-and-sweep broadphase, endpoints of each axis are kept sorted with
+/// insertion sort so that coherent motion between frames costs close to linear time
+pub struct SweepPrune<T>
+where
+    T: Default + Clone,
+{
+    _axes: [Vec<Endpoint>; 3],
+    _objs: Vec<Option<ObjSweepPrune<T>>>,
+    _free: Vec<usize>,
+    _pairs: HashSet<(usize, usize)>,
+    _events: Vec<OverlapEvent>,
+    _max_extent: f64, //upper limit on x extent of stored objects, not lowered on removal
+}
+
+///change in the overlap state of a pair of handles, given with the smaller handle first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapEvent {
+    Begin(usize, usize),
+    End(usize, usize),
+}
+
+#[derive(Clone, Copy)]
+struct Endpoint {
+    _value: f64,
+    _handle: usize,
+    _is_max: bool,
+}
+
+struct ObjSweepPrune<T> {
+    _obj: T,
+    _bound: AxisAlignedBBox,
+    _ends: [[usize; 2]; 3], //position of min and max endpoint per axis
+}
+
+fn overlaps(a: &AxisAlignedBBox, b: &AxisAlignedBBox) -> bool {
+    (0..3).all(|i| a._bound_lower[i] <= b._bound_upper[i] && b._bound_lower[i] <= a._bound_upper[i])
+}
+
+fn pair(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+///ordering of endpoints, a min precedes a max of equal value so touching boxes overlap
+fn less(a: &Endpoint, b: &Endpoint) -> bool {
+    a._value < b._value || (a._value == b._value && !a._is_max && b._is_max)
+}
+
+impl<T> Default for SweepPrune<T>
+where
+    T: Default + Clone,
+{
+    fn default() -> SweepPrune<T> {
+        SweepPrune::init()
+    }
+}
+
+impl<T> SweepPrune<T>
+where
+    T: Default + Clone,
+{
+    pub fn init() -> SweepPrune<T> {
+        SweepPrune {
+            _axes: [vec![], vec![], vec![]],
+            _objs: vec![],
+            _free: vec![],
+            _pairs: HashSet::new(),
+            _events: vec![],
+            _max_extent: 0.,
+        }
+    }
+    ///number of objects stored
+    pub fn len(&self) -> usize {
+        self._objs.len() - self._free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn get(&self, handle: usize) -> Option<&T> {
+        match self._objs.get(handle) {
+            Some(Some(o)) => Some(&o._obj),
+            _ => None,
+        }
+    }
+    ///currently overlapping pairs of handles in increasing order
+    pub fn get_pairs(&self) -> Vec<(usize, usize)> {
+        let mut v = self._pairs.iter().cloned().collect::<Vec<_>>();
+        v.sort();
+        v
+    }
+    ///overlap events in the order they occurred since the last call
+    pub fn take_events(&mut self) -> Vec<OverlapEvent> {
+        std::mem::take(&mut self._events)
+    }
+    fn begin(&mut self, a: usize, b: usize) {
+        let ba = &self._objs[a].as_ref().unwrap()._bound;
+        let bb = &self._objs[b].as_ref().unwrap()._bound;
+        if overlaps(ba, bb) && self._pairs.insert(pair(a, b)) {
+            let p = pair(a, b);
+            self._events.push(OverlapEvent::Begin(p.0, p.1));
+        }
+    }
+    fn end(&mut self, a: usize, b: usize) {
+        if self._pairs.remove(&pair(a, b)) {
+            let p = pair(a, b);
+            self._events.push(OverlapEvent::End(p.0, p.1));
+        }
+    }
+    fn set_position(&mut self, axis: usize, i: usize) {
+        let e = self._axes[axis][i];
+        self._objs[e._handle].as_mut().unwrap()._ends[axis][e._is_max as usize] = i;
+    }
+    ///swaps endpoint i with its left neighbour, updating overlap state of the two owners
+    fn swap_left(&mut self, axis: usize, i: usize) {
+        let moving = self._axes[axis][i];
+        let other = self._axes[axis][i - 1];
+        self._axes[axis].swap(i - 1, i);
+        self.set_position(axis, i - 1);
+        self.set_position(axis, i);
+        if moving._is_max == other._is_max {
+            return;
+        }
+        if !moving._is_max {
+            //min moved before a max
+            self.begin(moving._handle, other._handle);
+        } else {
+            //max moved before a min
+            self.end(moving._handle, other._handle);
+        }
+    }
+    fn sift(&mut self, axis: usize, i: usize) {
+        let mut i = i;
+        while i > 0 && less(&self._axes[axis][i], &self._axes[axis][i - 1]) {
+            self.swap_left(axis, i);
+            i -= 1;
+        }
+        while i + 1 < self._axes[axis].len() && less(&self._axes[axis][i + 1], &self._axes[axis][i])
+        {
+            self.swap_left(axis, i + 1);
+            i += 1;
+        }
+    }
+    ///re-sorts endpoints of an object after its bound changed, leading endpoint first so the
+    ///interval is never inverted
+    fn resort(&mut self, handle: usize, old: &AxisAlignedBBox) {
+        for axis in 0..3 {
+            let (ends, b) = {
+                let o = self._objs[handle].as_ref().unwrap();
+                (o._ends[axis], o._bound.clone())
+            };
+            self._axes[axis][ends[0]]._value = b._bound_lower[axis];
+            self._axes[axis][ends[1]]._value = b._bound_upper[axis];
+            if b._bound_lower[axis] < old._bound_lower[axis] {
+                self.sift(axis, ends[0]);
+                let e = self._objs[handle].as_ref().unwrap()._ends[axis][1];
+                self.sift(axis, e);
+            } else {
+                self.sift(axis, ends[1]);
+                let e = self._objs[handle].as_ref().unwrap()._ends[axis][0];
+                self.sift(axis, e);
+            }
+        }
+    }
+    fn check_bound(b: &dyn IBound) -> Result<AxisAlignedBBox, &'static str> {
+        match b.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let a = bound_copy(b);
+        if (0..3).any(|i| !a._bound_lower[i].is_finite() || !a._bound_upper[i].is_finite()) {
+            return Err("sweep and prune requires finite bounds");
+        }
+        if (0..3).any(|i| a._bound_lower[i] > a._bound_upper[i]) {
+            return Err("sweep and prune requires lower bound not above upper bound");
+        }
+        Ok(a)
+    }
+    fn extend(&mut self, b: &AxisAlignedBBox) {
+        self._max_extent = self._max_extent.max(b._bound_upper[0] - b._bound_lower[0]);
+    }
+    ///add an object and return its handle
+    pub fn insert(&mut self, obj: T, b: &dyn IBound) -> Result<usize, &'static str> {
+        let bound = SweepPrune::<T>::check_bound(b)?;
+        self.extend(&bound);
+        let o = ObjSweepPrune {
+            _obj: obj,
+            _bound: bound,
+            _ends: [[0; 2]; 3],
+        };
+        let h = match self._free.pop() {
+            Some(h) => {
+                self._objs[h] = Some(o);
+                h
+            }
+            _ => {
+                self._objs.push(Some(o));
+                self._objs.len() - 1
+            }
+        };
+        //append at the far end then sort into place
+        let far = AxisAlignedBBox {
+            _bound_lower: [f64::INFINITY; 3],
+            _bound_upper: [f64::INFINITY; 3],
+        };
+        for axis in 0..3 {
+            for is_max in [false, true] {
+                self._axes[axis].push(Endpoint {
+                    _value: f64::INFINITY,
+                    _handle: h,
+                    _is_max: is_max,
+                });
+                let i = self._axes[axis].len() - 1;
+                self.set_position(axis, i);
+            }
+        }
+        self.resort(h, &far);
+        Ok(h)
+    }
+    ///remove an object by handle and return it, ending all of its overlaps
+    pub fn remove(&mut self, handle: usize) -> Result<T, &'static str> {
+        let old = match self._objs.get(handle) {
+            Some(Some(o)) => o._bound.clone(),
+            _ => return Err("invalid handle"),
+        };
+        //move to the far end so that it separates from everything, then drop its endpoints
+        self._objs[handle].as_mut().unwrap()._bound = AxisAlignedBBox {
+            _bound_lower: [f64::INFINITY; 3],
+            _bound_upper: [f64::INFINITY; 3],
+        };
+        self.resort(handle, &old);
+        for axis in self._axes.iter_mut() {
+            axis.truncate(axis.len() - 2);
+        }
+        self._free.push(handle);
+        Ok(self._objs[handle].take().unwrap()._obj)
+    }
+    ///move an object to a new bound
+    pub fn update(&mut self, handle: usize, b: &dyn IBound) -> Result<(), &'static str> {
+        let bound = SweepPrune::<T>::check_bound(b)?;
+        let old = match self._objs.get_mut(handle) {
+            Some(Some(o)) => std::mem::replace(&mut o._bound, bound.clone()),
+            _ => return Err("invalid handle"),
+        };
+        self.extend(&bound);
+        self.resort(handle, &old);
+        Ok(())
+    }
+    fn query(&self, input: &dyn IBound, single: bool) -> Result<Vec<T>, &'static str> {
+        match input.get_type() {
+            BoundType::AxisAlignBox => (),
+            _ => return Err("unsupported bound type"),
+        }
+        let d = input.get_bound_data();
+        let mut out = vec![];
+        //objects overlapping the query start no further than the widest object before it
+        let start = d[0] - self._max_extent;
+        let first = self._axes[0].partition_point(|e| e._value < start);
+        //sweep along x up to the end of the query
+        for e in self._axes[0][first..].iter() {
+            if e._value > d[3] {
+                break;
+            }
+            if e._is_max {
+                continue;
+            }
+            let o = self._objs[e._handle].as_ref().unwrap();
+            if o._bound.intersect(input) {
+                out.push(o._obj.clone());
+                if single {
+                    break;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl<T> ISpatialAccel<T> for SweepPrune<T>
+where
+    T: Default + Clone,
+{
+    fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, false)
+    }
+    fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str> {
+        self.query(input, true)
+    }
+    ///replaces content by sorting all endpoints at once, reporting a begin event for each
+    ///initial overlap, handles of the objects follow input order
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str> {
+        let mut bounds = vec![];
+        for i in objs {
+            bounds.push(SweepPrune::<T>::check_bound(i.1)?);
+        }
+        *self = SweepPrune::init();
+        for (i, b) in objs.iter().zip(bounds) {
+            self.extend(&b);
+            self._objs.push(Some(ObjSweepPrune {
+                _obj: i.0.clone(),
+                _bound: b,
+                _ends: [[0; 2]; 3],
+            }));
+        }
+        for axis in 0..3 {
+            let mut v = vec![];
+            for (h, o) in self._objs.iter().enumerate() {
+                let b = &o.as_ref().unwrap()._bound;
+                v.push(Endpoint {
+                    _value: b._bound_lower[axis],
+                    _handle: h,
+                    _is_max: false,
+                });
+                v.push(Endpoint {
+                    _value: b._bound_upper[axis],
+                    _handle: h,
+                    _is_max: true,
+                });
+            }
+            v.sort_by(|a, b| {
+                if less(a, b) {
+                    Ordering::Less
+                } else if less(b, a) {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            });
+            self._axes[axis] = v;
+            for i in 0..self._axes[axis].len() {
+                self.set_position(axis, i);
+            }
+        }
+        //sweep x with an active list to find the initial overlaps
+        let mut active: Vec<usize> = vec![];
+        for i in 0..self._axes[0].len() {
+            let e = self._axes[0][i];
+            if e._is_max {
+                active.retain(|x| *x != e._handle);
+            } else {
+                for a in active.clone() {
+                    self.begin(a, e._handle);
+                }
+                active.push(e._handle);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64;
+
+use implement::bound_util::Dist;
+use interface::i_metric::IMetric;
+
+/// vantage-point tree for similarity search in a metric space given by a user supplied metric
+///
+/// nodes are stored implicitly: the first item of each index range is the vantage point, followed
+/// by the inner half of the remaining items within distance mu of it and then the outer half
+pub struct VpTree<P, T, M>
+where
+    T: Default + Clone,
+    M: IMetric<P>,
+{
+    _metric: M,
+    _items: Vec<NodeVpTree<P, T>>,
+}
+
+///item of the tree together with the median distance splitting the range it is vantage point of
+pub struct NodeVpTree<P, T> {
+    _point: P,
+    _obj: T,
+    _mu: f64,
+}
+
+///L1 distance between coordinate vectors of equal length
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricL1;
+
+///L2 distance between coordinate vectors of equal length
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricL2;
+
+impl<P> IMetric<P> for MetricL1
+where
+    P: AsRef<[f64]>,
+{
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        a.as_ref()
+            .iter()
+            .zip(b.as_ref().iter())
+            .fold(0., |acc, (x, y)| acc + (x - y).abs())
+    }
+}
+
+impl<P> IMetric<P> for MetricL2
+where
+    P: AsRef<[f64]>,
+{
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        a.as_ref()
+            .iter()
+            .zip(b.as_ref().iter())
+            .fold(0., |acc, (x, y)| acc + (x - y) * (x - y))
+            .sqrt()
+    }
+}
+
+impl<P, T, M> VpTree<P, T, M>
+where
+    P: Clone,
+    T: Default + Clone,
+    M: IMetric<P>,
+{
+    pub fn init(metric: M) -> VpTree<P, T, M> {
+        VpTree {
+            _metric: metric,
+            _items: vec![],
+        }
+    }
+    ///number of items stored
+    pub fn len(&self) -> usize {
+        self._items.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._items.is_empty()
+    }
+    ///replaces content of the tree with the given items
+    pub fn build(&mut self, items: &[(T, P)]) -> Result<(), &'static str> {
+        self._items = items
+            .iter()
+            .map(|x| NodeVpTree {
+                _point: x.1.clone(),
+                _obj: x.0.clone(),
+                _mu: 0.,
+            })
+            .collect();
+        let mut q = vec![(0, self._items.len())];
+        while let Some((lo, hi)) = q.pop() {
+            if hi - lo < 2 {
+                continue;
+            }
+            //use the item farthest from an arbitrary one as vantage point, favouring the periphery
+            let mut far = (f64::NEG_INFINITY, lo);
+            for i in lo..hi {
+                let d = self
+                    ._metric
+                    .distance(&self._items[lo]._point, &self._items[i]._point);
+                if d.is_nan() || d < 0. {
+                    return Err("metric returned invalid distance");
+                }
+                if d > far.0 {
+                    far = (d, i);
+                }
+            }
+            self._items.swap(lo, far.1);
+
+            let mut d = self._items[lo + 1..hi]
+                .iter()
+                .map(|x| self._metric.distance(&self._items[lo]._point, &x._point))
+                .enumerate()
+                .collect::<Vec<_>>();
+            if d.iter().any(|x| x.1.is_nan() || x.1 < 0.) {
+                return Err("metric returned invalid distance");
+            }
+            let half = d.len() / 2;
+            d.select_nth_unstable_by(half, |a, b| {
+                a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal)
+            });
+            self._items[lo]._mu = d[half].1;
+
+            //reorder the remaining items following the partition
+            let mut rest = self._items.drain(lo + 1..hi).map(Some).collect::<Vec<_>>();
+            let reordered = d
+                .iter()
+                .map(|x| rest[x.0].take().unwrap())
+                .collect::<Vec<_>>();
+            self._items.splice(lo + 1..lo + 1, reordered);
+
+            let mid = lo + 1 + half;
+            q.push((lo + 1, mid));
+            q.push((mid, hi));
+        }
+        Ok(())
+    }
+    ///k items closest to p with their distances in increasing order
+    pub fn query_nearest(&self, p: &P, k: usize) -> Vec<(T, f64)> {
+        let mut best = BinaryHeap::new();
+        if k > 0 {
+            self.nearest(0, self._items.len(), p, k, &mut best);
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|x| (self._items[x.1]._obj.clone(), x.0))
+            .collect()
+    }
+    fn nearest(&self, lo: usize, hi: usize, p: &P, k: usize, best: &mut BinaryHeap<Dist>) {
+        if lo >= hi {
+            return;
+        }
+        let n = &self._items[lo];
+        let d = self._metric.distance(p, &n._point);
+        if best.len() < k {
+            best.push(Dist(d, lo));
+        } else if d < best.peek().unwrap().0 {
+            best.pop();
+            best.push(Dist(d, lo));
+        }
+        let mid = lo + 1 + (hi - lo - 1) / 2;
+        let tau = |best: &BinaryHeap<Dist>| {
+            if best.len() < k {
+                f64::INFINITY
+            } else {
+                best.peek().unwrap().0
+            }
+        };
+        if d < n._mu {
+            self.nearest(lo + 1, mid, p, k, best);
+            if d + tau(best) >= n._mu {
+                self.nearest(mid, hi, p, k, best);
+            }
+        } else {
+            self.nearest(mid, hi, p, k, best);
+            if d - tau(best) <= n._mu {
+                self.nearest(lo + 1, mid, p, k, best);
+            }
+        }
+    }
+    ///items within distance r of p with their distances in increasing order
+    pub fn query_radius(&self, p: &P, r: f64) -> Vec<(T, f64)> {
+        let mut out = vec![];
+        let mut q = vec![(0, self._items.len())];
+        while let Some((lo, hi)) = q.pop() {
+            if lo >= hi {
+                continue;
+            }
+            let n = &self._items[lo];
+            let d = self._metric.distance(p, &n._point);
+            if d <= r {
+                out.push(Dist(d, lo));
+            }
+            let mid = lo + 1 + (hi - lo - 1) / 2;
+            if d - r <= n._mu {
+                q.push((lo + 1, mid));
+            }
+            if d + r >= n._mu {
+                q.push((mid, hi));
+            }
+        }
+        out.sort();
+        out.into_iter()
+            .map(|x| (self._items[x.1]._obj.clone(), x.0))
+            .collect()
+    }
+}
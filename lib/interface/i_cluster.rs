@@ -1,28 +1,46 @@
-use interface::i_bound::IBound;
+extern crate mazth;
 
+use self::mazth::i_bound::IBound;
+
+///number of clusters to form
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ClusterSize {
     Auto,
-    Manual( usize ),
+    Manual(usize),
 }
 
+///distance used between objects, L1 makes centre updates use medians
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ClusterMetric {
     L1,
     L2,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ClusterMethod {
     Density,
     Average,
     Gaussian,
 }
 
+///how initial cluster centres are chosen
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ClusterOrder {
     InOrder,
     PreSample,
 }
 
-/// acceleration interface for building and querying spatial data
+/// clustering interface for grouping objects by the centroids of their bounds
 pub trait ICluster {
-    fn query_cluster( & self, input: u64 ) -> Result< u64, & 'static str >;
-    fn build_all( & mut self, ClusterSize, ClusterMethod, ClusterMetric, ClusterOrder, input: &[ (u64, &IBound) ] ) -> Result< (), & 'static str >;
+    /// cluster id of an object id
+    fn query_cluster(&self, input: u64) -> Result<u64, &'static str>;
+    /// form clusters over input objects ids and bounds
+    fn build_all(
+        &mut self,
+        size: ClusterSize,
+        method: ClusterMethod,
+        metric: ClusterMetric,
+        order: ClusterOrder,
+        input: &[(u64, &dyn IBound)],
+    ) -> Result<(), &'static str>;
 }
@@ -0,0 +1,14 @@
+/// distance between two values, expected to be non-negative, symmetric and to satisfy the
+/// triangle inequality
+pub trait IMetric<P> {
+    fn distance(&self, a: &P, b: &P) -> f64;
+}
+
+impl<P, F> IMetric<P> for F
+where
+    F: Fn(&P, &P) -> f64,
+{
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        self(a, b)
+    }
+}
@@ -0,0 +1,5 @@
+/// associative combination of values with an identity element, used for subtree aggregates
+pub trait IMonoid {
+    fn identity() -> Self;
+    fn combine(&self, other: &Self) -> Self;
+}
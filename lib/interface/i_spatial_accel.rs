@@ -11,5 +11,5 @@ where
     fn query_intersect(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str>;
     fn query_intersect_single(&self, input: &dyn IBound) -> Result<Vec<T>, &'static str>;
     /// build a acceleration structure with input bounds and object ids
-    fn build_all(&mut self, &[(T, &dyn IBound)]) -> Result<(), &'static str>;
+    fn build_all(&mut self, objs: &[(T, &dyn IBound)]) -> Result<(), &'static str>;
 }
@@ -0,0 +1,21 @@
+extern crate mazth;
+
+use self::mazth::i_bound::IBound;
+
+use interface::i_spatial_accel::ISpatialAccel;
+
+/// acceleration interface with per-object category masks for filtered queries
+pub trait ISpatialAccelMask<T>: ISpatialAccel<T>
+where
+    T: Default + Clone,
+{
+    /// query for a list of objects intersecting with input whose mask shares a bit with filter mask
+    fn query_intersect_mask(&self, input: &dyn IBound, mask: u64) -> Result<Vec<T>, &'static str>;
+    fn query_intersect_single_mask(
+        &self,
+        input: &dyn IBound,
+        mask: u64,
+    ) -> Result<Vec<T>, &'static str>;
+    /// build a acceleration structure with input bounds, object ids and object category masks
+    fn build_all_mask(&mut self, objs: &[(T, &dyn IBound, u64)]) -> Result<(), &'static str>;
+}
@@ -1,4 +1,7 @@
+pub mod i_cluster;
+pub mod i_metric;
+pub mod i_monoid;
 pub mod i_spatial_accel;
+pub mod i_spatial_accel_mask;
 pub mod i_stat;
 pub mod i_stat_tree;
-//pub mod i_cluster;
@@ -0,0 +1,122 @@
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use implement::balltree::{BallTree, Kernel};
+
+fn dist(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .fold(0., |acc, (x, y)| acc + (x - y) * (x - y))
+        .sqrt()
+}
+
+#[test]
+fn test_balltree_invalid_input() {
+    let mut a = BallTree::init(4, 8);
+    match a.build(&[(0u32, vec![0., 0., 0.])]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for point of wrong dimension"),
+    }
+    match a.build(&[(0u32, vec![0., 0., 0., f64::NAN])]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for non finite point"),
+    }
+    a.build(&[(0u32, vec![0., 0., 0., 0.])])
+        .expect("build unexpected result");
+    match a.query_nearest(&[0., 0.], 1) {
+        Err(_) => (),
+        _ => panic!("unexpected result for query of wrong dimension"),
+    }
+    match a.query_kernel_density(&[0., 0., 0., 0.], Kernel::Gaussian, 0., 0.) {
+        Err(_) => (),
+        _ => panic!("unexpected result for zero bandwidth"),
+    }
+}
+
+#[test]
+fn test_balltree_duplicate_points() {
+    let mut a = BallTree::init(2, 2);
+    let pts = (0..10u32).map(|i| (i, vec![1., 1.])).collect::<Vec<_>>();
+    a.build(&pts[..]).expect("build unexpected result");
+    assert_eq!(a.node_count(), 1);
+    match a.query_radius(&[1., 2.], 1.) {
+        Ok(o) => assert_eq!(o.len(), 10),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_kernel_density(&[1., 1.], Kernel::Tophat, 0.5, 0.) {
+        Ok(o) => assert_eq!(o, 10.),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_balltree_random_brute_force() {
+    let mut rng = StdRng::seed_from_u64(29);
+    let dim = 16;
+    let pts = (0..3000u32)
+        .map(|i| {
+            //clustered data as is typical of feature vectors
+            let c = f64::from(i % 5);
+            (
+                i,
+                (0..dim)
+                    .map(|_| c + rng.gen_range(-0.5, 0.5))
+                    .collect::<Vec<f64>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut a = BallTree::init(dim, 16);
+    a.build(&pts[..]).expect("build unexpected result");
+    assert_eq!(a.len(), 3000);
+    assert_eq!(a.get_dim(), 16);
+
+    for _ in 0..20 {
+        let c = f64::from(rng.gen_range(0, 5));
+        let p = (0..dim)
+            .map(|_| c + rng.gen_range(-0.7, 0.7))
+            .collect::<Vec<f64>>();
+        let mut expect = pts
+            .iter()
+            .map(|x| (dist(&p[..], &x.1[..]), x.0))
+            .collect::<Vec<_>>();
+        expect.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        match a.query_nearest(&p[..], 10) {
+            Ok(o) => {
+                assert_eq!(o.len(), 10);
+                for (i, j) in o.iter().zip(expect.iter()) {
+                    assert!((i.1 - j.0).abs() < 1e-9);
+                }
+            }
+            _ => panic!("query unexpected result"),
+        }
+
+        let r = expect[100].0;
+        match a.query_radius(&p[..], r) {
+            Ok(o) => {
+                let e = expect.iter().filter(|x| x.0 <= r).collect::<Vec<_>>();
+                assert_eq!(o.len(), e.len());
+                for (i, j) in o.iter().zip(e.iter()) {
+                    assert!((i.1 - j.0).abs() < 1e-9);
+                }
+            }
+            _ => panic!("query unexpected result"),
+        }
+
+        for kernel in [Kernel::Gaussian, Kernel::Epanechnikov, Kernel::Tophat] {
+            let h = 0.8;
+            let exact = expect.iter().map(|x| kernel.eval(x.0 / h)).sum::<f64>();
+            match a.query_kernel_density(&p[..], kernel, h, 0.) {
+                Ok(o) => assert!((o - exact).abs() < 1e-6 * exact.max(1.)),
+                _ => panic!("query unexpected result"),
+            }
+            let atol = 1e-3;
+            match a.query_kernel_density(&p[..], kernel, h, atol) {
+                Ok(o) => assert!((o - exact).abs() <= atol / 2. * pts.len() as f64 + 1e-9),
+                _ => panic!("query unexpected result"),
+            }
+        }
+    }
+}
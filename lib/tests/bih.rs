@@ -0,0 +1,192 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+    ray::Ray3,
+};
+use implement::bih::Bih;
+use implement::bvh::Bvh;
+use interface::i_spatial_accel::ISpatialAccel;
+
+#[test]
+fn test_bih_unsupported_bounds() {
+    let mut a = Bih::init(2);
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for unsupported bound type"),
+    }
+    let inf = AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., f64::INFINITY, 1., 1.]);
+    let objs = [(0u64, &inf as &dyn IBound)];
+    assert!(a.build_all(&objs[..]).is_err());
+
+    a.build_all(&[]).expect("build");
+    assert!(a.is_empty());
+    let q = AxisAlignedBBox::init(ShapeType::Box, &[0., 0., 0., 1.]);
+    match a.query_intersect(&q) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect(&b) {
+        Err(_) => (),
+        _ => panic!("unexpected result for unsupported bound type"),
+    }
+}
+
+#[test]
+fn test_bih_construction_and_query() {
+    let bounds = [
+        AxisAlignedBBox::init(ShapeType::Box, &[0., 0., 0., 1.]),
+        AxisAlignedBBox::init(ShapeType::Box, &[10., 0., 0., 1.]),
+        AxisAlignedBBox::init(ShapeType::Box, &[20., 0., 0., 1.]),
+        AxisAlignedBBox::init(ShapeType::Box, &[30., 0., 0., 1.]),
+        //coincident centres stay together in one leaf
+        AxisAlignedBBox::init(ShapeType::Box, &[30., 0., 0., 2.]),
+    ];
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u32, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = Bih::init(1);
+    a.build_all(&objs[..]).expect("build");
+    assert_eq!(a.len(), 5);
+    assert_eq!(a.node_count(), 7);
+    assert_eq!(a.get_bound()._bound_lower, [-1., -2., -2.]);
+
+    let q = AxisAlignedBBox::init(ShapeType::Box, &[10.5, 0., 0., 0.1]);
+    match a.query_intersect(&q) {
+        Ok(o) => assert_eq!(o, vec![1]),
+        _ => panic!("query unexpected result"),
+    }
+    //gap between clip planes
+    let q = AxisAlignedBBox::init(ShapeType::Box, &[15., 0., 0., 1.]);
+    match a.query_intersect(&q) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    let q = AxisAlignedBBox::init(ShapeType::Box, &[28., 0., 0., 0.5]);
+    match a.query_intersect(&q) {
+        Ok(o) => assert_eq!(o, vec![4]),
+        _ => panic!("query unexpected result"),
+    }
+
+    let r = Ray3::init(&[50., 0.5, 0.5], &[-1., 0., 0.]);
+    match a.query_ray(&r) {
+        Ok(o) => {
+            let ids = o.iter().map(|x| x.0).collect::<Vec<_>>();
+            assert_eq!(ids, vec![4, 3, 2, 1, 0]);
+            assert!((o[0].1 - 18.).abs() < 1e-9);
+            assert!((o[1].1 - 19.).abs() < 1e-9);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_ray_nearest(&r) {
+        Ok(Some((4, t))) => assert!((t - 18.).abs() < 1e-9),
+        _ => panic!("query unexpected result"),
+    }
+    let r = Ray3::init(&[50., 5., 0.], &[-1., 0., 0.]);
+    match a.query_ray_nearest(&r) {
+        Ok(None) => (),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+fn random_bounds(rng: &mut StdRng, n: usize) -> Vec<AxisAlignedBBox> {
+    (0..n)
+        .map(|_| {
+            AxisAlignedBBox::init(
+                ShapeType::Box,
+                &[
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(0.1, 5.),
+                ],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_bih_compare_bvh() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let bounds = random_bounds(&mut rng, 2000);
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = Bih::init(4);
+    a.build_all(&objs[..]).expect("build");
+    let mut b = Bvh::init(10);
+    b.build_all(&objs[..]).expect("build");
+
+    for _ in 0..200 {
+        let q = random_bounds(&mut rng, 1).pop().unwrap();
+        let mut x = a.query_intersect(&q).expect("query");
+        let mut y = b.query_intersect(&q).expect("query");
+        x.sort();
+        y.sort();
+        assert_eq!(x, y);
+        match a.query_intersect_single(&q) {
+            Ok(o) => assert_eq!(o.len(), x.len().min(1)),
+            _ => panic!("query unexpected result"),
+        }
+    }
+
+    for _ in 0..200 {
+        let o = [
+            rng.gen_range(-150., 150.),
+            rng.gen_range(-150., 150.),
+            rng.gen_range(-150., 150.),
+        ];
+        let d = [
+            rng.gen_range(-1., 1.),
+            rng.gen_range(-1., 1.),
+            rng.gen_range(-1., 1.),
+        ];
+        let r = Ray3::init(&o, &d);
+        let mut expect = vec![];
+        for (i, b) in bounds.iter().enumerate() {
+            let mut t0 = 0f64;
+            let mut t1 = f64::INFINITY;
+            for k in 0..3 {
+                let mut s0 = (b._bound_lower[k] - r._ori[k]) / r._dir[k];
+                let mut s1 = (b._bound_upper[k] - r._ori[k]) / r._dir[k];
+                if s0 > s1 {
+                    ::std::mem::swap(&mut s0, &mut s1);
+                }
+                t0 = t0.max(s0);
+                t1 = t1.min(s1);
+            }
+            if t0 <= t1 {
+                expect.push((t0, i));
+            }
+        }
+        expect.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        match a.query_ray(&r) {
+            Ok(hits) => {
+                assert_eq!(hits.len(), expect.len());
+                for (h, e) in hits.iter().zip(expect.iter()) {
+                    assert_eq!(h.0, e.1);
+                    assert!((h.1 - e.0).abs() < 1e-9);
+                }
+            }
+            _ => panic!("query unexpected result"),
+        }
+        match a.query_ray_nearest(&r) {
+            Ok(Some((i, t))) => {
+                assert_eq!(i, expect[0].1);
+                assert!((t - expect[0].0).abs() < 1e-9);
+            }
+            Ok(None) => assert!(expect.is_empty()),
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use implement::bsp::{Bsp, Classification, Plane, PlaneSelect};
+
+///faces of an axis aligned box as quads with outward normals
+fn box_faces(lower: [f64; 3], upper: [f64; 3]) -> Vec<(u32, Vec<[f64; 3]>)> {
+    let mut out = vec![];
+    for axis in 0..3 {
+        for side in 0..2 {
+            let u = (axis + 1) % 3;
+            let v = (axis + 2) % 3;
+            let w = if side == 0 { lower[axis] } else { upper[axis] };
+            let mut quad = vec![];
+            for (a, b) in [(0, 0), (1, 0), (1, 1), (0, 1)] {
+                let mut p = [0.; 3];
+                p[axis] = w;
+                p[u] = if a == 0 { lower[u] } else { upper[u] };
+                p[v] = if b == 0 { lower[v] } else { upper[v] };
+                quad.push(p);
+            }
+            let n = Plane::from_polygon(&quad[..]).unwrap()._normal;
+            if (n[axis] > 0.) != (side == 1) {
+                quad.reverse();
+            }
+            out.push((out.len() as u32, quad));
+        }
+    }
+    out
+}
+
+fn area(verts: &[[f64; 3]]) -> f64 {
+    let mut n = [0.; 3];
+    for i in 1..verts.len() - 1 {
+        let a = verts[0];
+        let b = verts[i];
+        let c = verts[i + 1];
+        let e1 = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let e2 = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        n[0] += e1[1] * e2[2] - e1[2] * e2[1];
+        n[1] += e1[2] * e2[0] - e1[0] * e2[2];
+        n[2] += e1[0] * e2[1] - e1[1] * e2[0];
+    }
+    (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt() / 2.
+}
+
+#[test]
+fn test_bsp_invalid_polygons() {
+    let mut a: Bsp<u32> = Bsp::init(PlaneSelect::First, 1e-9);
+    match a.build(&[(0, vec![[0., 0., 0.], [1., 0., 0.]])]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for polygon with 2 vertices"),
+    }
+    match a.build(&[(0, vec![[0., 0., 0.], [1., 0., 0.], [2., 0., 0.]])]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for degenerate polygon"),
+    }
+    a.build(&[]).expect("build unexpected result");
+    assert!(a.is_empty());
+    assert_eq!(a.classify_point(&[0., 0., 0.]), Classification::Outside);
+}
+
+#[test]
+fn test_bsp_classify_solid() {
+    for select in [
+        PlaneSelect::First,
+        PlaneSelect::Heuristic {
+            split_weight: 8.,
+            candidates: 4,
+        },
+    ] {
+        let mut a = Bsp::init(select, 1e-9);
+        a.build(&box_faces([0., 0., 0.], [2., 2., 2.])[..])
+            .expect("build unexpected result");
+        //faces of a convex solid never need splitting
+        assert_eq!(a.split_count(), 0);
+        assert_eq!(a.len(), 6);
+        assert_eq!(a.classify_point(&[1., 1., 1.]), Classification::Inside);
+        assert_eq!(a.classify_point(&[0.1, 1.9, 0.5]), Classification::Inside);
+        assert_eq!(a.classify_point(&[3., 1., 1.]), Classification::Outside);
+        assert_eq!(a.classify_point(&[-1., -1., -1.]), Classification::Outside);
+        assert_eq!(a.classify_point(&[2., 1., 1.]), Classification::Boundary);
+        assert_eq!(a.classify_point(&[0., 0., 1.]), Classification::Boundary);
+    }
+}
+
+#[test]
+fn test_bsp_painter_order() {
+    //parallel squares stacked along z, facing +z
+    let polys = (0..5)
+        .map(|i| {
+            let z = i as f64;
+            (i, vec![[0., 0., z], [1., 0., z], [1., 1., z], [0., 1., z]])
+        })
+        .collect::<Vec<_>>();
+    let mut a = Bsp::init(
+        PlaneSelect::Heuristic {
+            split_weight: 8.,
+            candidates: 5,
+        },
+        1e-9,
+    );
+    a.build(&polys[..]).expect("build unexpected result");
+
+    let mut order = vec![];
+    a.traverse_back_to_front(&[0.5, 0.5, 10.], |o, _| order.push(*o));
+    assert_eq!(order, vec![0, 1, 2, 3, 4]);
+    order.clear();
+    a.traverse_front_to_back(&[0.5, 0.5, 10.], |o, _| order.push(*o));
+    assert_eq!(order, vec![4, 3, 2, 1, 0]);
+    order.clear();
+    a.traverse_back_to_front(&[0.5, 0.5, 2.5], |o, _| order.push(*o));
+    assert_eq!(order.len(), 5);
+    //from between 2 and 3 each square is drawn after the ones it hides
+    let pos = |x: i32| order.iter().position(|o| *o == x).unwrap();
+    assert!(pos(0) < pos(1) && pos(1) < pos(2));
+    assert!(pos(4) < pos(3));
+}
+
+#[test]
+fn test_bsp_splitting() {
+    //two squares crossing each other
+    let polys = [
+        (
+            0,
+            vec![[-1., -1., 0.], [1., -1., 0.], [1., 1., 0.], [-1., 1., 0.]],
+        ),
+        (
+            1,
+            vec![[0., -1., -1.], [0., 1., -1.], [0., 1., 1.], [0., -1., 1.]],
+        ),
+    ];
+    let mut a = Bsp::init(PlaneSelect::First, 1e-9);
+    a.build(&polys[..]).expect("build unexpected result");
+    assert_eq!(a.split_count(), 1);
+    assert_eq!(a.len(), 3);
+    let total = a.get_polygons().iter().map(|x| area(&x.1[..])).sum::<f64>();
+    assert!((total - 8.).abs() < 1e-9);
+
+    //fragments seen from a point keep the painter invariant against the splitting plane
+    let mut order = vec![];
+    a.traverse_back_to_front(&[0., 0., 5.], |o, v| order.push((*o, v.to_vec())));
+    assert_eq!(order.len(), 3);
+    assert_eq!(order[0].0, 1);
+    assert!(order[0].1.iter().all(|p| p[2] <= 1e-9));
+    assert_eq!(order[1].0, 0);
+    assert!(order[2].1.iter().all(|p| p[2] >= -1e-9));
+}
+
+#[test]
+fn test_bsp_random_triangles() {
+    let mut rng = StdRng::seed_from_u64(17);
+    let polys = (0..200u32)
+        .map(|i| {
+            let c = [
+                rng.gen_range(-10., 10.),
+                rng.gen_range(-10., 10.),
+                rng.gen_range(-10., 10.),
+            ];
+            let mut v = vec![];
+            for _ in 0..3 {
+                v.push([
+                    c[0] + rng.gen_range(-2., 2.),
+                    c[1] + rng.gen_range(-2., 2.),
+                    c[2] + rng.gen_range(-2., 2.),
+                ]);
+            }
+            (i, v)
+        })
+        .collect::<Vec<_>>();
+    let input_area = polys.iter().map(|x| area(&x.1[..])).collect::<Vec<_>>();
+
+    let mut few_splits = 0;
+    for select in [
+        PlaneSelect::First,
+        PlaneSelect::Heuristic {
+            split_weight: 8.,
+            candidates: 10,
+        },
+    ] {
+        let mut a = Bsp::init(select, 1e-9);
+        a.build(&polys[..]).expect("build unexpected result");
+        assert_eq!(a.len(), 200 + a.split_count());
+
+        //splitting preserves the area of each source polygon
+        let mut out_area = vec![0.; 200];
+        for p in a.get_polygons() {
+            out_area[p.0 as usize] += area(&p.1[..]);
+        }
+        for (i, j) in input_area.iter().zip(out_area.iter()) {
+            assert!((i - j).abs() < 1e-6);
+        }
+
+        let eye = [0., 0., 30.];
+        let mut b2f = vec![];
+        a.traverse_back_to_front(&eye, |o, v| b2f.push((*o, v.len())));
+        let mut f2b = vec![];
+        a.traverse_front_to_back(&eye, |o, v| f2b.push((*o, v.len())));
+        assert_eq!(b2f.len(), a.len());
+        f2b.reverse();
+        assert_eq!(b2f, f2b);
+        few_splits = if few_splits == 0 {
+            a.split_count()
+        } else {
+            assert!(a.split_count() < few_splits);
+            few_splits
+        };
+    }
+}
@@ -14,6 +14,7 @@ use implement::bvh::Bvh;
 use interface::i_spatial_accel::ISpatialAccel;
 #[cfg(test)]
 use std::f64;
+use tests::mask;
 
 #[test]
 fn test_bvh_supported_bounds() {
@@ -205,3 +206,8 @@ fn test_bvh_stress() {
     );
     info!("avg query time: {}", query_time as f32 / v.len() as f32);
 }
+
+#[test]
+fn test_bvh_mask_query() {
+    mask::check_mask_query(&mut Bvh::init(30));
+}
@@ -0,0 +1,203 @@
+extern crate mazth;
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::bvh_aggregate::Bvh;
+use interface::i_monoid::IMonoid;
+use interface::i_spatial_accel::ISpatialAccel;
+#[cfg(test)]
+use std::f64;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Stat {
+    count: u64,
+    mass: f64,
+    priority_max: i64,
+}
+
+impl IMonoid for Stat {
+    fn identity() -> Stat {
+        Stat {
+            count: 0,
+            mass: 0.,
+            priority_max: i64::MIN,
+        }
+    }
+    fn combine(&self, other: &Stat) -> Stat {
+        Stat {
+            count: self.count + other.count,
+            mass: self.mass + other.mass,
+            priority_max: self.priority_max.max(other.priority_max),
+        }
+    }
+}
+
+fn stat(mass: f64, priority: i64) -> Stat {
+    Stat {
+        count: 1,
+        mass,
+        priority_max: priority,
+    }
+}
+
+fn build_line() -> (Bvh<u64, Stat>, Vec<AxisAlignedBBox>) {
+    let mut a = Bvh::init();
+    let bounds = (0..20)
+        .map(|i| {
+            AxisAlignedBBox::init(
+                ShapeType::Sphere,
+                &[f64::from(i), f64::from(i), f64::from(i), 0.25f64],
+            )
+        })
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound, stat(i as f64, i as i64)))
+        .collect::<Vec<_>>();
+    match a.build_all_aggregate(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    (a, bounds)
+}
+
+#[test]
+fn test_bvh_aggregate_unsupported_bounds() {
+    let mut a: Bvh<u64, Stat> = Bvh::init();
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound, stat(1., 1))];
+    match a.build_all_aggregate(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+}
+
+#[test]
+fn test_bvh_aggregate_query() {
+    let (a, _) = build_line();
+
+    assert_eq!(a.get_aggregate().count, 20);
+    assert_eq!(a.get_aggregate().mass, 190.);
+    assert_eq!(a.get_aggregate().priority_max, 19);
+
+    //objects 2..=5 intersect
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[2., 2., 2., 5., 5., 5.]);
+    match a.query_aggregate(&query) {
+        Ok(o) => {
+            assert_eq!(o.count, 4);
+            assert_eq!(o.mass, 14.);
+            assert_eq!(o.priority_max, 5);
+        }
+        _ => panic!("query unexpected result"),
+    }
+
+    //whole tree inside region
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[-10., -10., -10., 30., 30., 30.]);
+    match a.query_aggregate(&query) {
+        Ok(o) => assert_eq!(o, *a.get_aggregate()),
+        _ => panic!("query unexpected result"),
+    }
+
+    //nothing in region
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[50., 50., 50.]);
+    match a.query_aggregate(&query) {
+        Ok(o) => assert_eq!(o, Stat::identity()),
+        _ => panic!("query unexpected result"),
+    }
+
+    //aggregate agrees with object query
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[7.5, 7.5, 7.5, 11.1, 11.1, 11.1]);
+    match (a.query_aggregate(&query), a.query_intersect(&query)) {
+        (Ok(o), Ok(objs)) => {
+            assert_eq!(o.count, objs.len() as u64);
+            assert_eq!(o.mass, objs.iter().sum::<u64>() as f64);
+        }
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_bvh_aggregate_update() {
+    let (mut a, _) = build_line();
+
+    //move object 0 far away and raise its priority
+    let moved = AxisAlignedBBox::init(ShapeType::Sphere, &[100., 100., 100., 0.25]);
+    match a.update(&0, &moved, stat(1000., 99)) {
+        Ok(()) => (),
+        _ => panic!("update unexpected result"),
+    }
+    assert_eq!(a.get_aggregate().mass, 1190.);
+    assert_eq!(a.get_aggregate().priority_max, 99);
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[100., 100., 100.]);
+    match a.query_aggregate(&query) {
+        Ok(o) => assert_eq!(o, stat(1000., 99)),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+
+    match a.update(&20, &moved, stat(1., 1)) {
+        Err(_) => (),
+        _ => panic!("update of missing object unexpected result"),
+    }
+
+    let b1 = AxisAlignedBBox::init(ShapeType::Sphere, &[0., 0., 0., 0.25]);
+    let b2 = AxisAlignedBBox::init(ShapeType::Sphere, &[1., 1., 1., 0.25]);
+    let objs = [
+        (0u64, &b1 as &dyn IBound, stat(0., 0)),
+        (1u64, &b2 as &dyn IBound, stat(3., 1)),
+    ];
+    match a.update_batch(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("update unexpected result"),
+    }
+    assert_eq!(a.get_aggregate().mass, 192.);
+    assert_eq!(a.get_aggregate().priority_max, 19);
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[-1., -1., -1., 1., 1., 1.]);
+    match a.query_aggregate(&query) {
+        Ok(o) => {
+            assert_eq!(o.count, 2);
+            assert_eq!(o.mass, 3.);
+        }
+        _ => panic!("query unexpected result"),
+    }
+
+    //an unknown object in the middle of the batch leaves every entry unchanged
+    let b3 = AxisAlignedBBox::init(ShapeType::Point, &[50., 50., 50.]);
+    let objs = [
+        (0u64, &b3 as &dyn IBound, stat(7., 5)),
+        (1000u64, &b3 as &dyn IBound, stat(7., 5)),
+        (1u64, &b3 as &dyn IBound, stat(7., 5)),
+    ];
+    assert!(a.update_batch(&objs[..]).is_err());
+    assert_eq!(a.get_aggregate().mass, 192.);
+    assert_eq!(a.get_aggregate().priority_max, 19);
+    match a.query_aggregate(&query) {
+        Ok(o) => assert_eq!(o.count, 2),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_bvh_aggregate_empty() {
+    let a: Bvh<u64, Stat> = Bvh::init();
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_aggregate(&query) {
+        Ok(o) => assert_eq!(o, Stat::identity()),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+}
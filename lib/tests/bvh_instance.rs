@@ -0,0 +1,289 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use std::rc::Rc;
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+    mat::Mat4, ray::Ray3,
+};
+use implement::bvh::Bvh;
+use implement::bvh_instance::BvhInstance;
+use interface::i_spatial_accel::ISpatialAccel;
+
+fn translate(x: f64, y: f64, z: f64) -> Mat4<f64> {
+    Mat4::<f64>::init(
+        [1., 0., 0., x, 0., 1., 0., y, 0., 0., 1., z, 0., 0., 0., 1.],
+        true,
+    )
+}
+
+///rotation about z by angle a, uniform scale s and translation
+fn rotate_z(a: f64, s: f64, t: [f64; 3]) -> Mat4<f64> {
+    let (sin, cos) = a.sin_cos();
+    Mat4::<f64>::init(
+        [
+            s * cos,
+            -s * sin,
+            0.,
+            t[0],
+            s * sin,
+            s * cos,
+            0.,
+            t[1],
+            0.,
+            0.,
+            s,
+            t[2],
+            0.,
+            0.,
+            0.,
+            1.,
+        ],
+        true,
+    )
+}
+
+fn rotate_z_inverse(a: f64, s: f64, t: [f64; 3]) -> Mat4<f64> {
+    let (sin, cos) = (-a).sin_cos();
+    let t = [
+        -(cos * t[0] - sin * t[1]) / s,
+        -(sin * t[0] + cos * t[1]) / s,
+        -t[2] / s,
+    ];
+    rotate_z(-a, 1. / s, t)
+}
+
+///bound from the 8 transformed corners
+fn corners_bound(m: &Mat4<f64>, b: &AxisAlignedBBox) -> AxisAlignedBBox {
+    let mut lo = [f64::INFINITY; 3];
+    let mut hi = [f64::NEG_INFINITY; 3];
+    for c in 0..8 {
+        let p = [
+            if c & 1 == 0 {
+                b._bound_lower[0]
+            } else {
+                b._bound_upper[0]
+            },
+            if c & 2 == 0 {
+                b._bound_lower[1]
+            } else {
+                b._bound_upper[1]
+            },
+            if c & 4 == 0 {
+                b._bound_lower[2]
+            } else {
+                b._bound_upper[2]
+            },
+        ];
+        for i in 0..3 {
+            let v = m.index(i as u64, 0) * p[0]
+                + m.index(i as u64, 1) * p[1]
+                + m.index(i as u64, 2) * p[2]
+                + m.index(i as u64, 3);
+            lo[i] = lo[i].min(v);
+            hi[i] = hi[i].max(v);
+        }
+    }
+    AxisAlignedBBox {
+        _bound_lower: lo,
+        _bound_upper: hi,
+    }
+}
+
+fn mesh(boxes: &[AxisAlignedBBox]) -> Rc<Bvh<u32>> {
+    let mut a = Bvh::init(10);
+    let objs = boxes
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u32, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    a.build_all(&objs[..]).expect("bottom level build");
+    Rc::new(a)
+}
+
+fn unit_boxes() -> Vec<AxisAlignedBBox> {
+    vec![
+        AxisAlignedBBox::init(ShapeType::Box, &[0., 0., 0., 1.]),
+        AxisAlignedBBox::init(ShapeType::Box, &[3., 0., 0., 1.]),
+        AxisAlignedBBox::init(ShapeType::Box, &[0., 3., 0., 1.]),
+    ]
+}
+
+#[test]
+fn test_bvh_instance_translated() {
+    let blas = mesh(&unit_boxes()[..]);
+    let mut a = BvhInstance::init(10);
+    let instances = [
+        (blas.clone(), translate(0., 0., 0.)),
+        (blas.clone(), translate(100., 0., 0.)),
+        (blas.clone(), translate(0., 0., 50.)),
+    ];
+    a.build(&instances[..]).expect("build");
+    assert_eq!(a.len(), 3);
+    let b = a.get_instance_bound(1).unwrap();
+    assert_eq!(b._bound_lower, [99., -1., -1.]);
+    assert_eq!(b._bound_upper, [104., 4., 1.]);
+
+    let q = AxisAlignedBBox::init(ShapeType::Box, &[103., 0., 0., 0.5]);
+    match a.query_intersect(&q) {
+        Ok(o) => assert_eq!(o, vec![(1, 1)]),
+        _ => panic!("query unexpected result"),
+    }
+    let q = AxisAlignedBBox::init(ShapeType::Rect, &[-10., -10., -1., 200., 10., 1.]);
+    match a.query_intersect(&q) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_single(&q) {
+        Ok(o) => assert_eq!(o.len(), 1),
+        _ => panic!("query unexpected result"),
+    }
+
+    //ray along x through the first two instances, ordered by distance
+    let r = Ray3::init(&[-5., 0., 0.], &[1., 0., 0.]);
+    match a.query_ray(&r) {
+        Ok(o) => assert_eq!(o, vec![(0, 0), (0, 1), (1, 0), (1, 1)]),
+        _ => panic!("query unexpected result"),
+    }
+    let r = Ray3::init(&[0., 0., 60.], &[0., 0., -1.]);
+    match a.query_ray(&r) {
+        Ok(o) => assert_eq!(o, vec![(2, 0), (0, 0)]),
+        _ => panic!("query unexpected result"),
+    }
+
+    let s = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    match a.query_intersect(&s) {
+        Err(_) => (),
+        _ => panic!("unexpected result for unsupported bound type"),
+    }
+}
+
+#[test]
+fn test_bvh_instance_rotated() {
+    let blas = mesh(&unit_boxes()[..]);
+    let mut a = BvhInstance::init(10);
+    //quarter turn maps +x to +y, scaled by 2
+    let m = rotate_z(::std::f64::consts::FRAC_PI_2, 2., [10., 0., 0.]);
+    a.build(&[(blas, m)]).expect("build");
+    let b = a.get_instance_bound(0).unwrap();
+    for i in 0..3 {
+        assert!((b._bound_lower[i] - [2., -2., -2.][i]).abs() < 1e-9);
+        assert!((b._bound_upper[i] - [12., 8., 2.][i]).abs() < 1e-9);
+    }
+
+    //object box 1 at x = 3 lands at y = 6
+    let q = AxisAlignedBBox::init(ShapeType::Box, &[10., 6., 0., 0.5]);
+    match a.query_intersect(&q) {
+        Ok(o) => assert_eq!(o, vec![(0, 1)]),
+        _ => panic!("query unexpected result"),
+    }
+    let r = Ray3::init(&[10., 20., 0.], &[0., -1., 0.]);
+    match a.query_ray(&r) {
+        Ok(o) => assert_eq!(o, vec![(0, 1), (0, 0)]),
+        _ => panic!("query unexpected result"),
+    }
+    let r = Ray3::init(&[0., 6., 0.], &[1., 0., 0.]);
+    match a.query_ray(&r) {
+        Ok(o) => assert_eq!(o, vec![(0, 1)]),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_bvh_instance_invalid() {
+    let blas = mesh(&unit_boxes()[..]);
+    let mut a = BvhInstance::init(10);
+    let mut m = translate(1., 2., 3.);
+    *m.index_mut(3, 0) = 1.;
+    assert!(a.build(&[(blas.clone(), m)]).is_err());
+    let m = rotate_z(0., 0., [0., 0., 0.]);
+    assert!(a.build(&[(blas.clone(), m)]).is_err());
+    let empty: Rc<Bvh<u32>> = Rc::new(Bvh::init(10));
+    assert!(a.build(&[(empty, translate(0., 0., 0.))]).is_err());
+
+    a.build(&[]).expect("build");
+    assert!(a.is_empty());
+    let q = AxisAlignedBBox::init(ShapeType::Box, &[0., 0., 0., 100.]);
+    match a.query_intersect(&q) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_bvh_instance_random_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17);
+    let meshes = (0..3)
+        .map(|_| {
+            (0..20)
+                .map(|_| {
+                    AxisAlignedBBox::init(
+                        ShapeType::Box,
+                        &[
+                            rng.gen_range(-5., 5.),
+                            rng.gen_range(-5., 5.),
+                            rng.gen_range(-5., 5.),
+                            rng.gen_range(0.1, 1.),
+                        ],
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let blas = meshes.iter().map(|x| mesh(&x[..])).collect::<Vec<_>>();
+    let mut instances = vec![];
+    let mut placed = vec![];
+    for _ in 0..50 {
+        let k = rng.gen_range(0, meshes.len());
+        let a = rng.gen_range(0., 6.3);
+        let s = rng.gen_range(0.5, 2.);
+        let t = [
+            rng.gen_range(-50., 50.),
+            rng.gen_range(-50., 50.),
+            rng.gen_range(-50., 50.),
+        ];
+        let m = rotate_z(a, s, t);
+        instances.push((blas[k].clone(), m));
+        placed.push((k, rotate_z_inverse(a, s, t)));
+    }
+    let mut a = BvhInstance::init(10);
+    a.build(&instances[..]).expect("build");
+
+    for _ in 0..100 {
+        let q = AxisAlignedBBox::init(
+            ShapeType::Box,
+            &[
+                rng.gen_range(-50., 50.),
+                rng.gen_range(-50., 50.),
+                rng.gen_range(-50., 50.),
+                rng.gen_range(1., 15.),
+            ],
+        );
+        let mut expect = vec![];
+        for (id, &(k, ref m)) in placed.iter().enumerate() {
+            if !a.get_instance_bound(id).unwrap().intersect(&q) {
+                continue;
+            }
+            let local = corners_bound(m, &q);
+            for (j, b) in meshes[k].iter().enumerate() {
+                if b.intersect(&local) {
+                    expect.push((id, j as u32));
+                }
+            }
+        }
+        match a.query_intersect(&q) {
+            Ok(mut o) => {
+                o.sort();
+                assert_eq!(o, expect);
+            }
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
@@ -13,6 +13,7 @@ use implement::bvh_median::Bvh;
 use interface::i_spatial_accel::ISpatialAccel;
 #[cfg(test)]
 use std::f64;
+use tests::mask;
 
 #[test]
 fn test_bvh_median_supported_bounds() {
@@ -254,3 +255,8 @@ fn test_bvh_median_tri_prism_point_intersection() {
         }
     }
 }
+
+#[test]
+fn test_bvh_median_mask_query() {
+    mask::check_mask_query(&mut Bvh::init(30));
+}
@@ -0,0 +1,149 @@
+extern crate mazth;
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+    ray::Ray3,
+};
+use implement::bvh_motion::{BuildMethod, Bvh};
+use interface::i_spatial_accel::ISpatialAccel;
+#[cfg(test)]
+use std::f64;
+
+///objects moving from (i,0,0) at shutter open to (i,10,0) at shutter close
+fn build_moving(method: BuildMethod) -> Bvh<u64> {
+    let mut a = Bvh::init(method);
+    let mut bounds = vec![];
+    for i in 0..20 {
+        let b0 = AxisAlignedBBox::init(ShapeType::Sphere, &[f64::from(i) * 3., 0., 0., 1.]);
+        let b1 = AxisAlignedBBox::init(ShapeType::Sphere, &[f64::from(i) * 3., 10., 0., 1.]);
+        bounds.push((b0, b1));
+    }
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i as u64, &x.0 as &dyn IBound, &x.1 as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all_motion(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    a
+}
+
+#[test]
+fn test_bvh_motion_unsupported_bounds() {
+    let mut a = Bvh::init(BuildMethod::Median);
+    let b0 = AxisAlignedBBox::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let b1 = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b0 as &dyn IBound, &b1 as &dyn IBound)];
+    match a.build_all_motion(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+}
+
+#[test]
+fn test_bvh_motion_query_time() {
+    for method in [BuildMethod::Median, BuildMethod::SurfaceArea(10)] {
+        let a = build_moving(method);
+
+        let query = AxisAlignedBBox::init(ShapeType::Point, &[9., 5., 0.]);
+        match a.query_intersect_time(&query, 0.5) {
+            Ok(o) => assert_eq!(o, vec![3]),
+            _ => panic!("query unexpected result"),
+        }
+        match a.query_intersect_time(&query, 0.) {
+            Ok(o) => assert!(o.is_empty()),
+            _ => panic!("query unexpected result"),
+        }
+        match a.query_intersect_time(&query, 1.) {
+            Ok(o) => assert!(o.is_empty()),
+            _ => panic!("query unexpected result"),
+        }
+        match a.query_intersect_single_time(&query, 0.45) {
+            Ok(o) => assert_eq!(o, vec![3]),
+            _ => panic!("query unexpected result"),
+        }
+        match a.query_intersect_time(&query, 1.5) {
+            Err(_) => (),
+            _ => panic!("query time out of range unexpected result"),
+        }
+
+        //swept bounds cover the whole shutter interval
+        match a.query_intersect(&query) {
+            Ok(o) => assert_eq!(o, vec![3]),
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
+
+#[test]
+fn test_bvh_motion_query_ray() {
+    for method in [BuildMethod::Median, BuildMethod::SurfaceArea(10)] {
+        let a = build_moving(method);
+
+        //ray along x axis at height y=10 only hits objects at shutter close
+        let r = Ray3::init(&[-5., 10., 0.], &[1., 0., 0.]);
+        match a.query_ray_time(&r, 1.) {
+            Ok(mut o) => {
+                o.sort();
+                assert_eq!(o, (0..20).collect::<Vec<u64>>());
+            }
+            _ => panic!("query unexpected result"),
+        }
+        match a.query_ray_time(&r, 0.) {
+            Ok(o) => assert!(o.is_empty()),
+            _ => panic!("query unexpected result"),
+        }
+
+        //ray along y axis through object 2
+        let r = Ray3::init(&[6., -5., 0.], &[0., 1., 0.]);
+        match a.query_ray_time(&r, 0.3) {
+            Ok(o) => assert_eq!(o, vec![2]),
+            _ => panic!("query unexpected result"),
+        }
+        //ray pointing away from objects
+        let r = Ray3::init(&[6., -5., 0.], &[0., -1., 0.]);
+        match a.query_ray_time(&r, 0.3) {
+            Ok(o) => assert!(o.is_empty()),
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
+
+#[test]
+fn test_bvh_motion_stationary() {
+    let mut a = Bvh::init(BuildMethod::SurfaceArea(10));
+    let mut bounds = vec![];
+    for i in 0..20 {
+        bounds.push(AxisAlignedBBox::init(
+            ShapeType::Sphere,
+            &[f64::from(i), f64::from(i), f64::from(i), 5f64],
+        ));
+    }
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    for t in [0., 0.5, 1.].iter() {
+        match a.query_intersect_time(&query, *t) {
+            Ok(o) => {
+                assert_eq!(o.len(), 6);
+                assert!(o.iter().all(|x| *x <= 5));
+            }
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
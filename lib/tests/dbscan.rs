@@ -0,0 +1,159 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{bound::AxisAlignedBBox, i_bound::IBound, i_shape::ShapeType};
+use implement::dbscan::{Dbscan, PointLabel, NOISE};
+use interface::i_cluster::{ClusterMethod, ClusterMetric, ClusterOrder, ClusterSize, ICluster};
+
+fn point(x: f64, y: f64, z: f64) -> AxisAlignedBBox {
+    AxisAlignedBBox::init(ShapeType::Point, &[x, y, z])
+}
+
+fn build(a: &mut Dbscan, metric: ClusterMetric, bounds: &[AxisAlignedBBox]) {
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    a.build_all(
+        ClusterSize::Auto,
+        ClusterMethod::Density,
+        metric,
+        ClusterOrder::InOrder,
+        &objs[..],
+    )
+    .expect("build");
+}
+
+#[test]
+fn test_dbscan_labels() {
+    let bounds = [
+        //line of points 1 apart, the ends have fewer neighbours
+        point(0., 0., 0.),
+        point(1., 0., 0.),
+        point(2., 0., 0.),
+        point(3., 0., 0.),
+        point(4., 0., 0.),
+        //separate pair is too sparse to form a cluster
+        point(20., 0., 0.),
+        point(21., 0., 0.),
+        point(50., 0., 0.),
+        //second line
+        point(0., 30., 0.),
+        point(0., 31., 0.),
+        point(0., 32., 0.),
+    ];
+    let mut a = Dbscan::init(1., 3, 10);
+    build(&mut a, ClusterMetric::L2, &bounds[..]);
+    assert_eq!(a.cluster_count(), 2);
+    let clusters = (0..bounds.len() as u64)
+        .map(|i| a.query_cluster(i).expect("query"))
+        .collect::<Vec<_>>();
+    assert_eq!(clusters, vec![0, 0, 0, 0, 0, NOISE, NOISE, NOISE, 1, 1, 1]);
+    assert_eq!(a.get_label(0), Ok(PointLabel::Border));
+    assert_eq!(a.get_label(2), Ok(PointLabel::Core));
+    assert_eq!(a.get_label(4), Ok(PointLabel::Border));
+    assert_eq!(a.get_label(6), Ok(PointLabel::Noise));
+    assert_eq!(a.get_label(9), Ok(PointLabel::Core));
+    assert!(a.get_label(11).is_err());
+    assert!(a.query_cluster(11).is_err());
+}
+
+#[test]
+fn test_dbscan_metric() {
+    //diagonal neighbours are within eps under L2 but not under L1
+    let bounds = [point(0., 0., 0.), point(1., 1., 0.), point(2., 2., 0.)];
+    let mut a = Dbscan::init(1.5, 2, 10);
+    build(&mut a, ClusterMetric::L2, &bounds[..]);
+    assert_eq!(a.cluster_count(), 1);
+    build(&mut a, ClusterMetric::L1, &bounds[..]);
+    assert_eq!(a.cluster_count(), 0);
+    assert_eq!(a.query_cluster(1), Ok(NOISE));
+
+    build(&mut a, ClusterMetric::L2, &[]);
+    assert_eq!(a.cluster_count(), 0);
+
+    let objs = [(0u64, &bounds[0] as &dyn IBound)];
+    for (size, method, order) in [
+        (
+            ClusterSize::Manual(2),
+            ClusterMethod::Density,
+            ClusterOrder::InOrder,
+        ),
+        (
+            ClusterSize::Auto,
+            ClusterMethod::Average,
+            ClusterOrder::InOrder,
+        ),
+        (
+            ClusterSize::Auto,
+            ClusterMethod::Density,
+            ClusterOrder::PreSample,
+        ),
+    ] {
+        assert!(a
+            .build_all(size, method, ClusterMetric::L2, order, &objs[..])
+            .is_err());
+    }
+}
+
+#[test]
+fn test_dbscan_brute_force() {
+    let mut rng = StdRng::seed_from_u64(13);
+    let mut bounds = vec![];
+    for c in [[0., 0., 0.], [10., 10., 0.], [-10., 5., 5.]] {
+        for _ in 0..150 {
+            bounds.push(point(
+                c[0] + rng.gen_range(-2., 2.),
+                c[1] + rng.gen_range(-2., 2.),
+                c[2] + rng.gen_range(-2., 2.),
+            ));
+        }
+    }
+    for _ in 0..100 {
+        bounds.push(point(
+            rng.gen_range(-20., 20.),
+            rng.gen_range(-20., 20.),
+            rng.gen_range(-20., 20.),
+        ));
+    }
+    let eps = 1.;
+    let min_pts = 5;
+    let mut a = Dbscan::init(eps, min_pts, 10);
+    build(&mut a, ClusterMetric::L2, &bounds[..]);
+
+    let p = bounds.iter().map(|b| b._bound_lower).collect::<Vec<_>>();
+    let d = |i: usize, j: usize| {
+        (0..3)
+            .map(|k| (p[i][k] - p[j][k]).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    };
+    let n = p.len();
+    let core = (0..n)
+        .map(|i| (0..n).filter(|j| d(i, *j) <= eps).count() >= min_pts)
+        .collect::<Vec<_>>();
+    for i in 0..n {
+        let c = a.query_cluster(i as u64).expect("query");
+        let label = a.get_label(i as u64).expect("label");
+        if core[i] {
+            assert_eq!(label, PointLabel::Core);
+            //cores within eps share a cluster
+            for (j, cj) in core.iter().enumerate() {
+                if *cj && d(i, j) <= eps {
+                    assert_eq!(c, a.query_cluster(j as u64).expect("query"));
+                }
+            }
+        } else if (0..n).any(|j| core[j] && d(i, j) <= eps) {
+            assert_eq!(label, PointLabel::Border);
+            assert!((0..n).any(|j| core[j] && d(i, j) <= eps && a.query_cluster(j as u64) == Ok(c)));
+        } else {
+            assert_eq!(label, PointLabel::Noise);
+            assert_eq!(c, NOISE);
+        }
+    }
+    assert!(a.cluster_count() >= 3);
+}
@@ -0,0 +1,308 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{bound::AxisAlignedBBox, i_bound::IBound, i_shape::ShapeType};
+use implement::cluster_select::Criterion;
+use implement::gmm::{Covariance, Gmm};
+use interface::i_cluster::{ClusterMethod, ClusterMetric, ClusterOrder, ClusterSize, ICluster};
+
+fn point(x: f64, y: f64, z: f64) -> AxisAlignedBBox {
+    AxisAlignedBBox::init(ShapeType::Point, &[x, y, z])
+}
+
+fn build(a: &mut Gmm, k: usize, bounds: &[AxisAlignedBBox]) -> Result<(), &'static str> {
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    a.build_all(
+        ClusterSize::Manual(k),
+        ClusterMethod::Gaussian,
+        ClusterMetric::L2,
+        ClusterOrder::InOrder,
+        &objs[..],
+    )
+}
+
+#[test]
+fn test_gmm_separated_blobs() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let centres = [[0., 0., 0.], [30., 0., 0.], [0., 30., 30.]];
+    let spread = [1., 2., 4.];
+    //listed round robin over the centres so the first three objects seed one blob each
+    let bounds = (0..600)
+        .map(|i| {
+            let c = centres[i % 3];
+            let s = spread[i % 3];
+            point(
+                c[0] + rng.gen_range(-s, s),
+                c[1] + rng.gen_range(-s, s),
+                c[2] + rng.gen_range(-s, s),
+            )
+        })
+        .collect::<Vec<_>>();
+    for cov in [
+        Covariance::Full,
+        Covariance::Diagonal,
+        Covariance::Spherical,
+    ] {
+        let mut a = Gmm::init(200, 1e-6, cov);
+        build(&mut a, 3, &bounds[..]).expect("build");
+        assert!(a.is_converged());
+        assert!(a.get_iterations() < 200);
+        for i in 0..bounds.len() {
+            match a.query_cluster(i as u64) {
+                Ok(c) => assert_eq!(c, (i % 3) as u64),
+                _ => panic!("query unexpected result"),
+            }
+            let m = a.query_membership(i as u64).expect("membership");
+            assert_eq!(m.len(), 3);
+            assert!((m.iter().sum::<f64>() - 1.).abs() < 1e-9);
+            assert!(m[i % 3] > 0.99);
+        }
+        for (k, (m, e)) in a.get_means().iter().zip(centres.iter()).enumerate() {
+            for axis in 0..3 {
+                assert!((m[axis] - e[axis]).abs() < 0.2 * spread[k]);
+            }
+            //variance of uniform on [-s,s] is s^2/3
+            let v = spread[k] * spread[k] / 3.;
+            let c = a.get_covariances()[k];
+            for (axis, row) in c.iter().enumerate() {
+                assert!((row[axis] - v).abs() < 0.25 * v);
+            }
+            assert!((a.get_weights()[k] - 1. / 3.).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_gmm_covariance_shape() {
+    let mut rng = StdRng::seed_from_u64(8);
+    //correlated in x and y, thin along z
+    let bounds = (0..400)
+        .map(|_| {
+            let t = rng.gen_range(-5., 5.);
+            point(t, t + rng.gen_range(-0.5, 0.5), rng.gen_range(-0.1, 0.1))
+        })
+        .collect::<Vec<_>>();
+    let mut ll = vec![];
+    for cov in [
+        Covariance::Full,
+        Covariance::Diagonal,
+        Covariance::Spherical,
+    ] {
+        let mut a = Gmm::init(50, 1e-9, cov);
+        build(&mut a, 1, &bounds[..]).expect("build");
+        let c = a.get_covariances()[0];
+        match cov {
+            Covariance::Full => {
+                assert!(c[0][1] > 7.);
+                assert!((c[0][1] - c[1][0]).abs() < 1e-12);
+            }
+            Covariance::Diagonal => {
+                assert_eq!(c[0][1], 0.);
+                assert_eq!(c[1][2], 0.);
+                assert!(c[2][2] < 0.01);
+            }
+            Covariance::Spherical => {
+                assert_eq!(c[0][0], c[1][1]);
+                assert_eq!(c[1][1], c[2][2]);
+                assert_eq!(c[0][2], 0.);
+            }
+        }
+        assert_eq!(a.get_weights(), &[1.]);
+        ll.push(a.get_log_likelihood());
+    }
+    //less constrained covariances fit at least as well
+    assert!(ll[0] >= ll[1]);
+    assert!(ll[1] >= ll[2]);
+}
+
+#[test]
+fn test_gmm_likelihood_monotonic() {
+    let mut rng = StdRng::seed_from_u64(21);
+    let bounds = (0..300)
+        .map(|_| {
+            point(
+                rng.gen_range(-10., 10.),
+                rng.gen_range(-10., 10.),
+                rng.gen_range(-10., 10.),
+            )
+        })
+        .collect::<Vec<_>>();
+    //each EM step does not decrease the log-likelihood
+    let mut prev = f64::NEG_INFINITY;
+    for iter in 1..30 {
+        let mut a = Gmm::init(iter, 0., Covariance::Full);
+        build(&mut a, 4, &bounds[..]).expect("build");
+        assert_eq!(a.get_iterations(), iter);
+        assert!(!a.is_converged());
+        let ll = a.get_log_likelihood();
+        assert!(ll.is_finite());
+        assert!(ll >= prev - 1e-7);
+        prev = ll;
+    }
+}
+
+#[test]
+fn test_gmm_invalid() {
+    let bounds = [point(0., 0., 0.), point(0., 0., 0.), point(1., 0., 0.)];
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = Gmm::init(10, 1e-6, Covariance::Full);
+    assert!(build(&mut a, 0, &bounds[..]).is_err());
+    assert!(a.get_bic().is_infinite());
+    assert!(build(&mut a, 4, &bounds[..]).is_err());
+    //only two distinct centroids
+    assert!(build(&mut a, 3, &bounds[..]).is_err());
+    for (size, method, metric, order) in [
+        (
+            ClusterSize::Manual(1),
+            ClusterMethod::Average,
+            ClusterMetric::L2,
+            ClusterOrder::InOrder,
+        ),
+        (
+            ClusterSize::Manual(1),
+            ClusterMethod::Gaussian,
+            ClusterMetric::L1,
+            ClusterOrder::InOrder,
+        ),
+    ] {
+        assert!(a.build_all(size, method, metric, order, &objs[..]).is_err());
+    }
+    //coincident objects are kept finite by the covariance regularization
+    build(&mut a, 2, &bounds[..]).expect("build");
+    assert!(a.get_log_likelihood().is_finite());
+    assert_eq!(a.query_cluster(0), a.query_cluster(1));
+    assert!(a.query_cluster(3).is_err());
+    assert!(a.query_membership(3).is_err());
+
+    //covariance overflows, the failed build keeps the previous fit
+    let huge = [
+        point(-1e200, 0., 0.),
+        point(1e200, 0., 0.),
+        point(0., 1e200, 0.),
+        point(0., 0., 1e200),
+    ];
+    let ll = a.get_log_likelihood();
+    assert!(build(&mut a, 1, &huge[..]).is_err());
+    assert_eq!(a.get_log_likelihood(), ll);
+    assert_eq!(a.query_membership(0).expect("membership").len(), 2);
+    assert!(a.query_cluster(3).is_err());
+}
+
+#[test]
+fn test_gmm_auto() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let centres = [[0., 0., 0.], [15., 0., 0.], [0., 15., 15.]];
+    let bounds = (0..450)
+        .map(|i| {
+            let c = centres[i % 3];
+            point(
+                c[0] + rng.gen_range(-1., 1.),
+                c[1] + rng.gen_range(-1., 1.),
+                c[2] + rng.gen_range(-1., 1.),
+            )
+        })
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    for criterion in [Criterion::Bic, Criterion::Silhouette] {
+        let mut a = Gmm::init(100, 1e-6, Covariance::Diagonal);
+        a.set_auto(1, 6, criterion).expect("set auto");
+        a.build_all(
+            ClusterSize::Auto,
+            ClusterMethod::Gaussian,
+            ClusterMetric::L2,
+            ClusterOrder::InOrder,
+            &objs[..],
+        )
+        .expect("build");
+        assert_eq!(a.get_means().len(), 3);
+        let scores = a.get_scores();
+        match criterion {
+            Criterion::Bic => {
+                assert_eq!(scores.len(), 6);
+                assert_eq!(scores[2].1, a.get_bic());
+                assert!(scores.iter().all(|x| x.1 >= a.get_bic()));
+            }
+            _ => assert_eq!(scores[0].0, 2),
+        }
+        for i in 0..bounds.len() {
+            assert_eq!(a.query_cluster(i as u64), Ok((i % 3) as u64));
+        }
+    }
+    let mut a = Gmm::init(100, 1e-6, Covariance::Full);
+    let gap = Criterion::Gap {
+        references: 5,
+        seed: 1,
+    };
+    assert!(a.set_auto(1, 4, gap).is_err());
+    assert!(a.set_auto(3, 2, Criterion::Bic).is_err());
+}
+
+#[test]
+fn test_gmm_presample() {
+    let mut rng = StdRng::seed_from_u64(17);
+    let centres = [[0., 0., 0.], [20., 0., 0.], [0., 20., 0.], [0., 0., 20.]];
+    //listed one centre after another so InOrder seeding starts all means in one blob
+    let bounds = (0..400)
+        .map(|i| {
+            let c = centres[i / 100];
+            point(
+                c[0] + rng.gen_range(-1., 1.),
+                c[1] + rng.gen_range(-1., 1.),
+                c[2] + rng.gen_range(-1., 1.),
+            )
+        })
+        .collect::<Vec<_>>();
+    let fit = |seed| {
+        let mut a = Gmm::init(200, 1e-6, Covariance::Full);
+        a.set_seed(seed);
+        a.set_sample_size(50).expect("sample size");
+        let objs = bounds
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i as u64, b as &dyn IBound))
+            .collect::<Vec<_>>();
+        a.build_all(
+            ClusterSize::Manual(4),
+            ClusterMethod::Gaussian,
+            ClusterMetric::L2,
+            ClusterOrder::PreSample,
+            &objs[..],
+        )
+        .expect("build");
+        a
+    };
+    let a = fit(2);
+    let clusters = (0..400)
+        .map(|i| a.query_cluster(i as u64).expect("query"))
+        .collect::<Vec<_>>();
+    for (i, c) in clusters.iter().enumerate() {
+        assert_eq!(*c, clusters[(i / 100) * 100]);
+    }
+    let mut firsts = vec![clusters[0], clusters[100], clusters[200], clusters[300]];
+    firsts.sort();
+    firsts.dedup();
+    assert_eq!(firsts.len(), 4);
+
+    //same seed gives the same fit
+    let b = fit(2);
+    assert_eq!(a.get_means(), b.get_means());
+    assert_eq!(a.get_log_likelihood(), b.get_log_likelihood());
+    assert!(Gmm::init(10, 0., Covariance::Full)
+        .set_sample_size(0)
+        .is_err());
+}
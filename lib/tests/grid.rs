@@ -0,0 +1,413 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+    ray::Ray3,
+};
+use implement::grid::{CellHit, CellSize, Grid, GridHash};
+use interface::i_spatial_accel::ISpatialAccel;
+#[cfg(test)]
+use std::f64;
+
+fn check_construction_and_query(a: &mut dyn ISpatialAccel<u64>) {
+    let mut bounds = vec![];
+    let mut bound_refs = vec![];
+    for i in 0..20 {
+        let aabb = AxisAlignedBBox::init(
+            ShapeType::Sphere,
+            &[f64::from(i), f64::from(i), f64::from(i), 5f64],
+        );
+        bounds.push(aabb);
+    }
+    for (i, b) in bounds.iter().enumerate() {
+        bound_refs.push((i as u64, b as &dyn IBound));
+    }
+
+    match a.build_all(&bound_refs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 1, 2, 3, 4, 5]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[-5., -5., -5.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![0]),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[-5.1, -5.1, -5.1]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[26., 26., 26.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    //query region covering everything returns each object once
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[-100., -100., -100., 100., 100., 100.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, (0..20).collect::<Vec<u64>>());
+        }
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[19., 19., 19.]);
+    match a.query_intersect_single(&query) {
+        Ok(o) => {
+            assert_eq!(o.len(), 1);
+            assert!(o[0] >= 14);
+        }
+        _ => panic!("query unexpected result"),
+    }
+}
+
+fn check_random_against_brute_force(a: &mut dyn ISpatialAccel<u64>) {
+    let mut rng = StdRng::seed_from_u64(7);
+    let v = (0..2000u64)
+        .map(|x| {
+            let c = [
+                rng.gen_range(-50., 50.),
+                rng.gen_range(-50., 50.),
+                rng.gen_range(-50., 50.),
+            ];
+            let r = rng.gen_range(0.01, 3.);
+            (
+                x,
+                AxisAlignedBBox::init(ShapeType::Sphere, &[c[0], c[1], c[2], r]),
+            )
+        })
+        .collect::<Vec<_>>();
+    let objs = v
+        .iter()
+        .map(|x| (x.0, &x.1 as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    for _ in 0..100 {
+        let c = [
+            rng.gen_range(-60., 60.),
+            rng.gen_range(-60., 60.),
+            rng.gen_range(-60., 60.),
+        ];
+        let query =
+            AxisAlignedBBox::init(ShapeType::Box, &[c[0], c[1], c[2], rng.gen_range(0., 10.)]);
+        let mut expect = v
+            .iter()
+            .filter(|x| x.1.intersect(&query))
+            .map(|x| x.0)
+            .collect::<Vec<_>>();
+        expect.sort();
+        match a.query_intersect(&query) {
+            Ok(mut o) => {
+                o.sort();
+                assert_eq!(o, expect);
+            }
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
+
+#[test]
+fn test_grid_unsupported_bounds() {
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    let mut a = Grid::init(CellSize::Auto);
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    let mut a = GridHash::init(CellSize::Auto);
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+}
+
+#[test]
+fn test_grid_construction_and_query() {
+    check_construction_and_query(&mut Grid::init(CellSize::Manual(1.)));
+    check_construction_and_query(&mut Grid::init(CellSize::Auto));
+    check_construction_and_query(&mut GridHash::init(CellSize::Manual(1.)));
+    check_construction_and_query(&mut GridHash::init(CellSize::Auto));
+}
+
+#[test]
+fn test_grid_random_query() {
+    check_random_against_brute_force(&mut Grid::init(CellSize::Manual(2.5)));
+    check_random_against_brute_force(&mut Grid::init(CellSize::Auto));
+    check_random_against_brute_force(&mut GridHash::init(CellSize::Manual(2.5)));
+    check_random_against_brute_force(&mut GridHash::init(CellSize::Auto));
+}
+
+#[test]
+fn test_grid_auto_cell_size() {
+    //1000 unit boxes spread over a 100^3 domain gives roughly one object per cell of edge 10
+    let v = (0..1000)
+        .map(|i| {
+            let x = f64::from(i % 10) * 10.;
+            let y = f64::from((i / 10) % 10) * 10.;
+            let z = f64::from(i / 100) * 10.;
+            AxisAlignedBBox::init(ShapeType::Rect, &[x, y, z, x + 1., y + 1., z + 1.])
+        })
+        .collect::<Vec<_>>();
+    let objs = v
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i as u64, x as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = Grid::init(CellSize::Auto);
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    assert!((a.get_cell_size() - 9.1).abs() < 0.01);
+    assert_eq!(a.get_dims(), [11, 11, 11]);
+    let mut a = GridHash::init(CellSize::Auto);
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    assert!((a.get_cell_size() - 9.1).abs() < 0.01);
+}
+
+#[test]
+fn test_grid_sparse_cells() {
+    //tiny manual cells over a wide domain only store the populated cells
+    let v = [
+        AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]),
+        AxisAlignedBBox::init(ShapeType::Point, &[0.75, 0.75, 0.75]),
+    ];
+    let objs = v
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i as u64, x as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = Grid::init(CellSize::Manual(1. / 512.));
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    assert_eq!(a.get_dims(), [385, 385, 385]);
+    assert_eq!(a.get_cell_count(), 2);
+    assert_eq!(a.get_cell([384, 384, 384]), Some(vec![1]));
+    assert_eq!(a.get_cell([1, 0, 0]), Some(vec![]));
+    assert_eq!(a.get_cell([385, 0, 0]), None);
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[0.5, 0.5, 0.5, 1., 1., 1.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![1]),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_grid_hash_unbounded() {
+    let mut a = GridHash::init(CellSize::Manual(1.));
+    let b0 = AxisAlignedBBox::init(ShapeType::Sphere, &[-1e9, -1e9, -1e9, 0.5]);
+    let b1 = AxisAlignedBBox::init(ShapeType::Sphere, &[1e9, 1e9, 1e9, 0.5]);
+    let objs = [(0u64, &b0 as &dyn IBound), (1u64, &b1 as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    assert!(a.get_cell_count() <= 16);
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[1e9, 1e9, 1e9]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![1]),
+        _ => panic!("query unexpected result"),
+    }
+    //query spanning the whole domain scans populated cells only
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[-2e9, -2e9, -2e9, 2e9, 2e9, 2e9]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 1]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+
+    //bounded grid over the same extent has too many cells
+    let mut a = Grid::init(CellSize::Manual(1.));
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for oversized grid"),
+    }
+
+    //single object spanning too many small cells
+    let mut a = GridHash::init(CellSize::Manual(1e-3));
+    let b2 = AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., 1e3, 1e3, 1e3]);
+    let objs = [(0u64, &b0 as &dyn IBound), (2u64, &b2 as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for oversized object"),
+    }
+    assert_eq!(a.get_cell_count(), 0);
+}
+
+///grid of unit cells over [0,5)^3
+fn build_unit_grid() -> Grid<u64> {
+    let mut a = Grid::init(CellSize::Manual(1.));
+    let b0 = AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., 0.5, 0.5, 0.5]);
+    let b1 = AxisAlignedBBox::init(ShapeType::Rect, &[3.5, 0.2, 0.2, 4., 0.7, 0.7]);
+    let b2 = AxisAlignedBBox::init(ShapeType::Rect, &[4.5, 4.5, 4.5, 4.9, 4.9, 4.9]);
+    let objs = [
+        (0u64, &b0 as &dyn IBound),
+        (1u64, &b1 as &dyn IBound),
+        (2u64, &b2 as &dyn IBound),
+    ];
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    assert_eq!(a.get_dims(), [5, 5, 5]);
+    a
+}
+
+#[test]
+fn test_grid_traverse_ray_axis() {
+    let a = build_unit_grid();
+
+    //ray from outside along +x through the row y=0,z=0
+    let r = Ray3::init(&[-2., 0.5, 0.5], &[1., 0., 0.]);
+    let mut hits = vec![];
+    match a.traverse_ray(&r, 100., |h| {
+        hits.push(h.clone());
+        false
+    }) {
+        Ok(None) => (),
+        _ => panic!("traversal unexpected result"),
+    }
+    assert_eq!(hits.len(), 5);
+    for (i, h) in hits.iter().enumerate() {
+        assert_eq!(h._coord, [i, 0, 0]);
+        assert!((h._t_entry - (2. + i as f64)).abs() < 1e-9);
+        assert!((h._t_exit - (3. + i as f64)).abs() < 1e-9);
+        assert_eq!(h._normal, [-1., 0., 0.]);
+    }
+
+    //stop at the first cell holding an object after the ray origin
+    let r = Ray3::init(&[1.5, 0.5, 0.5], &[1., 0., 0.]);
+    let ret = a.traverse_ray(&r, 100., |h| match a.get_cell(h._coord) {
+        Some(o) => !o.is_empty(),
+        _ => false,
+    });
+    match ret {
+        Ok(Some(h)) => {
+            assert_eq!(h._coord, [3, 0, 0]);
+            assert_eq!(a.get_cell(h._coord), Some(vec![1]));
+            assert!((h._t_entry - 1.5).abs() < 1e-9);
+            assert_eq!(h._normal, [-1., 0., 0.]);
+        }
+        _ => panic!("traversal unexpected result"),
+    }
+
+    //ray starting inside a cell has no entry face
+    let mut first = None;
+    let _ = a.traverse_ray(&r, 100., |h| {
+        first = Some(h.clone());
+        true
+    });
+    match first {
+        Some(h) => {
+            assert_eq!(h._coord, [1, 0, 0]);
+            assert_eq!(h._normal, [0., 0., 0.]);
+            assert_eq!(h._t_entry, 0.);
+        }
+        _ => panic!("traversal unexpected result"),
+    }
+
+    //ray along -y entering through the top face
+    let r = Ray3::init(&[2.5, 10., 3.5], &[0., -1., 0.]);
+    let mut hits = vec![];
+    let _ = a.traverse_ray(&r, 100., |h| {
+        hits.push(h.clone());
+        false
+    });
+    let coords = hits.iter().map(|h| h._coord).collect::<Vec<_>>();
+    assert_eq!(
+        coords,
+        vec![[2, 4, 3], [2, 3, 3], [2, 2, 3], [2, 1, 3], [2, 0, 3]]
+    );
+    assert!(hits.iter().all(|h| h._normal == [0., 1., 0.]));
+    assert!((hits[0]._t_entry - 5.).abs() < 1e-9);
+}
+
+#[test]
+fn test_grid_traverse_ray_diagonal_and_limits() {
+    let a = build_unit_grid();
+
+    //diagonal ray visits face adjacent cells in order with increasing distance
+    let r = Ray3::init(&[0.1, 0.3, 0.2], &[1., 0.7, 0.4]);
+    let mut hits: Vec<CellHit> = vec![];
+    let _ = a.traverse_ray(&r, 100., |h| {
+        hits.push(h.clone());
+        false
+    });
+    assert_eq!(hits[0]._coord, [0, 0, 0]);
+    assert!(hits.len() > 5);
+    for w in hits.windows(2) {
+        let diff = (0..3)
+            .map(|i| (w[1]._coord[i] as i64 - w[0]._coord[i] as i64).abs())
+            .sum::<i64>();
+        assert_eq!(diff, 1);
+        assert!((w[0]._t_exit - w[1]._t_entry).abs() < 1e-9);
+        assert!(w[1]._t_entry >= w[0]._t_entry);
+        let axis = (0..3).find(|i| w[0]._coord[*i] != w[1]._coord[*i]).unwrap();
+        assert_eq!(w[1]._normal[axis], -1.);
+    }
+    let last = hits.last().unwrap();
+    assert!((0..3).any(|i| last._coord[i] == 4));
+
+    //traversal limited by distance
+    let r = Ray3::init(&[0.5, 0.5, 0.5], &[1., 0., 0.]);
+    let mut count = 0;
+    let _ = a.traverse_ray(&r, 2.2, |h| {
+        count += 1;
+        assert!(h._t_exit <= 2.2);
+        false
+    });
+    assert_eq!(count, 3);
+
+    //ray missing the grid
+    let r = Ray3::init(&[-1., 10., 0.5], &[1., 0., 0.]);
+    let mut count = 0;
+    match a.traverse_ray(&r, 100., |_| {
+        count += 1;
+        false
+    }) {
+        Ok(None) => assert_eq!(count, 0),
+        _ => panic!("traversal unexpected result"),
+    }
+    //ray pointing away from the grid
+    let r = Ray3::init(&[-1., 0.5, 0.5], &[-1., 0., 0.]);
+    match a.traverse_ray(&r, 100., |_| true) {
+        Ok(None) => (),
+        _ => panic!("traversal unexpected result"),
+    }
+    match a.traverse_ray(&r, -1., |_| true) {
+        Err(_) => (),
+        _ => panic!("traversal unexpected result"),
+    }
+}
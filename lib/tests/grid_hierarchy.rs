@@ -0,0 +1,223 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::grid::CellSize;
+use implement::grid_hierarchy::GridHierarchy;
+use interface::i_spatial_accel::ISpatialAccel;
+
+#[test]
+fn test_grid_hierarchy_unsupported_bounds() {
+    let mut a = GridHierarchy::init(CellSize::Auto);
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    match a.insert(0u64, &b) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+}
+
+#[test]
+fn test_grid_hierarchy_levels() {
+    let mut a = GridHierarchy::init(CellSize::Manual(0.01));
+    //objects from centimetres to kilometres
+    let b0 = AxisAlignedBBox::init(ShapeType::Sphere, &[0., 0., 0., 0.005]);
+    let b1 = AxisAlignedBBox::init(ShapeType::Sphere, &[1., 0., 0., 0.5]);
+    let b2 = AxisAlignedBBox::init(ShapeType::Sphere, &[0., 0., 0., 1000.]);
+    let objs = [
+        (0u64, &b0 as &dyn IBound),
+        (1u64, &b1 as &dyn IBound),
+        (2u64, &b2 as &dyn IBound),
+    ];
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    assert_eq!(a.len(), 3);
+    //extents 0.01, 1 and 2000 map to cell sizes 0.01, 1.28 and 2621.44
+    assert_eq!(a.get_levels(), vec![0, 7, 18]);
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 2]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[1.2, 0.1, 0.1]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![1, 2]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[999., 999., 999.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![2]),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[1001., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_grid_hierarchy_insert_remove_update() {
+    let mut a = GridHierarchy::init(CellSize::Manual(1.));
+    let b0 = AxisAlignedBBox::init(ShapeType::Sphere, &[0., 0., 0., 0.25]);
+    let b1 = AxisAlignedBBox::init(ShapeType::Sphere, &[10., 0., 0., 4.]);
+    let h0 = a.insert(0u64, &b0).expect("insert unexpected result");
+    let h1 = a.insert(1u64, &b1).expect("insert unexpected result");
+    assert_eq!(a.get_levels(), vec![0, 3]);
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![0]),
+        _ => panic!("query unexpected result"),
+    }
+
+    //grow object 0 and move it next to object 1, which changes its level
+    let b0_moved = AxisAlignedBBox::init(ShapeType::Sphere, &[12., 0., 0., 2.]);
+    match a.update(h0, &b0_moved) {
+        Ok(()) => (),
+        _ => panic!("update unexpected result"),
+    }
+    assert_eq!(a.get_levels(), vec![2, 3]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[13., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 1]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+
+    match a.remove(h1) {
+        Ok(o) => assert_eq!(o, 1),
+        _ => panic!("remove unexpected result"),
+    }
+    assert_eq!(a.get_levels(), vec![2]);
+    match a.remove(h1) {
+        Err(_) => (),
+        _ => panic!("remove of stale handle unexpected result"),
+    }
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![0]),
+        _ => panic!("query unexpected result"),
+    }
+
+    //handles are reused after removal
+    let h2 = a.insert(2u64, &b1).expect("insert unexpected result");
+    assert_eq!(h2, h1);
+    assert_eq!(a.len(), 2);
+    match a.remove(h0) {
+        Ok(o) => assert_eq!(o, 0),
+        _ => panic!("remove unexpected result"),
+    }
+    match a.remove(h2) {
+        Ok(o) => assert_eq!(o, 2),
+        _ => panic!("remove unexpected result"),
+    }
+    assert!(a.is_empty());
+    assert!(a.get_levels().is_empty());
+}
+
+#[test]
+fn test_grid_hierarchy_random_query() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut a = GridHierarchy::init(CellSize::Auto);
+    let v = (0..2000u64)
+        .map(|x| {
+            let c = [
+                rng.gen_range(-500., 500.),
+                rng.gen_range(-500., 500.),
+                rng.gen_range(-500., 500.),
+            ];
+            //radii spanning five orders of magnitude
+            let r = 10f64.powf(rng.gen_range(-2., 3.));
+            (
+                x,
+                AxisAlignedBBox::init(ShapeType::Sphere, &[c[0], c[1], c[2], r]),
+            )
+        })
+        .collect::<Vec<_>>();
+    let objs = v
+        .iter()
+        .map(|x| (x.0, &x.1 as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    for _ in 0..100 {
+        let c = [
+            rng.gen_range(-600., 600.),
+            rng.gen_range(-600., 600.),
+            rng.gen_range(-600., 600.),
+        ];
+        let query =
+            AxisAlignedBBox::init(ShapeType::Box, &[c[0], c[1], c[2], rng.gen_range(0., 50.)]);
+        let mut expect = v
+            .iter()
+            .filter(|x| x.1.intersect(&query))
+            .map(|x| x.0)
+            .collect::<Vec<_>>();
+        expect.sort();
+        match a.query_intersect(&query) {
+            Ok(mut o) => {
+                o.sort();
+                assert_eq!(o, expect);
+            }
+            _ => panic!("query unexpected result"),
+        }
+        match a.query_intersect_single(&query) {
+            Ok(o) => assert_eq!(o.len(), expect.len().min(1)),
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
+
+#[test]
+fn test_grid_hierarchy_manual_cell_before_build() {
+    let mut a = GridHierarchy::init(CellSize::Manual(0.25));
+    assert_eq!(a.get_cell_size(0), 0.25);
+    assert_eq!(a.get_cell_size(3), 2.);
+    let b0 = AxisAlignedBBox::init(ShapeType::Sphere, &[0., 0., 0., 0.1]);
+    let b1 = AxisAlignedBBox::init(ShapeType::Sphere, &[10., 0., 0., 1.]);
+    let h0 = a.insert(0u64, &b0).expect("insert unexpected result");
+    a.insert(1u64, &b1).expect("insert unexpected result");
+    assert_eq!(a.get_levels(), vec![0, 3]);
+
+    let b0_grown = AxisAlignedBBox::init(ShapeType::Sphere, &[0., 0., 0., 0.5]);
+    match a.update(h0, &b0_grown) {
+        Ok(()) => (),
+        _ => panic!("update unexpected result"),
+    }
+    assert_eq!(a.get_levels(), vec![2, 3]);
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0.4, 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![0]),
+        _ => panic!("query unexpected result"),
+    }
+}
@@ -0,0 +1,153 @@
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use implement::interval_tree::IntervalTree;
+
+#[test]
+fn test_interval_tree_stab_and_overlap() {
+    let mut a = IntervalTree::init();
+    let h0 = a.insert(0., 10., 0u32).expect("insert");
+    let h1 = a.insert(5., 6., 1).expect("insert");
+    let h2 = a.insert(8., 20., 2).expect("insert");
+    let h3 = a.insert(-3., -1., 3).expect("insert");
+    assert_eq!(a.len(), 4);
+
+    match a.query_stab(5.5) {
+        Ok(o) => assert_eq!(o, vec![0, 1]),
+        _ => panic!("query unexpected result"),
+    }
+    //closed intervals include their ends
+    match a.query_stab(10.) {
+        Ok(o) => assert_eq!(o, vec![0, 2]),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_stab(-0.5) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_overlap(-1., 5.) {
+        Ok(o) => assert_eq!(o, vec![3, 0, 1]),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_overlap_single(7., 100.) {
+        Ok(o) => assert_eq!(o.len(), 1),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_overlap_handles(19., 30.) {
+        Ok(o) => assert_eq!(o, vec![h2]),
+        _ => panic!("query unexpected result"),
+    }
+
+    assert_eq!(a.remove(h0), Ok(0));
+    assert!(a.remove(h0).is_err());
+    assert_eq!(a.get(h1), Some(&1));
+    assert_eq!(a.get_interval(h3), Some((-3., -1.)));
+    match a.query_stab(5.5) {
+        Ok(o) => assert_eq!(o, vec![1]),
+        _ => panic!("query unexpected result"),
+    }
+
+    assert!(a.insert(2., 1., 0).is_err());
+    assert!(a.insert(f64::NAN, 1., 0).is_err());
+    assert!(a.query_overlap(3., 2.).is_err());
+    assert!(a.query_stab(f64::NAN).is_err());
+    //unbounded intervals are allowed
+    a.insert(f64::NEG_INFINITY, f64::INFINITY, 4)
+        .expect("insert");
+    match a.query_stab(1e300) {
+        Ok(o) => assert_eq!(o, vec![4]),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_interval_tree_random_brute_force() {
+    let mut rng = StdRng::seed_from_u64(23);
+    let mut a = IntervalTree::init();
+    let mut live: Vec<Option<(f64, f64, u32)>> = vec![];
+    let mut free = vec![];
+    for i in 0..3000u32 {
+        if rng.gen_range(0, 3) == 0 && !a.is_empty() {
+            let handles = (0..live.len())
+                .filter(|x| live[*x].is_some())
+                .collect::<Vec<_>>();
+            let h = handles[rng.gen_range(0, handles.len())];
+            assert_eq!(a.remove(h), Ok(live[h].unwrap().2));
+            live[h] = None;
+            free.push(h);
+        } else {
+            let lo = rng.gen_range(0., 1000.);
+            let hi = lo + rng.gen_range(0., 50.);
+            let h = a.insert(lo, hi, i).expect("insert");
+            match free.pop() {
+                Some(f) => assert_eq!(h, f),
+                _ => {
+                    assert_eq!(h, live.len());
+                    live.push(None);
+                }
+            }
+            live[h] = Some((lo, hi, i));
+        }
+        //AVL height is below 1.45 log2(n + 2)
+        let bound = 1.45 * ((a.len() + 2) as f64).log2();
+        assert!(f64::from(a.height()) <= bound);
+
+        if i % 10 == 0 {
+            let lo = rng.gen_range(-10., 1010.);
+            let hi = lo + rng.gen_range(0., 20.);
+            let mut expect = live
+                .iter()
+                .filter_map(|x| *x)
+                .filter(|x| x.0 <= hi && lo <= x.1)
+                .collect::<Vec<_>>();
+            expect.sort_by(|x, y| x.partial_cmp(y).unwrap());
+            match a.query_overlap(lo, hi) {
+                Ok(o) => {
+                    assert_eq!(o, expect.iter().map(|x| x.2).collect::<Vec<_>>());
+                }
+                _ => panic!("query unexpected result"),
+            }
+            let mut expect = live
+                .iter()
+                .filter_map(|x| *x)
+                .filter(|x| x.0 <= lo && lo <= x.1)
+                .map(|x| x.2)
+                .collect::<Vec<_>>();
+            let mut o = a.query_stab(lo).expect("query");
+            expect.sort();
+            o.sort();
+            assert_eq!(o, expect);
+        }
+    }
+}
+
+#[test]
+fn test_interval_tree_build_all() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let objs = (0..1000u32)
+        .map(|i| {
+            let lo = rng.gen_range(0., 100.);
+            (i, lo, lo + rng.gen_range(0., 5.))
+        })
+        .collect::<Vec<_>>();
+    let mut a = IntervalTree::init();
+    a.insert(0., 1., 9999).expect("insert");
+    a.build_all(&objs[..]).expect("build");
+    assert_eq!(a.len(), objs.len());
+    assert!(a.height() <= 10);
+    for (i, o) in objs.iter().enumerate() {
+        assert_eq!(a.get(i), Some(&o.0));
+    }
+    let mut expect = objs
+        .iter()
+        .filter(|x| x.1 <= 50. && 50. <= x.2)
+        .map(|x| x.0)
+        .collect::<Vec<_>>();
+    let mut o = a.query_stab(50.).expect("query");
+    expect.sort();
+    o.sort();
+    assert_eq!(o, expect);
+    assert!(a.build_all(&[(0, 1., 0.)]).is_err());
+}
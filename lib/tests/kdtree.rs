@@ -0,0 +1,160 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::kdtree::KdTree;
+use interface::i_spatial_accel::ISpatialAccel;
+
+fn dist(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+fn random_points(rng: &mut StdRng, count: u64) -> Vec<(u64, [f64; 3])> {
+    (0..count)
+        .map(|x| {
+            (
+                x,
+                [
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(-100., 100.),
+                ],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_kdtree_unsupported_bounds() {
+    let mut a = KdTree::init();
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    let b = AxisAlignedBBox::init(ShapeType::Box, &[0., 0., 0., 5.]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for non point bound");
+        }
+    }
+    match a.build(&[(0u64, [0., f64::NAN, 0.])]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for non finite point");
+        }
+    }
+}
+
+#[test]
+fn test_kdtree_construction_and_query() {
+    let mut a = KdTree::init();
+    assert!(a.query_nearest(&[0., 0., 0.], 3).is_empty());
+
+    let bounds = (0..10)
+        .map(|x| AxisAlignedBBox::init(ShapeType::Point, &[x as f64, 0., 0.]))
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i as u64, x as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    assert_eq!(a.len(), 10);
+
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[2.5, -1., -1., 5., 1., 1.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![3, 4, 5]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_single(&query) {
+        Ok(o) => {
+            assert_eq!(o.len(), 1);
+            assert!(o[0] >= 3 && o[0] <= 5);
+        }
+        _ => panic!("query unexpected result"),
+    }
+
+    let o = a.query_nearest(&[6.2, 1., 0.], 2);
+    assert_eq!(o.iter().map(|x| x.0).collect::<Vec<_>>(), vec![6, 7]);
+    assert!((o[0].1 - (0.04f64 + 1.).sqrt()).abs() < 1e-9);
+
+    let o = a.query_radius(&[0., 0., 0.], 2.);
+    assert_eq!(o.iter().map(|x| x.0).collect::<Vec<_>>(), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_kdtree_random_brute_force() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let pts = random_points(&mut rng, 5000);
+    let mut a = KdTree::init();
+    a.build(&pts[..]).expect("build unexpected result");
+
+    for _ in 0..50 {
+        let p = [
+            rng.gen_range(-120., 120.),
+            rng.gen_range(-120., 120.),
+            rng.gen_range(-120., 120.),
+        ];
+        let mut expect = pts
+            .iter()
+            .map(|x| (dist(&p, &x.1), x.0))
+            .collect::<Vec<_>>();
+        expect.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        //knn
+        let o = a.query_nearest(&p, 10);
+        assert_eq!(o.len(), 10);
+        for (i, j) in o.iter().zip(expect.iter()) {
+            assert!((i.1 - j.0).abs() < 1e-9);
+        }
+
+        //approximate nearest neighbour stays within the error bound
+        let eps = 0.5;
+        let o = a.query_nearest_approx(&p, 10, eps);
+        assert_eq!(o.len(), 10);
+        for (i, j) in o.iter().zip(expect.iter()) {
+            assert!(i.1 <= j.0 * (1. + eps) + 1e-9);
+        }
+
+        //radius
+        let r = rng.gen_range(0., 30.);
+        let o = a.query_radius(&p, r);
+        let e = expect.iter().filter(|x| x.0 <= r).collect::<Vec<_>>();
+        assert_eq!(o.len(), e.len());
+        for (i, j) in o.iter().zip(e.iter()) {
+            assert!((i.1 - j.0).abs() < 1e-9);
+        }
+
+        //box
+        let h = rng.gen_range(0., 40.);
+        let b = AxisAlignedBBox::init(ShapeType::Box, &[p[0], p[1], p[2], h]);
+        let mut e = pts
+            .iter()
+            .filter(|x| (0..3).all(|i| b._bound_lower[i] <= x.1[i] && x.1[i] <= b._bound_upper[i]))
+            .map(|x| x.0)
+            .collect::<Vec<_>>();
+        e.sort();
+        let mut o = a.query_box(&b);
+        o.sort();
+        assert_eq!(o, e);
+    }
+}
@@ -0,0 +1,452 @@
+extern crate mazth;
+extern crate rand;
+extern crate rand_chacha;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+use self::rand_chacha::ChaChaRng;
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::cluster_select::{self, Criterion};
+use implement::kmeans::KMeans;
+use interface::i_cluster::{ClusterMethod, ClusterMetric, ClusterOrder, ClusterSize, ICluster};
+
+fn point(x: f64, y: f64, z: f64) -> AxisAlignedBBox {
+    AxisAlignedBBox::init(ShapeType::Point, &[x, y, z])
+}
+
+///points scattered about the given centres, listed round robin over the centres
+fn blobs(rng: &mut StdRng, centres: &[[f64; 3]], n: usize) -> Vec<AxisAlignedBBox> {
+    (0..n)
+        .map(|i| {
+            let c = centres[i % centres.len()];
+            point(
+                c[0] + rng.gen_range(-1., 1.),
+                c[1] + rng.gen_range(-1., 1.),
+                c[2] + rng.gen_range(-1., 1.),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_kmeans_separated_blobs() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let centres = [[0., 0., 0.], [50., 0., 0.], [0., 50., 50.]];
+    let bounds = blobs(&mut rng, &centres[..], 300);
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (100 + i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    for metric in [ClusterMetric::L1, ClusterMetric::L2] {
+        let mut a = KMeans::init(100);
+        a.build_all(
+            ClusterSize::Manual(3),
+            ClusterMethod::Average,
+            metric,
+            ClusterOrder::InOrder,
+            &objs[..],
+        )
+        .expect("build");
+        //first three objects seed one cluster per blob
+        for (i, _) in bounds.iter().enumerate() {
+            match a.query_cluster(100 + i as u64) {
+                Ok(c) => assert_eq!(c, (i % 3) as u64),
+                _ => panic!("query unexpected result"),
+            }
+        }
+        for (c, e) in a.get_centres().iter().zip(centres.iter()) {
+            for k in 0..3 {
+                assert!((c[k] - e[k]).abs() < 0.5);
+            }
+        }
+        assert!(a.get_iterations() <= 100);
+    }
+}
+
+#[test]
+fn test_kmeans_median_update() {
+    let bounds = [
+        point(0., 0., 0.),
+        point(1., 0., 0.),
+        point(2., 0., 0.),
+        point(100., 0., 0.),
+    ];
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = KMeans::init(10);
+    a.build_all(
+        ClusterSize::Manual(1),
+        ClusterMethod::Average,
+        ClusterMetric::L1,
+        ClusterOrder::InOrder,
+        &objs[..],
+    )
+    .expect("build");
+    assert_eq!(a.get_centres()[0], [1.5, 0., 0.]);
+    assert_eq!(a.get_cost(), 1.5 + 0.5 + 0.5 + 98.5);
+
+    a.build_all(
+        ClusterSize::Manual(1),
+        ClusterMethod::Average,
+        ClusterMetric::L2,
+        ClusterOrder::InOrder,
+        &objs[..],
+    )
+    .expect("build");
+    assert_eq!(a.get_centres()[0], [25.75, 0., 0.]);
+}
+
+#[test]
+fn test_kmeans_converged_state() {
+    let mut rng = StdRng::seed_from_u64(9);
+    let bounds = (0..500)
+        .map(|_| {
+            point(
+                rng.gen_range(-10., 10.),
+                rng.gen_range(-10., 10.),
+                rng.gen_range(-10., 10.),
+            )
+        })
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = KMeans::init(1000);
+    a.build_all(
+        ClusterSize::Manual(7),
+        ClusterMethod::Average,
+        ClusterMetric::L2,
+        ClusterOrder::InOrder,
+        &objs[..],
+    )
+    .expect("build");
+    assert!(a.get_iterations() < 1000);
+    //each object is closest to its own centre and each centre is the mean of its members
+    let centres = a.get_centres().to_vec();
+    let mut sums = vec![([0f64; 3], 0usize); centres.len()];
+    for (i, b) in bounds.iter().enumerate() {
+        let c = a.query_cluster(i as u64).expect("query") as usize;
+        let p = b._bound_lower;
+        let d = |x: &[f64; 3]| (0..3).map(|k| (p[k] - x[k]).powi(2)).sum::<f64>();
+        for other in centres.iter() {
+            assert!(d(&centres[c]) <= d(other) + 1e-9);
+        }
+        for (s, x) in sums[c].0.iter_mut().zip(p.iter()) {
+            *s += x;
+        }
+        sums[c].1 += 1;
+    }
+    for (c, s) in centres.iter().zip(sums.iter()) {
+        assert!(s.1 > 0);
+        for (x, sum) in c.iter().zip(s.0.iter()) {
+            assert!((x - sum / s.1 as f64).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_kmeans_invalid() {
+    let bounds = [point(0., 0., 0.), point(1., 0., 0.)];
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = KMeans::init(10);
+    let mut build = |size, method, input: &[(u64, &dyn IBound)]| {
+        a.build_all(
+            size,
+            method,
+            ClusterMetric::L2,
+            ClusterOrder::InOrder,
+            input,
+        )
+    };
+    assert!(build(ClusterSize::Manual(0), ClusterMethod::Average, &objs[..]).is_err());
+    assert!(build(ClusterSize::Manual(3), ClusterMethod::Average, &objs[..]).is_err());
+    assert!(build(ClusterSize::Manual(1), ClusterMethod::Density, &objs[..]).is_err());
+    let dup = [objs[0], objs[0]];
+    assert!(build(ClusterSize::Manual(1), ClusterMethod::Average, &dup[..]).is_err());
+    let s = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let sphere = [(0u64, &s as &dyn IBound)];
+    assert!(build(ClusterSize::Manual(1), ClusterMethod::Average, &sphere[..]).is_err());
+
+    build(ClusterSize::Manual(2), ClusterMethod::Average, &objs[..]).expect("build");
+    assert!(a.query_cluster(5).is_err());
+    assert_eq!(a.query_cluster(1), Ok(1));
+}
+
+#[test]
+fn test_kmeans_auto() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let centres = [[0., 0., 0.], [20., 0., 0.], [0., 20., 0.], [20., 20., 20.]];
+    let bounds = blobs(&mut rng, &centres[..], 200);
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = KMeans::init(100);
+    for criterion in [
+        Criterion::Silhouette,
+        Criterion::Gap {
+            references: 10,
+            seed: 1,
+        },
+    ] {
+        a.set_auto(1, 8, criterion).expect("set auto");
+        a.build_all(
+            ClusterSize::Auto,
+            ClusterMethod::Average,
+            ClusterMetric::L2,
+            ClusterOrder::InOrder,
+            &objs[..],
+        )
+        .expect("build");
+        assert_eq!(a.get_centres().len(), 4);
+        let ks = a.get_scores().iter().map(|x| x.0).collect::<Vec<_>>();
+        match criterion {
+            //silhouette is undefined for a single cluster
+            Criterion::Silhouette => assert_eq!(ks, (2..=8).collect::<Vec<_>>()),
+            _ => assert_eq!(ks, (1..=8).collect::<Vec<_>>()),
+        }
+        for (i, _) in bounds.iter().enumerate() {
+            assert_eq!(a.query_cluster(i as u64), Ok((i % 4) as u64));
+        }
+        //scores are reproducible
+        let scores = a.get_scores().to_vec();
+        a.build_all(
+            ClusterSize::Auto,
+            ClusterMethod::Average,
+            ClusterMetric::L2,
+            ClusterOrder::InOrder,
+            &objs[..],
+        )
+        .expect("build");
+        assert_eq!(a.get_scores(), &scores[..]);
+    }
+
+    //manual builds do not score
+    a.build_all(
+        ClusterSize::Manual(2),
+        ClusterMethod::Average,
+        ClusterMetric::L2,
+        ClusterOrder::InOrder,
+        &objs[..],
+    )
+    .expect("build");
+    assert!(a.get_scores().is_empty());
+
+    assert!(a.set_auto(0, 3, Criterion::Silhouette).is_err());
+    assert!(a.set_auto(4, 3, Criterion::Silhouette).is_err());
+    assert!(a.set_auto(1, 3, Criterion::Bic).is_err());
+    let gap = Criterion::Gap {
+        references: 0,
+        seed: 1,
+    };
+    assert!(a.set_auto(1, 3, gap).is_err());
+    a.set_auto(1, 3, Criterion::Silhouette).expect("set auto");
+    assert!(a
+        .build_all(
+            ClusterSize::Auto,
+            ClusterMethod::Average,
+            ClusterMetric::L2,
+            ClusterOrder::InOrder,
+            &objs[..2],
+        )
+        .is_err());
+}
+
+///points scattered about the given centres, listed one centre after another
+fn blocks(rng: &mut StdRng, centres: &[[f64; 3]], per_centre: usize) -> Vec<AxisAlignedBBox> {
+    (0..centres.len() * per_centre)
+        .map(|i| {
+            let c = centres[i / per_centre];
+            point(
+                c[0] + rng.gen_range(-1., 1.),
+                c[1] + rng.gen_range(-1., 1.),
+                c[2] + rng.gen_range(-1., 1.),
+            )
+        })
+        .collect()
+}
+
+///objects from the same block share a cluster and objects from different blocks do not
+fn check_blocks(a: &KMeans, count: usize, per_centre: usize) {
+    let clusters = (0..count * per_centre)
+        .map(|i| a.query_cluster(i as u64).expect("query"))
+        .collect::<Vec<_>>();
+    for (i, c) in clusters.iter().enumerate() {
+        assert_eq!(*c, clusters[(i / per_centre) * per_centre]);
+    }
+    let mut firsts = (0..count)
+        .map(|b| clusters[b * per_centre])
+        .collect::<Vec<_>>();
+    firsts.sort();
+    firsts.dedup();
+    assert_eq!(firsts.len(), count);
+}
+
+#[test]
+fn test_kmeans_presample() {
+    let mut rng = StdRng::seed_from_u64(12);
+    let centres = [
+        [0., 0., 0.],
+        [30., 0., 0.],
+        [0., 30., 0.],
+        [0., 0., 30.],
+        [30., 30., 30.],
+    ];
+    let bounds = blocks(&mut rng, &centres[..], 100);
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let build = |a: &mut KMeans, metric| {
+        a.build_all(
+            ClusterSize::Manual(5),
+            ClusterMethod::Average,
+            metric,
+            ClusterOrder::PreSample,
+            &objs[..],
+        )
+        .expect("build");
+    };
+    for metric in [ClusterMetric::L1, ClusterMetric::L2] {
+        let mut a = KMeans::init(100);
+        a.set_seed(7);
+        a.set_sample_size(100).expect("sample size");
+        build(&mut a, metric);
+        check_blocks(&a, centres.len(), 100);
+
+        //same seed gives the same clustering
+        let mut b = KMeans::init(100);
+        b.set_seed(7);
+        b.set_sample_size(100).expect("sample size");
+        build(&mut b, metric);
+        assert_eq!(a.get_centres(), b.get_centres());
+        assert_eq!(a.get_iterations(), b.get_iterations());
+        for i in 0..bounds.len() as u64 {
+            assert_eq!(a.query_cluster(i), b.query_cluster(i));
+        }
+    }
+    let mut a = KMeans::init(100);
+    assert!(a.set_sample_size(0).is_err());
+    //a sample smaller than k still draws k objects
+    a.set_sample_size(1).expect("sample size");
+    build(&mut a, ClusterMetric::L2);
+    assert_eq!(a.get_centres().len(), 5);
+}
+
+#[test]
+fn test_kmeans_mini_batch() {
+    let mut rng = StdRng::seed_from_u64(15);
+    let centres = [[0., 0., 0.], [30., 0., 0.], [0., 30., 0.], [0., 0., 30.]];
+    let bounds = blocks(&mut rng, &centres[..], 500);
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let fit = |seed| {
+        let mut a = KMeans::init(50);
+        a.set_seed(seed);
+        a.set_sample_size(200).expect("sample size");
+        a.set_mini_batch(64);
+        a.build_all(
+            ClusterSize::Manual(4),
+            ClusterMethod::Average,
+            ClusterMetric::L2,
+            ClusterOrder::PreSample,
+            &objs[..],
+        )
+        .expect("build");
+        a
+    };
+    let a = fit(3);
+    assert_eq!(a.get_iterations(), 50);
+    check_blocks(&a, centres.len(), 500);
+    for c in a.get_centres() {
+        assert!(centres
+            .iter()
+            .any(|e| (0..3).all(|k| (c[k] - e[k]).abs() < 0.5)));
+    }
+    let b = fit(3);
+    assert_eq!(a.get_centres(), b.get_centres());
+    let c = fit(4);
+    check_blocks(&c, centres.len(), 500);
+
+    let mut a = KMeans::init(50);
+    a.set_mini_batch(64);
+    assert!(a
+        .build_all(
+            ClusterSize::Manual(4),
+            ClusterMethod::Average,
+            ClusterMetric::L1,
+            ClusterOrder::InOrder,
+            &objs[..],
+        )
+        .is_err());
+}
+
+#[test]
+fn test_kmeans_gap_duplicates() {
+    //three distinct positions each repeated, a clustering with k >= 3 can have zero cost
+    let bounds = (0..30)
+        .map(|i| point(f64::from(i % 3) * 10., 0., 0.))
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = KMeans::init(100);
+    let gap = Criterion::Gap {
+        references: 5,
+        seed: 2,
+    };
+    a.set_auto(1, 30, gap).expect("set auto");
+    let build = |a: &mut KMeans, objs: &[(u64, &dyn IBound)]| {
+        a.build_all(
+            ClusterSize::Auto,
+            ClusterMethod::Average,
+            ClusterMetric::L2,
+            ClusterOrder::InOrder,
+            objs,
+        )
+    };
+    build(&mut a, &objs[..]).expect("build");
+    let ks = a.get_scores().iter().map(|x| x.0).collect::<Vec<_>>();
+    assert_eq!(ks, vec![1, 2]);
+    assert!(a.get_scores().iter().all(|x| x.1.is_finite()));
+
+    //every object at the same position leaves no k with a positive cost
+    let same = [point(1., 1., 1.), point(1., 1., 1.), point(1., 1., 1.)];
+    let objs = same
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    assert!(build(&mut a, &objs[..]).is_err());
+}
+
+#[test]
+fn test_kmeans_seed_plus_plus_pinned() {
+    //seeding draws from a named rng algorithm, so a seed maps to fixed picks on every platform
+    let points = (0..20)
+        .map(|i| [f64::from(i), f64::from(i * i % 7), 0.])
+        .collect::<Vec<_>>();
+    let mut rng = ChaChaRng::seed_from_u64(5);
+    let centres = cluster_select::seed_plus_plus(ClusterMetric::L2, &points[..], 3, 10, &mut rng);
+    assert_eq!(centres, vec![[17., 2., 0.], [3., 2., 0.], [14., 0., 0.]]);
+}
@@ -0,0 +1,64 @@
+extern crate mazth;
+
+use self::mazth::{bound::AxisAlignedBBox, i_bound::IBound, i_shape::ShapeType};
+use interface::i_spatial_accel_mask::ISpatialAccelMask;
+
+///builds 20 spheres with even ids in category 0b01 and odd ids in category 0b10 and checks
+///filtered queries against them
+pub fn check_mask_query<A: ISpatialAccelMask<u64>>(a: &mut A) {
+    let mut bounds = vec![];
+    let mut bound_refs = vec![];
+    for i in 0..20 {
+        let aabb = AxisAlignedBBox::init(
+            ShapeType::Sphere,
+            &[f64::from(i), f64::from(i), f64::from(i), 5f64],
+        );
+        bounds.push(aabb);
+    }
+    for (i, b) in bounds.iter().enumerate() {
+        bound_refs.push((i as u64, b as &dyn IBound, 1u64 << (i % 2)));
+    }
+
+    match a.build_all_mask(&bound_refs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0f64, 0f64, 0f64]);
+    match a.query_intersect_mask(&query, 0b01) {
+        Ok(o) => {
+            assert_eq!(o.len(), 3);
+            assert!(o.iter().all(|x| *x % 2 == 0 && *x <= 5));
+        }
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_mask(&query, 0b10) {
+        Ok(o) => {
+            assert_eq!(o.len(), 3);
+            assert!(o.iter().all(|x| *x % 2 == 1 && *x <= 5));
+        }
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_mask(&query, 0b11) {
+        Ok(o) => assert_eq!(o.len(), 6),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_mask(&query, 0b100) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_single_mask(&query, 0b10) {
+        Ok(o) => {
+            assert_eq!(o.len(), 1);
+            assert!(o[0] % 2 == 1);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    //unmasked query sees every category
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o.len(), 6),
+        _ => panic!("query unexpected result"),
+    }
+}
@@ -1,2 +1,36 @@
+mod balltree;
+mod bih;
+mod bsp;
+#[allow(
+    clippy::single_match,
+    clippy::needless_range_loop,
+    clippy::len_zero,
+    clippy::unnecessary_cast
+)]
 mod bvh;
+mod bvh_aggregate;
+mod bvh_instance;
+#[allow(
+    clippy::single_match,
+    clippy::needless_range_loop,
+    clippy::len_zero,
+    clippy::bool_assert_comparison
+)]
 mod bvh_median;
+mod bvh_motion;
+mod dbscan;
+mod gmm;
+mod grid;
+mod grid_hierarchy;
+mod interval_tree;
+mod kdtree;
+mod kmeans;
+mod mask;
+mod octree;
+mod octree_loose;
+mod rtree;
+mod rtree_packed;
+mod spacefill;
+mod svo;
+mod sweep_prune;
+mod vptree;
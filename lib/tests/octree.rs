@@ -0,0 +1,255 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::octree::Octree;
+use interface::i_spatial_accel::ISpatialAccel;
+
+fn unit_domain() -> AxisAlignedBBox {
+    AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., 1., 1., 1.])
+}
+
+#[test]
+fn test_octree_unsupported_bounds() {
+    let mut a = Octree::init(unit_domain(), 8, 4);
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    let b = AxisAlignedBBox::init(ShapeType::Point, &[2., 0.5, 0.5]);
+    match a.insert(0u64, &b) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for object outside of octree");
+        }
+    }
+}
+
+#[test]
+fn test_octree_construction_and_query() {
+    let mut a = Octree::init(unit_domain(), 8, 2);
+    let mut bounds = vec![];
+    for i in 0..20 {
+        let aabb = AxisAlignedBBox::init(
+            ShapeType::Sphere,
+            &[f64::from(i), f64::from(i), f64::from(i), 5f64],
+        );
+        bounds.push(aabb);
+    }
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (i as u64, b as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    assert_eq!(a.len(), 20);
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 1, 2, 3, 4, 5]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[-5., -5., -5.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![0]),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[26., 26., 26.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[19., 19., 19.]);
+    match a.query_intersect_single(&query) {
+        Ok(o) => {
+            assert_eq!(o.len(), 1);
+            assert!(o[0] >= 14);
+        }
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_octree_split_thresholds() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let points = (0..1000)
+        .map(|_| {
+            [
+                rng.gen_range(0., 1.),
+                rng.gen_range(0., 1.),
+                rng.gen_range(0., 1.),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut a = Octree::init(unit_domain(), 3, 4);
+    for (i, p) in points.iter().enumerate() {
+        a.insert_point(i, p).expect("insert unexpected result");
+    }
+    assert_eq!(a.depth(), 3);
+    assert!(a.node_count() > 1);
+
+    let mut b = Octree::init(unit_domain(), 10, 2000);
+    for (i, p) in points.iter().enumerate() {
+        b.insert_point(i, p).expect("insert unexpected result");
+    }
+    assert_eq!(b.depth(), 0);
+    assert_eq!(b.node_count(), 1);
+
+    //removing most objects collapses the tree
+    let mut handles = vec![];
+    let mut c = Octree::init(unit_domain(), 10, 4);
+    for (i, p) in points.iter().enumerate() {
+        handles.push(c.insert_point(i, p).expect("insert unexpected result"));
+    }
+    assert!(c.node_count() > 100);
+    for h in handles.iter().skip(3) {
+        c.remove(*h).expect("remove unexpected result");
+    }
+    assert_eq!(c.len(), 3);
+    assert_eq!(c.node_count(), 1);
+    let query = unit_domain();
+    match c.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 1, 2]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_octree_insert_remove_update() {
+    let mut a = Octree::init(unit_domain(), 6, 1);
+    let h0 = a.insert_point(0u64, &[0.1, 0.1, 0.1]).unwrap();
+    let h1 = a.insert_point(1u64, &[0.9, 0.9, 0.9]).unwrap();
+    let b2 = AxisAlignedBBox::init(ShapeType::Rect, &[0.4, 0.4, 0.4, 0.6, 0.6, 0.6]);
+    let h2 = a.insert(2u64, &b2).unwrap();
+
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., 0.5, 0.5, 0.5]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 2]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+
+    let moved = AxisAlignedBBox::init(ShapeType::Point, &[0.2, 0.2, 0.2]);
+    a.update(h1, &moved).expect("update unexpected result");
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 1, 2]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+
+    assert_eq!(a.remove(h2), Ok(2));
+    match a.remove(h2) {
+        Err(_) => (),
+        _ => panic!("remove of stale handle unexpected result"),
+    }
+    assert_eq!(a.remove(h0), Ok(0));
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![1]),
+        _ => panic!("query unexpected result"),
+    }
+    assert_eq!(a.len(), 1);
+}
+
+#[test]
+fn test_octree_random_query_and_knn() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let v = (0..2000u64)
+        .map(|x| {
+            let c = [
+                rng.gen_range(-50., 50.),
+                rng.gen_range(-50., 50.),
+                rng.gen_range(-50., 50.),
+            ];
+            //mix of points and boxes
+            let r = if x % 2 == 0 {
+                0.
+            } else {
+                rng.gen_range(0.01, 3.)
+            };
+            (
+                x,
+                AxisAlignedBBox::init(ShapeType::Sphere, &[c[0], c[1], c[2], r]),
+            )
+        })
+        .collect::<Vec<_>>();
+    let objs = v
+        .iter()
+        .map(|x| (x.0, &x.1 as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = Octree::init(unit_domain(), 10, 8);
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    for _ in 0..50 {
+        let c = [
+            rng.gen_range(-60., 60.),
+            rng.gen_range(-60., 60.),
+            rng.gen_range(-60., 60.),
+        ];
+        let query =
+            AxisAlignedBBox::init(ShapeType::Box, &[c[0], c[1], c[2], rng.gen_range(0., 10.)]);
+        let mut expect = v
+            .iter()
+            .filter(|x| x.1.intersect(&query))
+            .map(|x| x.0)
+            .collect::<Vec<_>>();
+        expect.sort();
+        match a.query_intersect(&query) {
+            Ok(mut o) => {
+                o.sort();
+                assert_eq!(o, expect);
+            }
+            _ => panic!("query unexpected result"),
+        }
+
+        //nearest neighbours agree with brute force distances
+        let mut dists = v
+            .iter()
+            .map(|x| {
+                (0..3)
+                    .map(|i| {
+                        let d = (x.1._bound_lower[i] - c[i])
+                            .max(c[i] - x.1._bound_upper[i])
+                            .max(0.);
+                        d * d
+                    })
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .collect::<Vec<_>>();
+        dists.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let nearest = a.query_nearest(&c, 10);
+        assert_eq!(nearest.len(), 10);
+        for (n, d) in nearest.iter().zip(dists.iter()) {
+            assert!((n.1 - d).abs() < 1e-9);
+        }
+    }
+    assert!(a.query_nearest(&[0., 0., 0.], 0).is_empty());
+    assert_eq!(a.query_nearest(&[0., 0., 0.], 5000).len(), 2000);
+}
@@ -0,0 +1,198 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::octree_loose::OctreeLoose;
+use interface::i_spatial_accel::ISpatialAccel;
+
+fn world() -> AxisAlignedBBox {
+    AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., 64., 64., 64.])
+}
+
+#[test]
+fn test_octree_loose_unsupported_bounds() {
+    let mut a = OctreeLoose::init(world(), 2., 8);
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    let b = AxisAlignedBBox::init(ShapeType::Sphere, &[70., 1., 1., 1.]);
+    match a.insert(0u64, &b) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for object outside of octree");
+        }
+    }
+}
+
+#[test]
+fn test_octree_loose_depth_selection() {
+    let mut a = OctreeLoose::init(world(), 2., 5);
+    //with looseness 2 an object of extent e goes to the deepest level with cell size at least e
+    let b0 = AxisAlignedBBox::init(ShapeType::Sphere, &[10., 10., 10., 16.]);
+    let b1 = AxisAlignedBBox::init(ShapeType::Sphere, &[10., 10., 10., 4.]);
+    let b2 = AxisAlignedBBox::init(ShapeType::Sphere, &[10., 10., 10., 0.01]);
+    let b3 = AxisAlignedBBox::init(ShapeType::Sphere, &[10., 10., 10., 100.]);
+    let h0 = a.insert(0u64, &b0).unwrap();
+    let h1 = a.insert(1u64, &b1).unwrap();
+    let h2 = a.insert(2u64, &b2).unwrap();
+    let h3 = a.insert(3u64, &b3).unwrap();
+    assert_eq!(a.get_depth(h0), Some(1));
+    assert_eq!(a.get_depth(h1), Some(3));
+    assert_eq!(a.get_depth(h2), Some(5));
+    assert_eq!(a.get_depth(h3), Some(0));
+
+    //objects stay inside the loose bound of their node
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[-80., -80., -80.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![3]),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[10., 10., 10.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 1, 2, 3]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[-5., 10., 10.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 3]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_octree_loose_move() {
+    let mut a = OctreeLoose::init(world(), 2., 6);
+    let b = AxisAlignedBBox::init(ShapeType::Sphere, &[1., 1., 1., 0.5]);
+    let h = a.insert(7u64, &b).unwrap();
+    let nodes = a.node_count();
+
+    //small move within the same cell keeps the node
+    let b = AxisAlignedBBox::init(ShapeType::Sphere, &[1.2, 1.1, 1., 0.5]);
+    a.update(h, &b).expect("update unexpected result");
+    assert_eq!(a.node_count(), nodes);
+
+    //move across the world
+    let b = AxisAlignedBBox::init(ShapeType::Sphere, &[60., 60., 60., 0.5]);
+    a.update(h, &b).expect("update unexpected result");
+    assert_eq!(a.node_count(), nodes);
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[1., 1., 1.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[60., 60., 60.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![7]),
+        _ => panic!("query unexpected result"),
+    }
+
+    assert_eq!(a.remove(h), Ok(7));
+    assert!(a.is_empty());
+    assert_eq!(a.node_count(), 0);
+    match a.update(h, &b) {
+        Err(_) => (),
+        _ => panic!("update of stale handle unexpected result"),
+    }
+}
+
+#[test]
+fn test_octree_loose_random_moving() {
+    let mut rng = StdRng::seed_from_u64(13);
+    let mut a = OctreeLoose::init(world(), 2., 8);
+    let mut v = (0..1000u64)
+        .map(|x| {
+            let c = [
+                rng.gen_range(0., 64.),
+                rng.gen_range(0., 64.),
+                rng.gen_range(0., 64.),
+            ];
+            let r = rng.gen_range(0., 2.);
+            (
+                x,
+                AxisAlignedBBox::init(ShapeType::Sphere, &[c[0], c[1], c[2], r]),
+            )
+        })
+        .collect::<Vec<_>>();
+    let handles = v
+        .iter()
+        .map(|x| a.insert(x.0, &x.1).unwrap())
+        .collect::<Vec<_>>();
+
+    for _frame in 0..10 {
+        //move entities
+        for (h, x) in handles.iter().zip(v.iter_mut()) {
+            let c = x.1.get_centroid();
+            let r = (x.1._bound_upper[0] - x.1._bound_lower[0]) / 2.;
+            let c = [
+                (c[0] + rng.gen_range(-3., 3.)).clamp(0., 64.),
+                (c[1] + rng.gen_range(-3., 3.)).clamp(0., 64.),
+                (c[2] + rng.gen_range(-3., 3.)).clamp(0., 64.),
+            ];
+            x.1 = AxisAlignedBBox::init(ShapeType::Sphere, &[c[0], c[1], c[2], r]);
+            a.update(*h, &x.1).expect("update unexpected result");
+        }
+        for _ in 0..20 {
+            let c = [
+                rng.gen_range(0., 64.),
+                rng.gen_range(0., 64.),
+                rng.gen_range(0., 64.),
+            ];
+            let query =
+                AxisAlignedBBox::init(ShapeType::Box, &[c[0], c[1], c[2], rng.gen_range(0., 8.)]);
+            let mut expect = v
+                .iter()
+                .filter(|x| x.1.intersect(&query))
+                .map(|x| x.0)
+                .collect::<Vec<_>>();
+            expect.sort();
+            match a.query_intersect(&query) {
+                Ok(mut o) => {
+                    o.sort();
+                    assert_eq!(o, expect);
+                }
+                _ => panic!("query unexpected result"),
+            }
+        }
+    }
+
+    //rebuild from scratch gives the same answers
+    let objs = v
+        .iter()
+        .map(|x| (x.0, &x.1 as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+    assert_eq!(a.len(), 1000);
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[10., 10., 10., 30., 30., 30.]);
+    let mut expect = v
+        .iter()
+        .filter(|x| x.1.intersect(&query))
+        .map(|x| x.0)
+        .collect::<Vec<_>>();
+    expect.sort();
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, expect);
+        }
+        _ => panic!("query unexpected result"),
+    }
+}
@@ -0,0 +1,153 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::rtree::RTree;
+use interface::i_spatial_accel::ISpatialAccel;
+
+fn random_box(rng: &mut StdRng) -> AxisAlignedBBox {
+    AxisAlignedBBox::init(
+        ShapeType::Box,
+        &[
+            rng.gen_range(-100., 100.),
+            rng.gen_range(-100., 100.),
+            rng.gen_range(-100., 100.),
+            rng.gen_range(0., 3.),
+        ],
+    )
+}
+
+fn check_query(a: &RTree<u64>, v: &[Option<AxisAlignedBBox>], query: &AxisAlignedBBox) {
+    let mut expect = v
+        .iter()
+        .enumerate()
+        .filter_map(|(i, x)| match x {
+            Some(b) if b.intersect(query) => Some(i as u64),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    expect.sort();
+    match a.query_intersect(query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, expect);
+        }
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_rtree_unsupported_bounds() {
+    let mut a = RTree::init(2, 4);
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    match a.query_intersect(&b) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+}
+
+#[test]
+fn test_rtree_construction_and_fanout() {
+    let mut a = RTree::init(2, 4);
+    let bounds = (0..64)
+        .map(|x| AxisAlignedBBox::init(ShapeType::Box, &[x as f64 * 3., 0., 0., 1.]))
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i as u64, x as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    assert_eq!(a.len(), 64);
+    //with at least 2 entries per node the height is bounded by log2
+    assert!(a.height() >= 3 && a.height() <= 6);
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[30., 0.5, 0.5]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![10]),
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_single(&bounds[20]) {
+        Ok(o) => assert_eq!(o, vec![20]),
+        _ => panic!("query unexpected result"),
+    }
+
+    //removing everything condenses back to a single empty leaf
+    for h in 0..64 {
+        assert_eq!(a.remove(h), Ok(h as u64));
+    }
+    assert!(a.is_empty());
+    assert_eq!(a.height(), 1);
+    assert_eq!(a.node_count(), 1);
+    match a.remove(0) {
+        Err(_) => (),
+        _ => panic!("remove of stale handle unexpected result"),
+    }
+}
+
+#[test]
+fn test_rtree_random_dynamic() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut a = RTree::init(4, 16);
+    let mut v: Vec<Option<AxisAlignedBBox>> = vec![];
+    for i in 0..2000u64 {
+        let b = random_box(&mut rng);
+        let h = a.insert(i, &b).unwrap();
+        assert_eq!(h, v.len());
+        v.push(Some(b));
+    }
+    for _ in 0..20 {
+        let query = random_box(&mut rng);
+        check_query(&a, &v[..], &query);
+    }
+
+    //churn: remove, move and reinsert features, freed handles are reused most recent first
+    let mut free = vec![];
+    for round in 0..2000 {
+        let h = rng.gen_range(0, v.len());
+        match v[h] {
+            Some(_) if round % 3 == 0 => {
+                assert_eq!(a.remove(h), Ok(h as u64));
+                v[h] = None;
+                free.push(h);
+            }
+            Some(_) => {
+                let b = random_box(&mut rng);
+                a.update(h, &b).expect("update unexpected result");
+                v[h] = Some(b);
+            }
+            None => {
+                let h = free.pop().unwrap();
+                let b = random_box(&mut rng);
+                assert_eq!(a.insert(h as u64, &b), Ok(h));
+                v[h] = Some(b);
+            }
+        }
+        if round % 100 == 0 {
+            let query = random_box(&mut rng);
+            check_query(&a, &v[..], &query);
+        }
+    }
+    assert_eq!(a.len(), v.iter().filter(|x| x.is_some()).count());
+    let query = AxisAlignedBBox::init(ShapeType::Box, &[0., 0., 0., 200.]);
+    check_query(&a, &v[..], &query);
+}
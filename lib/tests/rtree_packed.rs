@@ -0,0 +1,131 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::rtree_packed::RTreePacked;
+use interface::i_spatial_accel::ISpatialAccel;
+
+#[test]
+fn test_rtree_packed_unsupported_bounds() {
+    let mut a = RTreePacked::init(8);
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+}
+
+#[test]
+fn test_rtree_packed_construction_and_query() {
+    let mut a = RTreePacked::init(4);
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert!(o.is_empty()),
+        _ => panic!("query unexpected result"),
+    }
+
+    let bounds = (0..10)
+        .map(|x| AxisAlignedBBox::init(ShapeType::Box, &[x as f64 * 3., 0., 0., 1.]))
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i as u64, x as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    //3 leaves and a root
+    assert_eq!(a.node_count(), 4);
+    assert_eq!(a.height(), 2);
+
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[12., 0.5, 0.5]);
+    match a.query_intersect(&query) {
+        Ok(o) => assert_eq!(o, vec![4]),
+        _ => panic!("query unexpected result"),
+    }
+    let query = AxisAlignedBBox::init(ShapeType::Rect, &[5., -1., -1., 13., 1., 1.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![2, 3, 4]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    match a.query_intersect_single(&query) {
+        Ok(o) => assert_eq!(o.len(), 1),
+        _ => panic!("query unexpected result"),
+    }
+}
+
+#[test]
+fn test_rtree_packed_random() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let bounds = (0..10000)
+        .map(|_| {
+            AxisAlignedBBox::init(
+                ShapeType::Box,
+                &[
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(-100., 100.),
+                    rng.gen_range(0., 2.),
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+    let objs = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i as u64, x as &dyn IBound))
+        .collect::<Vec<_>>();
+    let mut a = RTreePacked::init(16);
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    assert_eq!(a.len(), 10000);
+
+    //nodes are packed full: 625 leaves, 40 and 3 inner nodes and a root
+    assert_eq!(a.node_count(), 625 + 40 + 3 + 1);
+    assert_eq!(a.height(), 4);
+
+    for _ in 0..50 {
+        let query = AxisAlignedBBox::init(
+            ShapeType::Box,
+            &[
+                rng.gen_range(-100., 100.),
+                rng.gen_range(-100., 100.),
+                rng.gen_range(-100., 100.),
+                rng.gen_range(0., 20.),
+            ],
+        );
+        let mut expect = bounds
+            .iter()
+            .enumerate()
+            .filter(|x| x.1.intersect(&query))
+            .map(|x| x.0 as u64)
+            .collect::<Vec<_>>();
+        expect.sort();
+        match a.query_intersect(&query) {
+            Ok(mut o) => {
+                o.sort();
+                assert_eq!(o, expect);
+            }
+            _ => panic!("query unexpected result"),
+        }
+    }
+}
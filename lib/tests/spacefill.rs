@@ -0,0 +1,133 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::spacefill::{Curve, SpaceFill, MAX_BITS};
+
+fn unit(scale: f64) -> AxisAlignedBBox {
+    AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., scale, scale, scale])
+}
+
+#[test]
+fn test_spacefill_morton_known_keys() {
+    let a = SpaceFill::init(Curve::Morton, 4, unit(16.));
+    assert_eq!(a.encode_cell(&[1, 0, 0]), 1);
+    assert_eq!(a.encode_cell(&[0, 1, 0]), 2);
+    assert_eq!(a.encode_cell(&[0, 0, 1]), 4);
+    assert_eq!(a.encode_cell(&[3, 3, 3]), 63);
+    assert_eq!(a.encode(&[2.5, 0.5, 0.5]), 8);
+    assert_eq!(a.decode(8), [2, 0, 0]);
+    //points outside are clamped
+    assert_eq!(a.quantize(&[-5., 100., 8.]), [0, 15, 8]);
+
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    match a.encode_bound(&b) {
+        Err(_) => (),
+        _ => panic!("unexpected result for unsupported bound type"),
+    }
+}
+
+#[test]
+fn test_spacefill_roundtrip() {
+    let mut rng = StdRng::seed_from_u64(31);
+    for curve in [Curve::Morton, Curve::Hilbert] {
+        for bits in [1, 2, 5, 10, MAX_BITS] {
+            let a = SpaceFill::init(curve, bits, unit(1.));
+            let n = 1u32 << bits;
+            for _ in 0..200 {
+                let c = [
+                    rng.gen_range(0, n),
+                    rng.gen_range(0, n),
+                    rng.gen_range(0, n),
+                ];
+                let k = a.encode_cell(&c);
+                assert!(k < 1u64 << (3 * bits));
+                assert_eq!(a.decode(k), c);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_spacefill_hilbert_adjacency() {
+    //consecutive hilbert keys are face neighbours and every cell is visited once
+    let bits = 3;
+    let a = SpaceFill::init(Curve::Hilbert, bits, unit(1.));
+    let mut seen = vec![false; 512];
+    let mut prev = a.decode(0);
+    for k in 0..512u64 {
+        let c = a.decode(k);
+        let idx = (c[0] + c[1] * 8 + c[2] * 64) as usize;
+        assert!(!seen[idx]);
+        seen[idx] = true;
+        if k > 0 {
+            let d = (0..3).fold(0, |acc, i| acc + (c[i] as i64 - prev[i] as i64).abs());
+            assert_eq!(d, 1);
+        }
+        prev = c;
+    }
+}
+
+#[test]
+fn test_spacefill_decompose() {
+    let mut rng = StdRng::seed_from_u64(37);
+    for curve in [Curve::Morton, Curve::Hilbert] {
+        let a = SpaceFill::init(curve, 4, unit(16.));
+        for _ in 0..30 {
+            let lo = [
+                rng.gen_range(-2., 16.),
+                rng.gen_range(-2., 16.),
+                rng.gen_range(-2., 16.),
+            ];
+            let q = AxisAlignedBBox::init(
+                ShapeType::Rect,
+                &[
+                    lo[0],
+                    lo[1],
+                    lo[2],
+                    lo[0] + rng.gen_range(0., 8.),
+                    lo[1] + rng.gen_range(0., 8.),
+                    lo[2] + rng.gen_range(0., 8.),
+                ],
+            );
+            let ranges = a.decompose(&q, 4);
+            for w in ranges.windows(2) {
+                assert!(w[0].1 + 1 < w[1].0);
+            }
+            //exact at full depth
+            for k in 0..4096u64 {
+                let cell = a.get_cell_bound(&a.decode(k));
+                let covered = ranges.iter().any(|r| r.0 <= k && k <= r.1);
+                assert_eq!(covered, cell.intersect(&q));
+            }
+            //coarser depth gives fewer intervals covering a superset
+            let coarse = a.decompose(&q, 2);
+            assert!(coarse.len() <= ranges.len());
+            for r in ranges.iter() {
+                assert!(coarse.iter().any(|c| c.0 <= r.0 && r.1 <= c.1));
+            }
+        }
+        let outside = AxisAlignedBBox::init(ShapeType::Rect, &[20., 0., 0., 30., 1., 1.]);
+        assert!(a.decompose(&outside, 4).is_empty());
+    }
+}
+
+#[test]
+fn test_spacefill_sort_indices() {
+    let bounds = [
+        AxisAlignedBBox::init(ShapeType::Box, &[15., 15., 15., 0.5]),
+        AxisAlignedBBox::init(ShapeType::Box, &[0.5, 0.5, 0.5, 0.5]),
+        AxisAlignedBBox::init(ShapeType::Box, &[8.5, 0.5, 0.5, 0.5]),
+    ];
+    let b = bounds.iter().map(|x| x as &dyn IBound).collect::<Vec<_>>();
+    let a = SpaceFill::init(Curve::Morton, 4, unit(16.));
+    match a.sort_indices(&b[..]) {
+        Ok(o) => assert_eq!(o, vec![1, 2, 0]),
+        _ => panic!("unexpected result for supported bound type"),
+    }
+}
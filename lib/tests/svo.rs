@@ -0,0 +1,201 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use self::mazth::{bound::AxisAlignedBBox, i_shape::ShapeType, ray::Ray3};
+use implement::svo::{Svo, VoxelState};
+
+fn unit(scale: f64) -> AxisAlignedBBox {
+    AxisAlignedBBox::init(ShapeType::Rect, &[0., 0., 0., scale, scale, scale])
+}
+
+#[test]
+fn test_svo_set_get_clear() {
+    let mut a: Svo<u32> = Svo::init(unit(8.), 3);
+    assert_eq!(a.get(0, &[0, 0, 0]), Ok(VoxelState::Empty));
+    assert_eq!(a.node_count(), 1);
+
+    a.set(3, &[1, 2, 3], 7).expect("set");
+    assert_eq!(a.get(3, &[1, 2, 3]), Ok(VoxelState::Uniform(&7)));
+    assert_eq!(a.get(3, &[1, 2, 2]), Ok(VoxelState::Empty));
+    assert_eq!(a.get(0, &[0, 0, 0]), Ok(VoxelState::Mixed));
+    assert_eq!(a.get(1, &[0, 0, 0]), Ok(VoxelState::Mixed));
+    assert_eq!(a.get(1, &[1, 1, 1]), Ok(VoxelState::Empty));
+    assert_eq!(a.voxel_count(), 1);
+    assert_eq!(a.node_count(), 25);
+
+    //coarse set overrides finer content
+    a.set(1, &[0, 0, 0], 2).expect("set");
+    assert_eq!(a.get(3, &[1, 2, 3]), Ok(VoxelState::Uniform(&2)));
+    assert_eq!(a.voxel_count(), 64);
+    assert_eq!(a.node_count(), 9);
+
+    a.clear(3, &[0, 3, 3]).expect("clear");
+    assert_eq!(a.get(3, &[0, 3, 3]), Ok(VoxelState::Empty));
+    assert_eq!(a.get(3, &[1, 3, 3]), Ok(VoxelState::Uniform(&2)));
+    assert_eq!(a.voxel_count(), 63);
+
+    a.clear(0, &[0, 0, 0]).expect("clear");
+    assert_eq!(a.get(0, &[0, 0, 0]), Ok(VoxelState::Empty));
+    assert_eq!(a.node_count(), 1);
+
+    assert!(a.set(4, &[0, 0, 0], 1).is_err());
+    assert!(a.set(2, &[4, 0, 0], 1).is_err());
+    assert!(a.clear(3, &[0, 8, 0]).is_err());
+    assert!(a.get(1, &[0, 0, 2]).is_err());
+
+    assert_eq!(a.locate(&[1.5, 7.9, 8.]), Some([1, 7, 7]));
+    assert_eq!(a.locate(&[-0.1, 1., 1.]), None);
+    let b = a.get_voxel_bound(2, &[1, 0, 3]);
+    assert_eq!(b._bound_lower, [2., 0., 6.]);
+    assert_eq!(b._bound_upper, [4., 2., 8.]);
+}
+
+#[test]
+fn test_svo_merge_uniform_children() {
+    let mut a: Svo<u32> = Svo::init(unit(1.), 2);
+    for i in 0..8u32 {
+        let c = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+        for j in 0..8u32 {
+            let f = [
+                c[0] * 2 + (j & 1),
+                c[1] * 2 + ((j >> 1) & 1),
+                c[2] * 2 + ((j >> 2) & 1),
+            ];
+            a.set(2, &f, 5).expect("set");
+        }
+    }
+    assert_eq!(a.get(0, &[0, 0, 0]), Ok(VoxelState::Uniform(&5)));
+    assert_eq!(a.node_count(), 1);
+    assert_eq!(a.voxel_count(), 64);
+
+    //differing attribute prevents merging
+    a.set(2, &[3, 3, 3], 6).expect("set");
+    assert_eq!(a.node_count(), 17);
+    a.set(2, &[3, 3, 3], 5).expect("set");
+    assert_eq!(a.node_count(), 1);
+
+    //freed blocks are reused
+    a.set(2, &[0, 0, 0], 1).expect("set");
+    assert_eq!(a.node_count(), 17);
+    a.clear(0, &[0, 0, 0]).expect("clear");
+    a.set(2, &[0, 0, 0], 1).expect("set");
+    assert_eq!(a.node_count(), 17);
+}
+
+#[test]
+fn test_svo_cast_ray() {
+    let mut a: Svo<u32> = Svo::init(unit(4.), 2);
+    a.set(2, &[3, 1, 1], 1).expect("set");
+    a.set(2, &[2, 1, 1], 2).expect("set");
+    a.set(1, &[0, 0, 0], 3).expect("set");
+
+    let r = Ray3::init(&[-2., 1.5, 1.5], &[1., 0., 0.]);
+    match a.cast_ray(&r, 100., |_| true) {
+        Ok(Some(h)) => {
+            assert_eq!(*h._attr, 3);
+            assert_eq!(h._level, 1);
+            assert_eq!(h._coord, [0, 0, 0]);
+            assert!((h._t - 2.).abs() < 1e-9);
+        }
+        _ => panic!("cast unexpected result"),
+    }
+
+    //predicate skips attributes, traversal continues behind them
+    let mut visited = vec![];
+    match a.cast_ray(&r, 100., |x| {
+        visited.push(*x);
+        *x == 2
+    }) {
+        Ok(Some(h)) => {
+            assert_eq!(*h._attr, 2);
+            assert_eq!(h._coord, [2, 1, 1]);
+            assert!((h._t - 4.).abs() < 1e-9);
+        }
+        _ => panic!("cast unexpected result"),
+    }
+    assert_eq!(visited, vec![3, 2]);
+
+    //reversed ray hits the far side first
+    let r = Ray3::init(&[6., 1.5, 1.5], &[-1., 0., 0.]);
+    match a.cast_ray(&r, 100., |_| true) {
+        Ok(Some(h)) => assert_eq!(*h._attr, 1),
+        _ => panic!("cast unexpected result"),
+    }
+
+    //distance limit and misses
+    match a.cast_ray(&r, 1.5, |_| true) {
+        Ok(None) => (),
+        _ => panic!("cast unexpected result"),
+    }
+    let r = Ray3::init(&[-2., 3.5, 1.5], &[1., 0., 0.]);
+    match a.cast_ray(&r, 100., |_| true) {
+        Ok(None) => (),
+        _ => panic!("cast unexpected result"),
+    }
+    assert!(a.cast_ray(&r, -1., |_| true).is_err());
+}
+
+#[test]
+fn test_svo_random_dense() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let depth = 3;
+    let n = 1usize << depth;
+    let mut a: Svo<u32> = Svo::init(unit(8.), depth);
+    let mut dense = vec![None; n * n * n];
+    for _ in 0..2000 {
+        let level = rng.gen_range(0, depth + 1);
+        let s = 1u32 << level;
+        let c = [
+            rng.gen_range(0, s),
+            rng.gen_range(0, s),
+            rng.gen_range(0, s),
+        ];
+        let v = if rng.gen_range(0, 4) == 0 {
+            a.clear(level, &c).expect("clear");
+            None
+        } else {
+            let v = rng.gen_range(0, 3u32);
+            a.set(level, &c, v).expect("set");
+            Some(v)
+        };
+        let span = 1u32 << (depth - level);
+        for x in c[0] * span..(c[0] + 1) * span {
+            for y in c[1] * span..(c[1] + 1) * span {
+                for z in c[2] * span..(c[2] + 1) * span {
+                    dense[(x as usize * n + y as usize) * n + z as usize] = v;
+                }
+            }
+        }
+    }
+    let mut count = 0;
+    for (i, v) in dense.iter().enumerate() {
+        let c = [(i / (n * n)) as u32, (i / n % n) as u32, (i % n) as u32];
+        match *v {
+            Some(ref x) => {
+                count += 1;
+                assert_eq!(a.get(depth, &c), Ok(VoxelState::Uniform(x)));
+            }
+            _ => assert_eq!(a.get(depth, &c), Ok(VoxelState::Empty)),
+        }
+    }
+    assert_eq!(a.voxel_count(), count);
+
+    //rays along z stop at the first occupied voxel of each column
+    for x in 0..n {
+        for y in 0..n {
+            let r = Ray3::init(&[x as f64 + 0.5, y as f64 + 0.5, -1.], &[0., 0., 1.]);
+            let first = (0..n).find(|z| dense[(x * n + y) * n + z].is_some());
+            match (a.cast_ray(&r, 100., |_| true), first) {
+                (Ok(Some(h)), Some(z)) => {
+                    assert_eq!(Some(*h._attr), dense[(x * n + y) * n + z]);
+                    assert!((h._t - (z as f64 + 1.)).abs() < 1e-9);
+                }
+                (Ok(None), None) => (),
+                _ => panic!("cast unexpected result"),
+            }
+        }
+    }
+}
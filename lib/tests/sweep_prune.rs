@@ -0,0 +1,209 @@
+extern crate mazth;
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use std::collections::HashSet;
+
+use self::mazth::{
+    bound::AxisAlignedBBox, bound_sphere::BoundSphere, i_bound::IBound, i_shape::ShapeType,
+};
+use implement::sweep_prune::{OverlapEvent, SweepPrune};
+use interface::i_spatial_accel::ISpatialAccel;
+
+fn brute_force_pairs(v: &[Option<AxisAlignedBBox>]) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    for i in 0..v.len() {
+        for j in i + 1..v.len() {
+            if let (Some(a), Some(b)) = (&v[i], &v[j]) {
+                if a.intersect(b) {
+                    out.push((i, j));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn apply_events(pairs: &mut HashSet<(usize, usize)>, events: Vec<OverlapEvent>) {
+    for e in events {
+        match e {
+            OverlapEvent::Begin(a, b) => assert!(pairs.insert((a, b))),
+            OverlapEvent::End(a, b) => assert!(pairs.remove(&(a, b))),
+        }
+    }
+}
+
+#[test]
+fn test_sweep_prune_unsupported_bounds() {
+    let mut a = SweepPrune::init();
+    let b = BoundSphere::init(ShapeType::Sphere, &[0f64, 0f64, 0f64, 5f64]);
+    let objs = [(0u64, &b as &dyn IBound)];
+    match a.build_all(&objs[..]) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    match a.insert(0u64, &b) {
+        Err(_) => (),
+        _ => {
+            panic!("unexpected result for unsupported bound type");
+        }
+    }
+    let inverted = AxisAlignedBBox {
+        _bound_lower: [0., 1., 0.],
+        _bound_upper: [1., 0., 1.],
+    };
+    assert!(a.insert(0u64, &inverted).is_err());
+    let objs = [(0u64, &inverted as &dyn IBound)];
+    assert!(a.build_all(&objs[..]).is_err());
+    assert!(a.is_empty());
+}
+
+#[test]
+fn test_sweep_prune_query_spanning() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut a = SweepPrune::init();
+    let mut v = vec![];
+    for i in 0..200 {
+        let x = rng.gen_range(0., 100.);
+        let b = AxisAlignedBBox::init(ShapeType::Rect, &[x, 0., 0., x + 1., 1., 1.]);
+        assert_eq!(a.insert(i as u64, &b), Ok(i));
+        v.push(b);
+    }
+    //starts far before any query and ends after it
+    let wide = AxisAlignedBBox::init(ShapeType::Rect, &[-50., 0., 0., 150., 1., 1.]);
+    a.insert(200u64, &wide).expect("insert unexpected result");
+    v.push(wide);
+    for _ in 0..50 {
+        let x = rng.gen_range(0., 100.);
+        let q = AxisAlignedBBox::init(ShapeType::Rect, &[x, 0.5, 0.5, x + 2., 2., 2.]);
+        let mut o = a.query_intersect(&q).expect("query unexpected result");
+        o.sort();
+        let expect = (0..v.len() as u64)
+            .filter(|i| v[*i as usize].intersect(&q))
+            .collect::<Vec<_>>();
+        assert_eq!(o, expect);
+        assert!(o.contains(&200));
+    }
+}
+
+#[test]
+fn test_sweep_prune_events() {
+    let mut a = SweepPrune::init();
+    let b0 = AxisAlignedBBox::init(ShapeType::Box, &[0., 0., 0., 2.]);
+    let b1 = AxisAlignedBBox::init(ShapeType::Box, &[10., 0., 0., 2.]);
+    let h0 = a.insert(0u64, &b0).unwrap();
+    let h1 = a.insert(1u64, &b1).unwrap();
+    assert!(a.take_events().is_empty());
+
+    //move into contact
+    let b1 = AxisAlignedBBox::init(ShapeType::Box, &[1.5, 0.5, 0., 2.]);
+    a.update(h1, &b1).expect("update unexpected result");
+    assert_eq!(a.take_events(), vec![OverlapEvent::Begin(h0, h1)]);
+    assert_eq!(a.get_pairs(), vec![(h0, h1)]);
+
+    //separate on y only
+    let b1 = AxisAlignedBBox::init(ShapeType::Box, &[1.5, 5., 0., 2.]);
+    a.update(h1, &b1).expect("update unexpected result");
+    assert_eq!(a.take_events(), vec![OverlapEvent::End(h0, h1)]);
+
+    //insertion on top of existing and removal
+    let h2 = a.insert(2u64, &b0).unwrap();
+    assert_eq!(a.take_events(), vec![OverlapEvent::Begin(h0, h2)]);
+    assert_eq!(a.get(h2), Some(&2u64));
+    let query = AxisAlignedBBox::init(ShapeType::Point, &[0., 0., 0.]);
+    match a.query_intersect(&query) {
+        Ok(mut o) => {
+            o.sort();
+            assert_eq!(o, vec![0, 2]);
+        }
+        _ => panic!("query unexpected result"),
+    }
+    assert_eq!(a.remove(h0), Ok(0));
+    assert_eq!(a.take_events(), vec![OverlapEvent::End(h0, h2)]);
+    assert!(a.get_pairs().is_empty());
+    assert_eq!(a.len(), 2);
+    match a.update(h0, &b0) {
+        Err(_) => (),
+        _ => panic!("update of stale handle unexpected result"),
+    }
+}
+
+#[test]
+fn test_sweep_prune_random_frames() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut a = SweepPrune::init();
+    let mut v = (0..300)
+        .map(|_| {
+            Some(AxisAlignedBBox::init(
+                ShapeType::Box,
+                &[
+                    rng.gen_range(0., 50.),
+                    rng.gen_range(0., 50.),
+                    rng.gen_range(0., 50.),
+                    rng.gen_range(0.5, 3.),
+                ],
+            ))
+        })
+        .collect::<Vec<_>>();
+    let objs = v
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (i, x.as_ref().unwrap() as &dyn IBound))
+        .collect::<Vec<_>>();
+    match a.build_all(&objs[..]) {
+        Ok(()) => (),
+        _ => {
+            panic!("unexpected result for supported bound type");
+        }
+    }
+    let mut pairs = HashSet::new();
+    apply_events(&mut pairs, a.take_events());
+    assert_eq!(a.get_pairs(), brute_force_pairs(&v[..]));
+
+    let mut free = vec![];
+    for _frame in 0..30 {
+        for (h, x) in v.iter_mut().enumerate() {
+            let b = match *x {
+                Some(ref b) => b.clone(),
+                None => continue,
+            };
+            let c = b.get_centroid();
+            let r = (b._bound_upper[0] - b._bound_lower[0]) / 2.;
+            let nb = AxisAlignedBBox::init(
+                ShapeType::Box,
+                &[
+                    c[0] + rng.gen_range(-1., 1.),
+                    c[1] + rng.gen_range(-1., 1.),
+                    c[2] + rng.gen_range(-1., 1.),
+                    r,
+                ],
+            );
+            a.update(h, &nb).expect("update unexpected result");
+            *x = Some(nb);
+        }
+        //some churn
+        for _ in 0..5 {
+            let h = rng.gen_range(0, v.len());
+            if v[h].is_some() {
+                assert_eq!(a.remove(h), Ok(h));
+                v[h] = None;
+                free.push(h);
+            } else {
+                let h = free.pop().unwrap();
+                let b = AxisAlignedBBox::init(ShapeType::Box, &[25., 25., 25., 2.]);
+                assert_eq!(a.insert(h, &b), Ok(h));
+                v[h] = Some(b);
+            }
+        }
+        let expect = brute_force_pairs(&v[..]);
+        assert_eq!(a.get_pairs(), expect);
+        apply_events(&mut pairs, a.take_events());
+        let mut p = pairs.iter().cloned().collect::<Vec<_>>();
+        p.sort();
+        assert_eq!(p, expect);
+    }
+}
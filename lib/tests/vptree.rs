@@ -0,0 +1,97 @@
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Rng, SeedableRng};
+
+use implement::vptree::{MetricL1, MetricL2, VpTree};
+use interface::i_metric::IMetric;
+
+#[test]
+fn test_vptree_invalid_metric() {
+    let mut a = VpTree::init(|_a: &f64, _b: &f64| f64::NAN);
+    match a.build(&[(0u32, 1.), (1u32, 2.)]) {
+        Err(_) => (),
+        _ => panic!("unexpected result for invalid metric"),
+    }
+    let mut a: VpTree<f64, u32, _> = VpTree::init(|x: &f64, y: &f64| (x - y).abs());
+    a.build(&[]).expect("build unexpected result");
+    assert!(a.is_empty());
+    assert!(a.query_nearest(&0., 3).is_empty());
+    assert!(a.query_radius(&0., 3.).is_empty());
+}
+
+#[test]
+fn test_vptree_hamming() {
+    //bit strings under hamming distance
+    let hamming = |a: &u64, b: &u64| f64::from((a ^ b).count_ones());
+    let items = (0..256u64).map(|x| (x, x)).collect::<Vec<_>>();
+    let mut a = VpTree::init(hamming);
+    a.build(&items[..]).expect("build unexpected result");
+    assert_eq!(a.len(), 256);
+
+    let o = a.query_radius(&0b1010_1010, 1.);
+    let mut ids = o.iter().map(|x| x.0).collect::<Vec<_>>();
+    ids.sort();
+    let mut expect = vec![0b1010_1010];
+    for i in 0..8 {
+        expect.push(0b1010_1010 ^ (1 << i));
+    }
+    expect.sort();
+    assert_eq!(ids, expect);
+    assert_eq!(o[0], (0b1010_1010, 0.));
+
+    let o = a.query_nearest(&0b1_0000_0000, 1);
+    assert_eq!(o, vec![(0, 1.)]);
+}
+
+fn check_brute_force<M>(
+    tree: &VpTree<Vec<f64>, u32, M>,
+    metric: &M,
+    items: &[(u32, Vec<f64>)],
+    p: &Vec<f64>,
+) where
+    M: IMetric<Vec<f64>>,
+{
+    let mut expect = items
+        .iter()
+        .map(|x| (metric.distance(p, &x.1), x.0))
+        .collect::<Vec<_>>();
+    expect.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let o = tree.query_nearest(p, 7);
+    assert_eq!(o.len(), 7);
+    for (i, j) in o.iter().zip(expect.iter()) {
+        assert!((i.1 - j.0).abs() < 1e-9);
+    }
+
+    let r = expect[40].0;
+    let o = tree.query_radius(p, r);
+    let e = expect.iter().filter(|x| x.0 <= r).collect::<Vec<_>>();
+    assert_eq!(o.len(), e.len());
+    for (i, j) in o.iter().zip(e.iter()) {
+        assert!((i.1 - j.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_vptree_random_brute_force() {
+    let mut rng = StdRng::seed_from_u64(23);
+    let items = (0..2000u32)
+        .map(|i| {
+            (
+                i,
+                (0..8).map(|_| rng.gen_range(-1., 1.)).collect::<Vec<f64>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut l1 = VpTree::init(MetricL1);
+    l1.build(&items[..]).expect("build unexpected result");
+    let mut l2 = VpTree::init(MetricL2);
+    l2.build(&items[..]).expect("build unexpected result");
+
+    for _ in 0..30 {
+        let p = (0..8).map(|_| rng.gen_range(-1., 1.)).collect::<Vec<f64>>();
+        check_brute_force(&l1, &MetricL1, &items[..], &p);
+        check_brute_force(&l2, &MetricL2, &items[..], &p);
+    }
+}